@@ -3,30 +3,68 @@ use std::sync::Mutex;
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::app::TOASTS;
+use crate::app::{SCHEMA_CHANNEL, TOASTS, TOKIO_RT};
 use crate::component::debug_window::EguiWindows;
+use crate::component::header_ui::SelectKeyValueItem;
 use crate::component::tree::{DocType, TreeNode, TreeView};
 use crate::component::tree_ui::{TreeUi, self};
 use crate::history_db::{get_history_list, get_apitest};
-use crate::ui::request_ui::{CollectionUi, LoadTestDiagram, LoadTestUi, ScriptUi};
-use crate::utils::rhai_script::SCRIPT_ENGINE;
-use crate::utils::template::{add_global_var, TMP_SCOPE_CTX};
+use crate::ui::request_ui::{
+    AuthUi, ClientSettingsUi, CodeGenUi, CollectionUi, LoadTestDiagram, LoadTestUi, ScriptUi,
+};
+use crate::utils::auth::AuthData;
+use crate::ui::environment_ui::EnvironmentUi;
+use crate::ui::extractor_ui::ExtractorUi;
+use crate::ui::test_ui::TestUi;
+use crate::ui::variable_ui::{VarAction, VariableUi};
+use crate::ui::websocket_ui::{WebSocketUi, WsAction};
+use crate::utils::client::{client_for, ClientSettings, ResolvedClientSettings};
+use crate::utils::codegen::{CodeLang, Snippet};
+use crate::utils::debugger::{DebugSession, DebugStep};
+use crate::utils::curl::{parse_curl, to_curl};
+use crate::utils::environment::{
+    env_values, inspect_vars, mask_secrets, push_env_vars, Environments, VarSource,
+};
+use crate::utils::extractor::{run_extractors, ExtractScope, Extractor};
+use crate::utils::har::{export_har, parse_har};
+use crate::utils::http_file::{export_http, import_http};
+use crate::utils::import::{
+    import_collection, merge_request, script_vars, ImportNode, ImportResult,
+};
+use crate::utils::openapi::{export_openapi, SpecInfo, SpecOperation};
+use crate::utils::graphql::{introspect, GraphQLSchema};
+use crate::utils::websocket::{self, WebSocketData};
+use crate::utils::rhai_script::{script_offline, ScriptLimit, SCRIPT_ENGINE};
+use crate::utils::script_data::{request_map, response_map};
+use crate::utils::script_http::{self, SavedRequest};
+use crate::utils::template::{
+    add_global_var, remove_global_value, set_scope_var, TMP_SCOPE_CTX,
+};
 use crate::{
-    request_data::{LoadTestData, RequestData, ResponseData, ScriptData},
+    request_data::{BodyType, LoadTestData, RequestData, ResponseData, ScriptData},
     ui::request_ui::{RequestUi, ResponseUi},
 };
-use egui::{Id, WidgetText};
+use egui::{RichText, WidgetText};
 use egui_dock::TabViewer;
+use egui_file::FileDialog;
 use egui_notify::Toasts;
 use log::info;
 use minijinja::value::Value;
-use rhai::{EvalAltResult, Scope};
+use rhai::{Dynamic, EvalAltResult, Scope};
+
+///执行顺序里每段脚本预览的行数
+const SCRIPT_PREVIEW_LINES: usize = 3;
 
 #[derive(serde::Deserialize, serde::Serialize)]
 pub struct ApiContext {
     pub selected: Vec<u64>,
     pub tests: BTreeMap<u64, ApiTester>,
     pub collections: BTreeMap<u64, CollectionsData>,
+    #[serde(default)]
+    pub websockets: BTreeMap<u64, WebSocketData>,
+    ///全局的客户端设置
+    #[serde(default = "ClientSettings::global_default")]
+    pub client_settings: ClientSettings,
     debug_window: EguiWindows,
     tree_ui: TreeUi,
     tree_view: TreeView,
@@ -36,6 +74,46 @@ pub struct ApiContext {
     col_ui: CollectionUi,
     #[serde(skip)]
     script_ui: ScriptUi,
+    ///导入curl弹框里的命令,None时不显示
+    #[serde(skip)]
+    curl_import: Option<String>,
+    #[serde(skip)]
+    code_ui: CodeGenUi,
+    #[serde(skip)]
+    file_dialog: Option<(FileAction, FileDialog)>,
+    ///导入后没能转换的内容,None时不显示
+    #[serde(skip)]
+    import_warnings: Option<Vec<String>>,
+    ///dev、test、prod等环境和全局变量
+    #[serde(default)]
+    pub environments: Environments,
+    #[serde(skip)]
+    env_ui: EnvironmentUi,
+    #[serde(skip)]
+    env_window: bool,
+    #[serde(skip)]
+    var_ui: VariableUi,
+    ///正在调试的脚本,同时只有一个
+    #[serde(skip)]
+    debugger: Option<DebugSession>,
+    ///导出时是否带上加密变量和认证信息
+    #[serde(skip)]
+    pub export_secrets: bool,
+}
+
+///导航里选择文件后要做的事
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FileAction {
+    ImportHar,
+    ///Postman、Insomnia的导出文件或OpenAPI文档
+    ImportCollection,
+    ///导出集合下所有请求最后一次的结果
+    ExportHar(u64),
+    ///按扩展名导出成JSON或YAML
+    ExportOpenApi(u64),
+    ///`.http`或`.rest`文件,一个文件一个集合
+    ImportHttp,
+    ExportHttp(u64),
 }
 impl TabViewer for ApiContext {
     type Tab = String;
@@ -49,13 +127,67 @@ impl TabViewer for ApiContext {
         let selected = *self.selected.first().unwrap_or(&0);
         match tab.as_str() {
             "请求" => {
+                let schema_collection = self.parent_collection(selected);
+                self.req_ui.set_graphql_schema(
+                    self.collections
+                        .get(&schema_collection)
+                        .and_then(|c| c.graphql_schema.as_ref()),
+                );
                 if let Some(req_data) = self.tests.get_mut(&selected) {
                     self.req_ui.ui(ui, &mut req_data.req, selected);
                 }
+                if std::mem::take(&mut self.req_ui.introspect) {
+                    self.fetch_graphql_schema(selected);
+                }
+                if std::mem::take(&mut self.req_ui.copy_curl) {
+                    match self.copy_as_curl(selected) {
+                        Ok(curl) => {
+                            ui.ctx().output_mut(|o| o.copied_text = curl);
+                            if let Ok(mut toast_w) = toast.lock() {
+                                toast_w
+                                    .info("已复制curl命令")
+                                    .set_duration(Some(Duration::from_secs(3)));
+                            }
+                        }
+                        Err(e) => {
+                            if let Ok(mut toast_w) = toast.lock() {
+                                toast_w
+                                    .error(format!("生成curl失败:{}", e))
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                        }
+                    }
+                }
+                if let Some(ws_data) = self.websockets.get_mut(&selected) {
+                    match WebSocketUi::ui(ui, ws_data, selected) {
+                        WsAction::Keep => {}
+                        WsAction::Connect => {
+                            let ws_data = &self.websockets[&selected];
+                            let result = client_for(&self.resolve_settings(selected))
+                                .and_then(|client| websocket::connect(selected, client, ws_data));
+                            if let Err(e) = result {
+                                if let Ok(mut toast_w) = toast.lock() {
+                                    toast_w
+                                        .error(format!("连接失败:{}", e))
+                                        .set_duration(Some(Duration::from_secs(5)));
+                                }
+                            }
+                        }
+                        WsAction::Disconnect => websocket::disconnect(selected),
+                    }
+                }
             }
             "响应" => {
                 if let Some(req_data) = self.tests.get_mut(&selected) {
-                    ResponseUi::ui(ui, &mut req_data.resp, selected);
+                    let graphql = req_data.req.body_type == BodyType::GraphQL;
+                    ResponseUi::ui(ui, &mut req_data.resp, selected, graphql);
+                } else {
+                    ui.label("没有数据");
+                }
+            }
+            "测试" => {
+                if let Some(req_data) = self.tests.get(&selected) {
+                    TestUi::ui(ui, &req_data.resp.tests);
                 } else {
                     ui.label("没有数据");
                 }
@@ -77,18 +209,103 @@ impl TabViewer for ApiContext {
                 if let Some(req_data) = self.tests.get_mut(&selected) {
                     self.col_ui.ui(ui, &mut req_data.req.remark, selected);
                 }
+                if let Some(ws_data) = self.websockets.get_mut(&selected) {
+                    self.col_ui.ui(ui, &mut ws_data.remark, selected);
+                }
             }
             "运行日志" => {
                 // egui_logger::logger_ui(ui);
             }
+            "认证" => {
+                if let Some(req_data) = self.tests.get_mut(&selected) {
+                    AuthUi::ui(ui, &mut req_data.req.auth, selected);
+                }
+                if let Some(collect_data) = self.collections.get_mut(&selected) {
+                    AuthUi::ui(ui, &mut collect_data.auth, selected);
+                }
+            }
+            "变量" => {
+                let entries = inspect_vars();
+                let action = self.var_ui.ui(ui, &entries);
+                self.apply_var_action(action);
+            }
+            "提取变量" => {
+                let collection_id = self.parent_collection(selected);
+                if let Some(req_data) = self.tests.get_mut(&selected) {
+                    let collection = self.collections.get_mut(&collection_id);
+                    ExtractorUi::ui(
+                        ui,
+                        &mut req_data.extractors,
+                        &mut req_data.extracted,
+                        collection.map(|c| &mut c.extracted),
+                    );
+                } else {
+                    ui.label("没有数据");
+                }
+            }
+            "生成代码" => {
+                if self.tests.contains_key(&selected) {
+                    if self.code_ui.ui(ui, selected) {
+                        let code = self
+                            .generate_code(selected, self.code_ui.lang, self.code_ui.render)
+                            .unwrap_or_else(|e| format!("生成失败:{}", e));
+                        self.code_ui.set_code(code);
+                    }
+                } else {
+                    ui.label("没有数据");
+                }
+            }
+            "客户端设置" => {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.collapsing("全局", |ui| {
+                        ClientSettingsUi::ui(ui, &mut self.client_settings, "global");
+                    });
+                    if let Some(collect_data) = self.collections.get_mut(&selected) {
+                        ui.collapsing("当前集合", |ui| {
+                            ClientSettingsUi::ui(ui, &mut collect_data.settings, "collection");
+                        });
+                    }
+                    if let Some(req_data) = self.tests.get_mut(&selected) {
+                        ui.collapsing("当前请求", |ui| {
+                            ClientSettingsUi::ui(ui, &mut req_data.req.settings, "request");
+                        });
+                    }
+                });
+            }
             "后置脚本" => {
+                if self.tests.contains_key(&selected) || self.collections.contains_key(&selected) {
+                    self.script_chain_ui(ui, selected, true);
+                }
+                if let Some(collect_data) = self.collections.get_mut(&selected) {
+                    //每个响应到达后从外到内执行
+                    self.script_ui.ui(ui, &mut collect_data.after, selected);
+                }
                 if let Some(req_data) = self.tests.get_mut(&selected) {
-                    self.script_ui.ui(ui, &mut req_data.script.after, selected);
+                    let after = &mut req_data.script.after;
+                    if self
+                        .script_ui
+                        .debug_ui(ui, after, selected, true, &mut self.debugger)
+                    {
+                        self.start_debug(selected, true);
+                    }
+                }
+                if let Some(ws_data) = self.websockets.get_mut(&selected) {
+                    //收到每条消息时执行
+                    self.script_ui.ui(ui, &mut ws_data.script, selected);
                 }
             }
             "前置脚本" => {
+                if self.tests.contains_key(&selected) || self.collections.contains_key(&selected) {
+                    self.script_chain_ui(ui, selected, false);
+                }
                 if let Some(req_data) = self.tests.get_mut(&selected) {
-                    self.script_ui.ui(ui, &mut req_data.script.pre, selected);
+                    let pre = &mut req_data.script.pre;
+                    if self
+                        .script_ui
+                        .debug_ui(ui, pre, selected, false, &mut self.debugger)
+                    {
+                        self.start_debug(selected, false);
+                    }
                 }
                 if let Some(collect_data) = self.collections.get_mut(&selected) {
                     self.script_ui.ui(ui, &mut collect_data.script, selected);
@@ -111,6 +328,35 @@ impl TabViewer for ApiContext {
                 });
             }
             "导航" => {
+                ui.horizontal(|ui| {
+                    if ui.small_button("导入curl").clicked() {
+                        self.curl_import = Some(String::new());
+                    }
+                    if ui.small_button("导入HAR").clicked() {
+                        self.open_file_dialog(FileAction::ImportHar);
+                    }
+                    if ui.small_button("导入Postman/Insomnia/OpenAPI").clicked() {
+                        self.open_file_dialog(FileAction::ImportCollection);
+                    }
+                    if ui.small_button("导入.http").clicked() {
+                        self.open_file_dialog(FileAction::ImportHttp);
+                    }
+                    if ui.small_button("导出HAR").clicked() {
+                        let collection = self.selected_collection_path()[0];
+                        self.open_file_dialog(FileAction::ExportHar(collection));
+                    }
+                    if ui.small_button("导出OpenAPI").clicked() {
+                        let collection = self.selected_collection_path()[0];
+                        self.open_file_dialog(FileAction::ExportOpenApi(collection));
+                    }
+                    if ui.small_button("导出.http").clicked() {
+                        let collection = self.selected_collection_path()[0];
+                        self.open_file_dialog(FileAction::ExportHttp(collection));
+                    }
+                });
+                self.curl_import_window(ui.ctx());
+                self.file_dialog_ui(ui.ctx());
+                self.import_warnings_window(ui.ctx());
                 egui::ScrollArea::both().show(ui, |ui| {
                     // ui.with_layout(Layout::top_down(egui::Align::LEFT), |ui|{
                     match self.tree_ui.ui_impl(ui) {
@@ -122,6 +368,9 @@ impl TabViewer for ApiContext {
                                 info!("删除{}", del_id);
                                 self.delete_collecton(del_id);
                                 self.delete_test(del_id);
+                                if self.websockets.remove(&del_id).is_some() {
+                                    websocket::disconnect(del_id);
+                                }
                             }
                         }
                         tree_ui::Action::Add((adds, node_type)) => {
@@ -134,6 +383,9 @@ impl TabViewer for ApiContext {
                                 tree_ui::NodeType::Node => {
                                     self.insert_test(add_id, ApiTester::default());
                                 }
+                                tree_ui::NodeType::WebSocket => {
+                                    self.websockets.insert(add_id, WebSocketData::default());
+                                }
                             }
                         }
                         tree_ui::Action::Rename(_adds) => {
@@ -256,29 +508,51 @@ impl ApiContext {
             tree_ui: TreeUi::new(),
             debug_window:EguiWindows::default(),
             collections: BTreeMap::new(),
+            websockets: BTreeMap::new(),
+            client_settings: ClientSettings::global_default(),
             req_ui: RequestUi::default(),
             selected: vec![0],
             col_ui: CollectionUi::default(),
             script_ui: ScriptUi::default(),
+            curl_import: None,
+            code_ui: CodeGenUi::default(),
+            file_dialog: None,
+            import_warnings: None,
+            environments: Environments::default(),
+            env_ui: EnvironmentUi::default(),
+            env_window: false,
+            var_ui: VariableUi::default(),
+            debugger: None,
+            export_secrets: false,
         }
     }
 
     pub fn run_script(&self) -> Result<(), Box<EvalAltResult>> {
-        let mut parents = self.selected.clone();
+        self.publish_script_requests();
+        //所有前置脚本加起来不能超过SCRIPT_BUDGET
+        let _limit = ScriptLimit::start(script_offline());
         let script_scope = &mut Scope::new();
-        let cid = parents.remove(0);
-        while let Some(pid) = parents.pop() {
-            if let Some(pdata) = self.collections.get(&pid) {
-                let _res = SCRIPT_ENGINE.run_with_scope(script_scope, &pdata.script)?;
+        push_env_vars(script_scope);
+        let cid = *self.selected.first().unwrap_or(&0);
+        for (node_id, script) in self.script_chain(cid, false) {
+            if let Some(pdata) = self.collections.get(&node_id) {
+                push_extracted(script_scope, &pdata.extracted);
+            } else if let Some(aip) = self.tests.get(&node_id) {
+                push_extracted(script_scope, &aip.extracted);
             }
-        }
-
-        if let Some(aip) = self.tests.get(&cid) {
-            SCRIPT_ENGINE.run_with_scope(script_scope, &aip.script.pre)?;
+            SCRIPT_ENGINE.run_with_scope(script_scope, &script)?;
         }
         let mut script_ctx: HashMap<String, Value> = HashMap::new();
+        let env_vars = env_values();
 
         for (name, _is_constant, value) in script_scope.iter() {
+            //没改过的环境变量和加密变量不是脚本变量,加密变量的明文也不能留在上下文里
+            let unchanged = value.clone().into_string().map_or(false, |text| {
+                env_vars.iter().any(|(key, env)| key == name && env == &text)
+            });
+            if unchanged {
+                continue;
+            }
             // let temp_value = serde_json::to_string(&value).unwrap();
             if value.is_array()
                 || value.is_bool()
@@ -302,6 +576,885 @@ impl ApiContext {
         Ok(())
     }
 
+    ///从外到内的集合脚本,最后是请求自己的,after为true时取后置脚本
+    pub fn script_chain(&self, id: u64, after: bool) -> Vec<(u64, String)> {
+        let mut chain: Vec<(u64, String)> = self
+            .node_path(id)
+            .iter()
+            .rev()
+            .filter_map(|pid| {
+                let collection = self.collections.get(pid)?;
+                let script = if after {
+                    &collection.after
+                } else {
+                    &collection.script
+                };
+                Some((*pid, script.clone()))
+            })
+            .collect();
+        if let Some(test) = self.tests.get(&id) {
+            let script = if after {
+                &test.script.after
+            } else {
+                &test.script.pre
+            };
+            chain.push((id, script.clone()));
+        }
+        chain
+    }
+
+    ///脚本的执行顺序,空脚本也列出来
+    fn script_chain_ui(&self, ui: &mut egui::Ui, id: u64, after: bool) {
+        let chain = self.script_chain(id, after);
+        ui.collapsing(format!("执行顺序({})", chain.len()), |ui| {
+            for (i, (node_id, script)) in chain.iter().enumerate() {
+                let title = self
+                    .tree_ui
+                    .title(*node_id)
+                    .unwrap_or_else(|| format!("#{}", node_id));
+                let lines = script.lines().filter(|l| !l.trim().is_empty()).count();
+                let size = if lines == 0 {
+                    "空".to_owned()
+                } else {
+                    format!("{}行", lines)
+                };
+                let current = if *node_id == id { "(当前)" } else { "" };
+                ui.label(format!("{}. {}{} - {}", i + 1, title, current, size));
+                //预览前几行,看得出每一步做了什么
+                let preview: Vec<&str> = script
+                    .lines()
+                    .filter(|l| !l.trim().is_empty())
+                    .take(SCRIPT_PREVIEW_LINES)
+                    .collect();
+                if !preview.is_empty() {
+                    ui.indent(("script_preview", *node_id), |ui| {
+                        let mut text = preview.join("\n");
+                        if lines > SCRIPT_PREVIEW_LINES {
+                            text.push_str("\n...");
+                        }
+                        ui.label(RichText::new(text).monospace().weak());
+                    });
+                }
+            }
+        });
+    }
+
+    ///调试请求的前置或后置脚本,集合的脚本先直接执行,变量和正常执行时一样
+    fn start_debug(&mut self, id: u64, after: bool) {
+        self.publish_script_requests();
+        let mut scope = Scope::new();
+        push_env_vars(&mut scope);
+        let mut steps: Vec<DebugStep> = self
+            .script_chain(id, after)
+            .into_iter()
+            .map(|(node_id, script)| {
+                let extracted = self
+                    .collections
+                    .get(&node_id)
+                    .map(|c| &c.extracted)
+                    .or_else(|| self.tests.get(&node_id).map(|t| &t.extracted));
+                let vars = match extracted {
+                    Some(extracted) if !after => extracted
+                        .iter()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                DebugStep { vars, script }
+            })
+            .collect();
+        let Some(step) = steps.pop() else {
+            return;
+        };
+        let mut resp = None;
+        if after {
+            if let Some(test) = self.tests.get(&id) {
+                push_response_vars(&mut scope, test);
+                resp = Some(test.resp.clone());
+            }
+        }
+        let breakpoints = self.script_ui.breakpoints(id, after);
+        self.debugger = Some(DebugSession::start(
+            (id, after),
+            scope,
+            steps,
+            step,
+            breakpoints,
+            resp,
+        ));
+    }
+
+    ///调试线程在后台执行,运行中时定时刷新界面
+    pub fn poll_debugger(&mut self, ctx: &egui::Context) {
+        let Some(session) = self.debugger.as_mut() else {
+            return;
+        };
+        if session.poll() {
+            ctx.request_repaint();
+        }
+        if !session.is_finished() {
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+    }
+
+    ///脚本里send(id)用的请求快照,执行脚本前更新
+    pub fn publish_script_requests(&self) {
+        let requests = self
+            .tests
+            .iter()
+            .map(|(id, test)| {
+                let mut req = test.req.clone();
+                req.auth = self.resolve_auth(*id);
+                let settings = self.resolve_settings(*id);
+                (*id, SavedRequest { req, settings })
+            })
+            .collect();
+        script_http::publish(self.client_settings.resolve(), requests);
+    }
+
+    ///响应到达后执行提取器,提取到的值马上放进模板上下文,失败的原因记到响应里
+    pub fn run_extractors(&mut self, id: u64) {
+        let collection_id = self.parent_collection(id);
+        let has_collection = self.collections.contains_key(&collection_id);
+        //脚本上下文是给当前选中的请求渲染用的,只放它看得到的值
+        let selected = *self.selected.first().unwrap_or(&0);
+        let selected_path = self.node_path(selected);
+        let Some(test) = self.tests.get_mut(&id) else {
+            return;
+        };
+        let (values, errors) = run_extractors(&test.resp, &test.extractors);
+        test.resp.extract_errors = errors;
+        let mut env_changed = false;
+        for (scope, name, value) in values {
+            match scope {
+                ExtractScope::Collection if has_collection => {
+                    if selected_path.contains(&collection_id) {
+                        set_scope_var(&name, Some(Value::from(value.clone())));
+                    }
+                    if let Some(collection) = self.collections.get_mut(&collection_id) {
+                        collection.extracted.insert(name, value);
+                    }
+                }
+                //不在集合里的请求,集合变量退回到请求自己
+                ExtractScope::Request | ExtractScope::Collection => {
+                    if selected == id {
+                        set_scope_var(&name, Some(Value::from(value.clone())));
+                    }
+                    test.extracted.insert(name, value);
+                }
+                //环境变量由apply统一生效
+                ExtractScope::Environment => {
+                    self.environments.set_var(&name, &value);
+                    env_changed = true;
+                }
+            }
+        }
+        if env_changed {
+            self.environments.apply();
+        }
+    }
+
+    ///节点到根的id路径,自己在前
+    pub fn node_path(&self, id: u64) -> Vec<u64> {
+        self.tree_ui.node_path(id)
+    }
+
+    ///请求实际使用的认证,继承时由内向外找第一个设置了认证的集合
+    pub fn resolve_auth(&self, id: u64) -> AuthData {
+        if let Some(test) = self.tests.get(&id) {
+            if !test.req.auth.is_inherit() {
+                return test.req.auth.clone();
+            }
+        }
+        for pid in self.node_path(id).iter().skip(1) {
+            if let Some(collection) = self.collections.get(pid) {
+                if !collection.auth.is_inherit() {
+                    return collection.auth.clone();
+                }
+            }
+        }
+        AuthData::default()
+    }
+
+    ///全局设置由外向内被集合和请求的设置覆盖
+    pub fn resolve_settings(&self, id: u64) -> ResolvedClientSettings {
+        let mut settings = self.client_settings.clone();
+        for pid in self.node_path(id).iter().skip(1).rev() {
+            if let Some(collection) = self.collections.get(pid) {
+                settings = settings.merge(&collection.settings);
+            }
+        }
+        if let Some(test) = self.tests.get(&id) {
+            settings = settings.merge(&test.req.settings);
+        }
+        settings.resolve()
+    }
+
+    ///请求所在的集合,GraphQL的Schema缓存在这里
+    pub fn parent_collection(&self, id: u64) -> u64 {
+        self.node_path(id).get(1).copied().unwrap_or(0)
+    }
+
+    ///后台发内省请求,结果从SCHEMA_CHANNEL回来
+    pub fn fetch_graphql_schema(&self, id: u64) {
+        let Some(test) = self.tests.get(&id) else {
+            return;
+        };
+        let mut req = test.req.clone();
+        req.auth = self.resolve_auth(id);
+        let collection_id = self.parent_collection(id);
+        let client = match client_for(&self.resolve_settings(id)) {
+            Ok(client) => client,
+            Err(e) => {
+                let _ = unsafe { SCHEMA_CHANNEL.0.try_send((collection_id, Err(e))) };
+                return;
+            }
+        };
+        TOKIO_RT.spawn(async move {
+            let result = introspect(&client, req).await;
+            let _ = unsafe { SCHEMA_CHANNEL.0.send((collection_id, result)).await };
+        });
+    }
+
+    ///生成渲染后的curl命令,会先执行前置脚本,脚本里的请求不会发出
+    pub fn copy_as_curl(&self, id: u64) -> Result<String, String> {
+        let test = self.tests.get(&id).ok_or_else(|| "没有选中请求".to_owned())?;
+        let _offline = ScriptLimit::start(true);
+        self.run_script().map_err(|e| e.to_string())?;
+        let mut req = test.req.clone();
+        req.auth = self.resolve_auth(id);
+        to_curl(&req, &self.resolve_settings(id))
+    }
+
+    ///生成代码,渲染模板时会先执行前置脚本
+    pub fn generate_code(&self, id: u64, lang: CodeLang, render: bool) -> Result<String, String> {
+        let test = self.tests.get(&id).ok_or_else(|| "没有选中请求".to_owned())?;
+        if render {
+            let _offline = ScriptLimit::start(true);
+            self.run_script().map_err(|e| e.to_string())?;
+        }
+        let mut req = test.req.clone();
+        req.auth = self.resolve_auth(id);
+        let code = Snippet::from_request(&req, render)?.generate(lang);
+        //渲染后的代码只用来预览,加密变量不显示明文
+        Ok(if render { mask_secrets(&code) } else { code })
+    }
+
+    ///选中的集合到根的路径,选中的是请求时取它所在的集合
+    fn selected_collection_path(&self) -> Vec<u64> {
+        let mut path = self.selected.clone();
+        if path
+            .first()
+            .is_some_and(|id| self.tests.contains_key(id) || self.websockets.contains_key(id))
+        {
+            path.remove(0);
+        }
+        if path.is_empty() {
+            path.push(0);
+        }
+        path
+    }
+
+    fn open_file_dialog(&mut self, action: FileAction) {
+        let mut dialog = match action {
+            FileAction::ImportHar => FileDialog::open_file(None)
+                .show_rename(false)
+                .show_files_filter(Box::new(|p| p.to_string_lossy().ends_with(".har"))),
+            FileAction::ImportCollection => FileDialog::open_file(None)
+                .show_rename(false)
+                .show_files_filter(Box::new(|p| {
+                    let p = p.to_string_lossy();
+                    p.ends_with(".json") || p.ends_with(".yaml") || p.ends_with(".yml")
+                })),
+            FileAction::ExportHar(_) => FileDialog::save_file(None).default_filename("export.har"),
+            FileAction::ImportHttp => FileDialog::open_file(None)
+                .show_rename(false)
+                .show_files_filter(Box::new(|p| {
+                    let p = p.to_string_lossy();
+                    p.ends_with(".http") || p.ends_with(".rest")
+                })),
+            FileAction::ExportHttp(_) => FileDialog::save_file(None).default_filename("requests.http"),
+            FileAction::ExportOpenApi(_) => {
+                FileDialog::save_file(None).default_filename("openapi.yaml")
+            }
+        };
+        dialog.open();
+        self.file_dialog = Some((action, dialog));
+    }
+
+    fn file_dialog_ui(&mut self, ctx: &egui::Context) {
+        let Some((action, dialog)) = &mut self.file_dialog else {
+            return;
+        };
+        if !dialog.show(ctx).selected() {
+            if !dialog.visible() {
+                self.file_dialog = None;
+            }
+            return;
+        }
+        let action = *action;
+        let path = dialog.path().map(|p| p.to_path_buf());
+        self.file_dialog = None;
+        let Some(path) = path else {
+            return;
+        };
+        let result = match action {
+            FileAction::ImportHar => self.import_har(&path),
+            FileAction::ImportCollection => std::fs::read_to_string(&path)
+                .map_err(|e| format!("读取文件失败:{}", e))
+                .and_then(|text| import_collection(&text))
+                .and_then(|result| self.apply_import(result)),
+            FileAction::ExportHar(id) => self.export_har(id, &path),
+            FileAction::ExportOpenApi(id) => self.export_openapi(id, &path),
+            FileAction::ImportHttp => std::fs::read_to_string(&path)
+                .map_err(|e| format!("读取文件失败:{}", e))
+                .and_then(|text| {
+                    let name = path
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().into_owned())
+                        .unwrap_or_else(|| "http".to_owned());
+                    self.apply_import(import_http(&name, &text))
+                }),
+            FileAction::ExportHttp(id) => self.export_http(id, &path),
+        };
+        if let Ok(mut toast_w) = TOASTS.get().unwrap().lock() {
+            match result {
+                Ok(msg) => {
+                    toast_w.info(msg).set_duration(Some(Duration::from_secs(3)));
+                }
+                Err(e) => {
+                    toast_w.error(e).set_duration(Some(Duration::from_secs(5)));
+                }
+            }
+        }
+    }
+
+    ///顶部菜单里的环境切换
+    pub fn environment_menu(&mut self, ui: &mut egui::Ui) {
+        let title = if self.environments.active.is_empty() {
+            "环境".to_owned()
+        } else {
+            format!("环境:{}", self.environments.active)
+        };
+        ui.menu_button(title, |ui| {
+            if EnvironmentUi::switcher(ui, &mut self.environments) {
+                self.environments.apply();
+                ui.close_menu();
+            }
+            ui.separator();
+            if ui.button("管理环境").clicked() {
+                self.env_window = true;
+                ui.close_menu();
+            }
+        });
+    }
+
+    pub fn environment_window(&mut self, ctx: &egui::Context) {
+        if !self.env_window {
+            return;
+        }
+        let mut open = true;
+        let mut changed = false;
+        egui::Window::new("管理环境")
+            .open(&mut open)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                changed = self.env_ui.editor(ui, &mut self.environments);
+            });
+        if changed {
+            self.environments.apply();
+        }
+        self.env_window = open;
+    }
+
+    ///变量查看器里的修改,环境变量改当前生效的那一项
+    fn apply_var_action(&mut self, action: VarAction) {
+        match action {
+            VarAction::Keep => {}
+            VarAction::Set(VarSource::Script, name, value) => {
+                set_scope_var(&name, Some(Value::from_serializable(&value)));
+            }
+            VarAction::Clear(VarSource::Script, name) => set_scope_var(&name, None),
+            VarAction::Set(VarSource::Global, name, value) => {
+                add_global_var(name, Value::from_serializable(&value));
+            }
+            VarAction::Clear(VarSource::Global, name) => remove_global_value(&name),
+            VarAction::Set(VarSource::Environment, name, value) => {
+                let value = match value {
+                    serde_json::Value::String(s) => s,
+                    other => other.to_string(),
+                };
+                if let Some(item) = self.env_var_mut(&name) {
+                    item.value = value;
+                }
+                self.environments.apply();
+            }
+            VarAction::Clear(VarSource::Environment, name) => {
+                let active = self.environments.active.clone();
+                let vars = match self.environments.envs.iter_mut().find(|e| e.name == active) {
+                    Some(env) if env.vars.iter().any(|v| v.key == name) => &mut env.vars,
+                    _ => &mut self.environments.globals,
+                };
+                vars.retain(|v| v.key != name);
+                self.environments.apply();
+            }
+            VarAction::Clear(VarSource::Secret, name) => {
+                let active = self.environments.active.clone();
+                let secrets = self
+                    .environments
+                    .envs
+                    .iter_mut()
+                    .filter(|e| e.name == active)
+                    .flat_map(|e| e.secrets.iter_mut())
+                    .chain(self.environments.secrets.iter_mut());
+                for secret in secrets.filter(|s| s.key == name) {
+                    secret.cipher.clear();
+                }
+                self.environments.apply();
+            }
+            //加密变量只能在管理环境里设置
+            VarAction::Set(VarSource::Secret, ..) => {}
+        }
+    }
+
+    ///生效的环境变量,当前环境里没有时找全局变量
+    fn env_var_mut(&mut self, name: &str) -> Option<&mut SelectKeyValueItem> {
+        let active = self.environments.active.clone();
+        let in_env = self
+            .environments
+            .envs
+            .iter()
+            .any(|e| e.name == active && e.vars.iter().any(|v| v.selected && v.key == name));
+        if in_env {
+            self.environments
+                .envs
+                .iter_mut()
+                .find(|e| e.name == active)?
+                .vars
+                .iter_mut()
+                .find(|v| v.selected && v.key == name)
+        } else {
+            self.environments
+                .globals
+                .iter_mut()
+                .find(|v| v.selected && v.key == name)
+        }
+    }
+
+    ///HAR导入成一个新集合,录制的响应作为初始响应
+    fn import_har(&mut self, path: &std::path::Path) -> Result<String, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("读取文件失败:{}", e))?;
+        let entries = parse_har(&text)?;
+        let title = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "HAR".to_owned());
+        let collection = self
+            .tree_ui
+            .add_with_title(vec![0], &title, tree_ui::NodeType::Collection)
+            .ok_or_else(|| "添加集合失败".to_owned())?;
+        self.insert_collecton(collection, CollectionsData::default());
+        let count = entries.len();
+        for (req, resp) in entries {
+            if let Some(id) = self.tree_ui.add_with_title(
+                vec![collection, 0],
+                &req.default_title(),
+                tree_ui::NodeType::Node,
+            ) {
+                self.insert_test(
+                    id,
+                    ApiTester {
+                        req,
+                        resp,
+                        ..Default::default()
+                    },
+                );
+            }
+        }
+        Ok(format!("已导入{}个请求", count))
+    }
+
+    ///导入的集合放在根下面,有没能转换的内容或文档里已删除的接口时弹框显示
+    fn apply_import(&mut self, result: ImportResult) -> Result<String, String> {
+        let count = result.root.request_count();
+        let keys: Vec<String> = result.root.keys().into_iter().map(str::to_owned).collect();
+        let root = self.add_import_node(vec![0], None, result.root)?;
+        let mut warnings = result.warnings;
+        warnings.extend(self.stale_imports(root, &keys));
+        if !warnings.is_empty() {
+            self.import_warnings = Some(warnings);
+        }
+        Ok(format!("已导入{}个请求", count))
+    }
+
+    ///parent是上级集合到根的路径,root是已导入的根集合,在它的整个子树里按来源找已有节点并更新,
+    ///手动移动过位置的节点留在原处
+    fn add_import_node(
+        &mut self,
+        parent: Vec<u64>,
+        root: Option<u64>,
+        node: ImportNode,
+    ) -> Result<u64, String> {
+        match node {
+            ImportNode::Collection {
+                key,
+                name,
+                doc,
+                script,
+                auth,
+                children,
+            } => {
+                let existing = self.find_imported(parent[0], root, &key, |id, ctx| {
+                    ctx.collections.get(&id).map(|c| c.source.as_str())
+                });
+                let id = match existing {
+                    //脚本和认证可能已经手动改过,只更新文档
+                    Some(id) => {
+                        self.tree_ui.set_title(id, &name);
+                        if let Some(collection) = self.collections.get_mut(&id) {
+                            collection.doc = doc;
+                        }
+                        id
+                    }
+                    None => {
+                        let id = self
+                            .tree_ui
+                            .add_with_title(parent.clone(), &name, tree_ui::NodeType::Collection)
+                            .ok_or_else(|| format!("添加集合{}失败", name))?;
+                        self.insert_collecton(
+                            id,
+                            CollectionsData {
+                                doc,
+                                script,
+                                auth,
+                                source: key,
+                                ..Default::default()
+                            },
+                        );
+                        id
+                    }
+                };
+                let mut path = self.node_path(id);
+                if path.last() != Some(&0) {
+                    path.push(0);
+                }
+                let root = root.or(Some(id));
+                for child in children {
+                    self.add_import_node(path.clone(), root, child)?;
+                }
+                Ok(id)
+            }
+            ImportNode::Request {
+                key,
+                name,
+                req,
+                script,
+            } => {
+                let existing = self.find_imported(parent[0], root, &key, |id, ctx| {
+                    ctx.tests.get(&id).map(|t| t.source.as_str())
+                });
+                if let Some(id) = existing {
+                    self.tree_ui.set_title(id, &name);
+                    if let Some(test) = self.tests.get_mut(&id) {
+                        test.req = merge_request(&test.req, req);
+                    }
+                    return Ok(id);
+                }
+                let id = self
+                    .tree_ui
+                    .add_with_title(parent, &name, tree_ui::NodeType::Node)
+                    .ok_or_else(|| format!("添加请求{}失败", name))?;
+                self.insert_test(
+                    id,
+                    ApiTester {
+                        req,
+                        script,
+                        source: key,
+                        ..Default::default()
+                    },
+                );
+                Ok(id)
+            }
+        }
+    }
+
+    ///按导入来源查找,有root时找它的整个子树,否则只找parent的直接子节点
+    fn find_imported(
+        &self,
+        parent: u64,
+        root: Option<u64>,
+        key: &str,
+        source: impl Fn(u64, &Self) -> Option<&str>,
+    ) -> Option<u64> {
+        if key.is_empty() {
+            return None;
+        }
+        let candidates = match root {
+            Some(root) => self.tree_ui.descendants(root),
+            None => self.tree_ui.children(parent),
+        };
+        candidates
+            .into_iter()
+            .find(|id| source(*id, self) == Some(key))
+    }
+
+    ///之前导入、这次文档里已经没有的节点,不自动删除,只提示
+    fn stale_imports(&self, root: u64, keys: &[String]) -> Vec<String> {
+        self.tree_ui
+            .descendants(root)
+            .into_iter()
+            .filter_map(|id| {
+                let source = self
+                    .collections
+                    .get(&id)
+                    .map(|c| &c.source)
+                    .or_else(|| self.tests.get(&id).map(|t| &t.source))?;
+                if source.is_empty() || keys.contains(source) {
+                    return None;
+                }
+                let title = self.tree_ui.title(id).unwrap_or_default();
+                Some(format!("{}({})已不在文档里,没有删除", title, source))
+            })
+            .collect()
+    }
+
+    fn import_warnings_window(&mut self, ctx: &egui::Context) {
+        let Some(warnings) = &self.import_warnings else {
+            return;
+        };
+        let mut open = true;
+        egui::Window::new("导入结果")
+            .open(&mut open)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                ui.label(format!("以下{}项需要手动处理:", warnings.len()));
+                egui::ScrollArea::vertical().max_height(360.).show(ui, |ui| {
+                    for warning in warnings {
+                        ui.label(warning);
+                    }
+                });
+            });
+        if !open {
+            self.import_warnings = None;
+        }
+    }
+
+    ///去掉请求和集合认证里直接写的凭证,返回原来的认证,序列化完用restore_credentials放回
+    pub fn take_credentials(&mut self) -> Vec<(u64, AuthData)> {
+        let tests = self.tests.iter_mut().map(|(id, t)| (*id, &mut t.req.auth));
+        let collections = self.collections.iter_mut().map(|(id, c)| (*id, &mut c.auth));
+        tests
+            .chain(collections)
+            .filter(|(_, auth)| auth.has_literal_credentials())
+            .map(|(id, auth)| {
+                let stripped = auth.without_credentials("");
+                (id, std::mem::replace(auth, stripped))
+            })
+            .collect()
+    }
+
+    pub fn restore_credentials(&mut self, taken: Vec<(u64, AuthData)>) {
+        for (id, auth) in taken {
+            if let Some(test) = self.tests.get_mut(&id) {
+                test.req.auth = auth;
+            } else if let Some(collection) = self.collections.get_mut(&id) {
+                collection.auth = auth;
+            }
+        }
+    }
+
+    ///菜单导出的工作区,没有勾选导出加密变量时去掉加密变量、主密码和认证里的明文凭证
+    pub fn export_value(&mut self) -> serde_json::Result<serde_json::Value> {
+        if self.export_secrets {
+            return serde_json::to_value(&*self);
+        }
+        let taken = self.take_credentials();
+        let result = serde_json::to_value(&*self).and_then(|mut value| {
+            value["environments"] = serde_json::to_value(self.environments.without_secrets())?;
+            Ok(value)
+        });
+        self.restore_credentials(taken);
+        result
+    }
+
+    ///没有勾选导出加密变量时,文件里出现的加密变量明文换成掩码
+    fn export_text(&self, text: String) -> String {
+        if self.export_secrets {
+            text
+        } else {
+            mask_secrets(&text)
+        }
+    }
+
+    ///导出集合下发送过的请求和最后一次的响应
+    fn export_har(&self, collection: u64, path: &std::path::Path) -> Result<String, String> {
+        let entries: Vec<(RequestData, ResponseData)> = self
+            .tree_ui
+            .descendants(collection)
+            .into_iter()
+            .filter_map(|id| {
+                let test = self.tests.get(&id).filter(|t| !t.resp.code.is_empty())?;
+                let mut req = test.req.clone();
+                req.auth = self.resolve_auth(id);
+                Some((req, test.resp.clone()))
+            })
+            .collect();
+        let pairs: Vec<(&RequestData, &ResponseData)> =
+            entries.iter().map(|e| (&e.0, &e.1)).collect();
+        let har = export_har(&pairs, !self.export_secrets);
+        let text = serde_json::to_string_pretty(&har).map_err(|e| e.to_string())?;
+        std::fs::write(path, self.export_text(text)).map_err(|e| format!("写入文件失败:{}", e))?;
+        Ok(format!("已导出{}个请求", entries.len()))
+    }
+
+    ///集合下所有请求按树的顺序写到一个文件,集合脚本里的变量写成`@var`
+    fn export_http(&self, collection: u64, path: &std::path::Path) -> Result<String, String> {
+        let vars: Vec<(String, String)> = self
+            .node_path(collection)
+            .iter()
+            .rev()
+            .filter_map(|id| self.collections.get(id))
+            .flat_map(|c| script_vars(&c.script))
+            .collect();
+        let requests: Vec<(String, RequestData)> = self
+            .tree_ui
+            .descendants(collection)
+            .into_iter()
+            .filter_map(|id| {
+                let test = self.tests.get(&id)?;
+                let mut req = test.req.clone();
+                req.auth = self.resolve_auth(id);
+                Some((self.tree_ui.title(id).unwrap_or_default(), req))
+            })
+            .collect();
+        let text = export_http(&vars, &requests, !self.export_secrets);
+        std::fs::write(path, self.export_text(text))
+            .map_err(|e| format!("写入文件失败:{}", e))?;
+        Ok(format!("已导出{}个请求", requests.len()))
+    }
+
+    ///集合下的直接子集合作为tag,集合脚本里的变量用来还原服务器地址
+    fn export_openapi(&self, collection: u64, path: &std::path::Path) -> Result<String, String> {
+        let title = self.tree_ui.title(collection).unwrap_or_default();
+        let mut info = SpecInfo {
+            doc: self
+                .collections
+                .get(&collection)
+                .map(|c| c.doc.clone())
+                .unwrap_or_default(),
+            title,
+            ..Default::default()
+        };
+        for id in self.node_path(collection).iter().rev() {
+            if let Some(c) = self.collections.get(id) {
+                info.vars.extend(script_vars(&c.script));
+            }
+        }
+        for id in self.tree_ui.children(collection) {
+            if let Some(c) = self.collections.get(&id) {
+                let name = self.tree_ui.title(id).unwrap_or_default();
+                info.tags.push((name, c.doc.clone()));
+            }
+        }
+        let ops: Vec<SpecOperation> = self
+            .tree_ui
+            .descendants(collection)
+            .into_iter()
+            .filter_map(|id| {
+                let test = self.tests.get(&id)?;
+                let node_path = self.node_path(id);
+                let pos = node_path.iter().position(|p| *p == collection)?;
+                //在集合下的第一层节点,是请求本身时没有tag
+                let tag = node_path
+                    .get(pos.checked_sub(1)?)
+                    .filter(|top| **top != id)
+                    .and_then(|top| self.tree_ui.title(*top));
+                Some(SpecOperation {
+                    summary: self.tree_ui.title(id).unwrap_or_default(),
+                    tag,
+                    req: &test.req,
+                    resp: &test.resp,
+                })
+            })
+            .collect();
+        let (spec, skipped) = export_openapi(&info, &ops);
+        let is_json = path.extension().is_some_and(|ext| ext == "json");
+        let text = if is_json {
+            serde_json::to_string_pretty(&spec).map_err(|e| e.to_string())?
+        } else {
+            serde_yaml::to_string(&spec).map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, self.export_text(text)).map_err(|e| format!("写入文件失败:{}", e))?;
+        let count = ops.len() - skipped.len();
+        if skipped.is_empty() {
+            Ok(format!("已导出{}个接口", count))
+        } else {
+            Ok(format!(
+                "已导出{}个接口,路径和方法重复没有导出:{}",
+                count,
+                skipped.join("、")
+            ))
+        }
+    }
+
+    ///粘贴curl命令,导入到选中的集合下
+    fn curl_import_window(&mut self, ctx: &egui::Context) {
+        let Some(cmd) = self.curl_import.as_mut() else {
+            return;
+        };
+        let mut open = true;
+        let mut confirm = false;
+        egui::Window::new("导入curl")
+            .open(&mut open)
+            .default_width(480.)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(cmd)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(8)
+                        .code_editor()
+                        .hint_text("curl 'https://...' -H '...'"),
+                );
+                confirm = ui.button("导入").clicked();
+            });
+        if !open {
+            self.curl_import = None;
+            return;
+        }
+        if !confirm {
+            return;
+        }
+        let cmd = cmd.clone();
+        let result = parse_curl(&cmd).and_then(|req| {
+            let path = self.selected_collection_path();
+            let new_id = self
+                .tree_ui
+                .add_with_title(path, &req.default_title(), tree_ui::NodeType::Node)
+                .ok_or_else(|| "选中的集合不存在".to_owned())?;
+            self.insert_test(
+                new_id,
+                ApiTester {
+                    req,
+                    ..Default::default()
+                },
+            );
+            Ok(())
+        });
+        if let Ok(mut toast_w) = TOASTS.get().unwrap().lock() {
+            match result {
+                Ok(()) => {
+                    self.curl_import = None;
+                    toast_w.info("已导入").set_duration(Some(Duration::from_secs(3)));
+                }
+                Err(e) => {
+                    toast_w
+                        .error(format!("导入失败:{}", e))
+                        .set_duration(Some(Duration::from_secs(5)));
+                }
+            }
+        }
+    }
+
     pub fn insert_collecton(
         &mut self,
         key: u64,
@@ -329,6 +1482,23 @@ impl ApiContext {
     }
 }
 
+///后置脚本能用的_req_url、_req_body、_req、_resp和_event
+pub fn push_response_vars(scope: &mut Scope, test: &ApiTester) {
+    scope.push("_req_url", test.req.url.clone());
+    scope.push("_req_body", test.req.body.clone());
+    scope.push("_req", request_map(&test.req));
+    scope.push("_resp", response_map(&test.resp));
+    //流式响应的每个事件执行时_event才有值,只有用到_event的脚本会逐个事件执行
+    scope.push("_event", Dynamic::UNIT);
+}
+
+///提取到的变量放进rhai作用域,脚本里的同名变量会覆盖它
+fn push_extracted(scope: &mut Scope, extracted: &BTreeMap<String, String>) {
+    for (key, value) in extracted {
+        scope.push(key.clone(), value.clone());
+    }
+}
+
 #[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
 pub struct ApiTester {
     pub script: ScriptData,
@@ -336,10 +1506,67 @@ pub struct ApiTester {
     pub resp: ResponseData,
     #[serde(skip)]
     pub load_test: LoadTestData,
+    ///导入来源的标识,重新导入时按它更新
+    #[serde(default)]
+    pub source: String,
+    ///响应到达后按顺序执行
+    #[serde(default)]
+    pub extractors: Vec<Extractor>,
+    ///提取到的请求作用域变量,执行前置脚本前放进作用域
+    #[serde(default)]
+    pub extracted: BTreeMap<String, String>,
 }
 
 #[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
 pub struct CollectionsData {
     pub doc: String,
+    ///前置脚本
     pub script: String,
+    ///后置脚本,在请求自己的后置脚本之前执行
+    #[serde(default)]
+    pub after: String,
+    #[serde(default)]
+    pub auth: AuthData,
+    #[serde(default)]
+    pub settings: ClientSettings,
+    #[serde(default)]
+    pub graphql_schema: Option<GraphQLSchema>,
+    ///导入来源的标识,重新导入时按它更新
+    #[serde(default)]
+    pub source: String,
+    ///提取到的集合作用域变量,执行集合脚本前放进作用域
+    #[serde(default)]
+    pub extracted: BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::auth::AuthType;
+
+    #[test]
+    fn test_export_without_credentials() {
+        let mut ctx = ApiContext::new();
+        let mut bearer = ApiTester::default();
+        bearer.req.auth.auth_type = AuthType::Bearer;
+        bearer.req.auth.token = "literal-bearer-token".to_owned();
+        ctx.insert_test(2, bearer);
+        let mut basic = CollectionsData::default();
+        basic.auth.auth_type = AuthType::Basic;
+        basic.auth.username = "admin".to_owned();
+        basic.auth.password = "literal-basic-pass".to_owned();
+        ctx.insert_collecton(3, basic);
+
+        let text = ctx.export_value().unwrap().to_string();
+        assert!(!text.contains("literal-bearer-token"));
+        assert!(!text.contains("literal-basic-pass"));
+        assert!(text.contains("admin"));
+        //导出后内存里的凭证还在
+        assert_eq!(ctx.tests[&2].req.auth.token, "literal-bearer-token");
+        assert_eq!(ctx.collections[&3].auth.password, "literal-basic-pass");
+
+        ctx.export_secrets = true;
+        let text = ctx.export_value().unwrap().to_string();
+        assert!(text.contains("literal-bearer-token"));
+    }
 }
@@ -1,10 +1,21 @@
 use crate::{
-    api_context::{ ApiContext, ApiTester, CollectionsData},
+    api_context::{ push_response_vars, ApiContext, ApiTester, CollectionsData},
     component::tree_ui::{self, TreeUi},
-    request_data::{covert_to_ui, PreHttpTest, PreRequest, PreResponse, RequestData, ResponseData},
+    request_data::{
+        covert_to_ui, request_size, response_head, PreHttpTest, PreRequest, PreResponse,
+        RequestData, ResponseData, StreamEvent, Timing,
+    },
     utils::{
         // rhai_script::ScriptEngine,
-        template::add_global_var, rhai_script::SCRIPT_ENGINE,
+        assertion,
+        auth::{self, AuthData},
+        client::client_for,
+        graphql::GraphQLSchema,
+        stream::{StreamKind, StreamParser, Utf8Decoder},
+        timing,
+        websocket::{WsDirection, WsEvent, WsLogItem, WsMessageKind},
+        environment::push_env_vars,
+        template::add_global_var, rhai_script::{script_error, ScriptLimit, SCRIPT_ENGINE},
     },
 };
 use chrono::Local;
@@ -19,8 +30,9 @@ use log::info;
 use minijinja::value::Value as JValue;
 use once_cell::sync::Lazy;
 use once_cell::sync::OnceCell;
-use reqwest::{Client, Request};
-use rhai::Scope;
+use reqwest::{header::CONTENT_TYPE, Client, Request, Response};
+use rhai::{serde::to_dynamic, Scope};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 use std::thread;
 use std::{io::BufReader, sync::Mutex};
@@ -29,6 +41,7 @@ use tokio::{
     runtime::Runtime,
     sync::mpsc::{Receiver, Sender},
 };
+use tokio_util::sync::CancellationToken;
 /**
  * 全局变量
  */
@@ -50,6 +63,17 @@ pub static mut M_RESULTE_CHANNEL: Lazy<(
     Sender<(u64, usize, i64, ResponseData)>,
     Receiver<(u64, usize, i64, ResponseData)>,
 )> = Lazy::new(|| tokio::sync::mpsc::channel(100));
+///流式响应的分块,先于RESULTE_CHANNEL里的完整响应
+pub static mut STREAM_CHANNEL: Lazy<(Sender<(u64, StreamUpdate)>, Receiver<(u64, StreamUpdate)>)> =
+    Lazy::new(|| tokio::sync::mpsc::channel(100));
+///GraphQL内省的结果,id是缓存Schema的集合
+pub static mut SCHEMA_CHANNEL: Lazy<(
+    Sender<(u64, Result<GraphQLSchema, String>)>,
+    Receiver<(u64, Result<GraphQLSchema, String>)>,
+)> = Lazy::new(|| tokio::sync::mpsc::channel(10));
+///WebSocket连接的事件
+pub static mut WS_CHANNEL: Lazy<(Sender<(u64, WsEvent)>, Receiver<(u64, WsEvent)>)> =
+    Lazy::new(|| tokio::sync::mpsc::channel(100));
 pub static TOASTS: OnceCell<Arc<Mutex<Toasts>>> = OnceCell::new();
 pub static TOKIO_RT: Lazy<Runtime> = Lazy::new(|| {
     tokio::runtime::Builder::new_multi_thread()
@@ -58,12 +82,72 @@ pub static TOKIO_RT: Lazy<Runtime> = Lazy::new(|| {
         .build()
         .unwrap()
 });
-pub static mut CLIENT: Lazy<Client> = Lazy::new(|| {
-    Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()
-        .unwrap_or_default()
-});
+
+pub enum StreamUpdate {
+    ///收到响应头
+    Start(ResponseData),
+    ///size是这一块在线上的字节数,text里不含还没收全的字符
+    Chunk {
+        text: String,
+        size: usize,
+        events: Vec<StreamEvent>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TaskKind {
+    ///单次发送
+    Send,
+    LoadTest,
+}
+
+///正在运行的请求和压测,用于取消,值里的序号区分同一个请求先后启动的任务
+static RUNNING_TASKS: Lazy<Mutex<HashMap<(u64, TaskKind), (u64, CancellationToken)>>> =
+    Lazy::new(|| Mutex::new(HashMap::default()));
+static TASK_SEQ: AtomicU64 = AtomicU64::new(0);
+
+///登记中的任务,drop时(完成或取消)从RUNNING_TASKS里移除
+pub struct RunningTask {
+    key: (u64, TaskKind),
+    seq: u64,
+    pub token: CancellationToken,
+}
+
+impl Drop for RunningTask {
+    fn drop(&mut self) {
+        if let Ok(mut tasks) = RUNNING_TASKS.lock() {
+            //已经被新任务替换时不动
+            if tasks.get(&self.key).is_some_and(|(seq, _)| *seq == self.seq) {
+                tasks.remove(&self.key);
+            }
+        }
+    }
+}
+
+///登记一个新任务,同一个请求之前未完成的同类任务会被取消,单次发送和压测互不影响
+pub fn start_task(id: u64, kind: TaskKind) -> RunningTask {
+    let token = CancellationToken::new();
+    let seq = TASK_SEQ.fetch_add(1, Ordering::Relaxed);
+    if let Ok(mut tasks) = RUNNING_TASKS.lock() {
+        if let Some((_, old)) = tasks.insert((id, kind), (seq, token.clone())) {
+            old.cancel();
+        }
+    }
+    RunningTask {
+        key: (id, kind),
+        seq,
+        token,
+    }
+}
+
+///取消请求对应的任务,已完成的任务不受影响
+pub fn cancel_task(id: u64, kind: TaskKind) {
+    if let Ok(mut tasks) = RUNNING_TASKS.lock() {
+        if let Some((_, token)) = tasks.remove(&(id, kind)) {
+            token.cancel();
+        }
+    }
+}
 
 /// We derive Deserialize/Serialize so we can persist app state on shutdown.
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -141,7 +225,8 @@ impl TemplateApp {
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
         if let Some(storage) = cc.storage {
-            let app = eframe::get_value(storage, APP_KEY).unwrap_or_default();
+            let app: TemplateApp = eframe::get_value(storage, APP_KEY).unwrap_or_default();
+            app.api_data.environments.apply();
             return app;
         }
         TemplateApp::default()
@@ -185,23 +270,23 @@ impl TemplateApp {
         true
     }
 
-    fn send_request(&self, req: &RequestData, id: u64) {
+    fn send_request(&self, req: &RequestData, id: u64, client: Client) {
         info!("发送请求：{},{}",req.method.to_string(),&req.url);
         let req = req.clone();
+        let task = start_task(id, TaskKind::Send);
         TOKIO_RT.spawn(async move {
             let start = Local::now().timestamp_millis();
             let pre_req: PreRequest = (&req).into();
+            let auth = req.auth.clone();
             let mut resp: ResponseData;
             if let Ok(send_req) = req.try_into() {
-                resp = match unsafe { CLIENT.execute(send_req) }.await {
-                    Ok(rep) => covert_to_ui(rep).await,
-                    Err(err) => ResponseData {
-                        headers: Default::default(),
-                        body: err.to_string(),
-                        size: 0,
-                        code: "999".to_owned(),
-                        time: 0,
-                    },
+                let exec = execute_timed(&client, send_req, &auth, Some(id));
+                resp = tokio::select! {
+                    _ = task.token.cancelled() => {
+                        info!("请求已取消：{}", id);
+                        return;
+                    }
+                    resp = exec => resp,
                 };
                 let pre_resp: PreResponse = (&resp).into();
                 let pre_http = PreHttpTest {
@@ -215,16 +300,15 @@ impl TemplateApp {
                 add_global_var(format!("REQ_{}", id), JValue::from_serializable(&pre_http));
             } else {
                 resp = ResponseData {
-                    headers: Default::default(),
                     body: "请求URL解析错误".to_string(),
-                    size: 0,
                     code: "999".to_owned(),
-                    time: 0,
+                    ..Default::default()
                 };
             }
             let end = Local::now().timestamp_millis();
             let now = end - start;
             resp.time = now;
+            drop(task);
             let _send_res = unsafe { RESULTE_CHANNEL.0.send((id, now, resp)).await };
         });
     }
@@ -233,7 +317,10 @@ impl TemplateApp {
 impl eframe::App for TemplateApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        //认证里直接写的凭证不落盘,要保存就写成${加密变量}
+        let taken = self.api_data.take_credentials();
         eframe::set_value(storage,APP_KEY, self);
+        self.api_data.restore_credentials(taken);
     }
 
     /// Called each time the UI needs repainting, which may be many times per second.
@@ -277,6 +364,7 @@ impl eframe::App for TemplateApp {
                         dialog.open();
                         self.open_file_dialog = Some(dialog);
                     }
+                    ui.checkbox(&mut self.api_data.export_secrets, "导出时包含加密变量");
 
                     if ui.button("Quit").clicked() {
                         ctx.send_viewport_cmd(ViewportCommand::Close);
@@ -313,6 +401,8 @@ impl eframe::App for TemplateApp {
                     ui.data_mut(|w|w.insert_temp(dsik_id, input_str));
                 });
 
+                self.api_data.environment_menu(ui);
+
                 ui.menu_button("视图", |ui| {
                     // allow certain tabs to be toggled
                     for tab in TABS
@@ -330,6 +420,11 @@ impl eframe::App for TemplateApp {
                                 "历史记录".to_owned(),
                                 "前置脚本".to_owned(),
                                 "后置脚本".to_owned(),
+                                "认证".to_owned(),
+                                "客户端设置".to_owned(),
+                                "生成代码".to_owned(),
+                                "变量".to_owned(),
+                                "提取变量".to_owned(),
                             ]
                         })
                         .iter()
@@ -357,6 +452,7 @@ impl eframe::App for TemplateApp {
                                 let reader = BufReader::new(rfile);
                                 let app: ApiContext = serde_json::from_reader(reader).unwrap();
                                 self.api_data = app;
+                                self.api_data.environments.apply();
                                 // self.records = app.records;
                                 // self.records_list = app.records_list;
                                 // self.list_selected = app.list_selected;
@@ -369,7 +465,11 @@ impl eframe::App for TemplateApp {
                                 std::fs::File::open(file).unwrap_or_else(|_err| {
                                     std::fs::File::create(file).unwrap()
                                 });
-                            if let Err(err) = serde_json::to_writer_pretty(app_json, &self.api_data) {
+                            let result = self
+                                .api_data
+                                .export_value()
+                                .and_then(|value| serde_json::to_writer_pretty(app_json, &value));
+                            if let Err(err) = result {
                                 if let Ok(mut toast_w) = toast.lock() {
                                     toast_w
                                         .error(format!("save file error:{}", err.to_string()))
@@ -457,6 +557,9 @@ impl eframe::App for TemplateApp {
         //         //    });
         //     });
 
+        self.api_data.environment_window(ctx);
+        self.api_data.poll_debugger(ctx);
+
         egui::CentralPanel::default()
             .frame(Frame::central_panel(&ctx.style()).inner_margin(0.))
             .show(ctx, |ui| {
@@ -477,19 +580,81 @@ impl eframe::App for TemplateApp {
             //         .set_duration(Some(Duration::from_secs(5)));
             // }
             if let Some(req) = self.api_data.tests.get(&send.0) {
-                if let Err(e) = self.api_data.run_script() {
+                let mut req_data = req.req.clone();
+                req_data.auth = self.api_data.resolve_auth(send.0);
+                let client = client_for(&self.api_data.resolve_settings(send.0));
+                if let Err(e) = &client {
                     if let Ok(mut toast_w) = toast.lock() {
                         toast_w
-                            .info(format!("脚本执行错误:{}-{}", e.position(), e.to_string()))
+                            .error(format!("客户端设置错误:{}", e))
+                            .set_duration(Some(Duration::from_secs(5)));
+                    }
+                } else if let Err(e) = self.api_data.run_script() {
+                    if let Ok(mut toast_w) = toast.lock() {
+                        toast_w
+                            .info(format!("脚本执行错误:{}-{}", e.position(), script_error(&e)))
                             .set_duration(Some(Duration::from_secs(5)));
                     }
                 } else {
                     if send.1 == 0 || send.2 == 0 {
                         //执行脚本,不发请求
-                    } else if send.1 == 1 && send.2 == 1 {
-                        self.send_request(&req.req, send.0);
-                    } else {
-                        load_test_sender(&req.req, send.0, send.1, send.2);
+                    } else if let Ok(client) = client {
+                        if send.1 == 1 && send.2 == 1 {
+                            self.send_request(&req_data, send.0, client);
+                        } else {
+                            load_test_sender(&req_data, send.0, send.1, send.2, client);
+                        }
+                    }
+                }
+            }
+        }
+
+        //先处理完分块,完整的响应总是在分块之后
+        while let Ok((stream_id, update)) = unsafe { STREAM_CHANNEL.1.try_recv() } {
+            match update {
+                StreamUpdate::Start(head) => {
+                    if let Some(resp_dn) = self.api_data.tests.get_mut(&stream_id) {
+                        resp_dn.resp = head;
+                    }
+                }
+                StreamUpdate::Chunk { text, size, events } => {
+                    //只有用到_event的后置脚本逐个事件执行,从外到内;其他的等完整响应再执行
+                    let chain: Vec<(u64, String)> = self
+                        .api_data
+                        .script_chain(stream_id, true)
+                        .into_iter()
+                        .filter(|(_, script)| script.contains("_event"))
+                        .collect();
+                    let Some(resp_dn) = self.api_data.tests.get_mut(&stream_id) else {
+                        continue;
+                    };
+                    resp_dn.resp.size += size as u64;
+                    resp_dn.resp.body.push_str(&text);
+                    for event in events {
+                        let value = to_dynamic(&event).unwrap_or_default();
+                        resp_dn.resp.events.push(event);
+                        if chain.is_empty() {
+                            continue;
+                        }
+                        let _limit = ScriptLimit::start(false);
+                        let script_scope = &mut Scope::new();
+                        push_env_vars(script_scope);
+                        push_response_vars(script_scope, resp_dn);
+                        script_scope.set_value("_event", value);
+                        //事件里的断言单独收集,追加到这次响应的测试结果
+                        assertion::begin(&resp_dn.resp);
+                        let result = chain.iter().try_for_each(|(_, script)| {
+                            SCRIPT_ENGINE.run_with_scope(script_scope, script)
+                        });
+                        if let Err(e) = &result {
+                            if let Ok(mut toast_w) = toast.lock() {
+                                toast_w
+                                    .info(format!("脚本执行错误:{}-{}", e.position(), script_error(e)))
+                                    .set_duration(Some(Duration::from_secs(5)));
+                            }
+                        }
+                        let tests = assertion::finish(result.err().as_deref());
+                        resp_dn.resp.tests.extend(tests);
                     }
                 }
             }
@@ -501,24 +666,40 @@ impl eframe::App for TemplateApp {
                     .info(format!("{}号响应时间{}", resp_id, resp_time))
                     .set_duration(Some(Duration::from_secs(5)));
             }
+            //流式响应事件里的断言结果留到最后和完整响应的合在一起
+            let mut event_tests = Vec::new();
             if let Some(resp_dn) = self.api_data.tests.get_mut(&resp_id) {
+                event_tests = std::mem::take(&mut resp_dn.resp.tests);
                 resp_dn.resp = resp_data;
                 if let Some(req_id) = REQ_UI_ID.get() {
                     let state_id = req_id.with(resp_id);
                     //更新对应的ui状态
                     let _send_state = ctx.data_mut(|d| d.insert_temp(state_id, false));
                 }
+            }
+            self.api_data.run_extractors(resp_id);
+            self.api_data.publish_script_requests();
+            //集合的后置脚本从外到内执行,最后是请求自己的
+            let chain = self.api_data.script_chain(resp_id, true);
+            if let Some(resp_dn) = self.api_data.tests.get_mut(&resp_id) {
                 let script_scope = &mut Scope::new();
-                script_scope.push("_req_url", resp_dn.req.url.clone());
-                script_scope.push("_req_body", resp_dn.req.body.clone());
-                script_scope.push("_resp", resp_dn.resp.clone());
-                if let Err(e) = SCRIPT_ENGINE.run_with_scope(script_scope,&resp_dn.script.after) {
+                push_env_vars(script_scope);
+                push_response_vars(script_scope, resp_dn);
+                assertion::begin(&resp_dn.resp);
+                //集合和请求的后置脚本加起来不能超过SCRIPT_BUDGET
+                let _limit = ScriptLimit::start(false);
+                let result = chain.iter().try_for_each(|(_, script)| {
+                    SCRIPT_ENGINE.run_with_scope(script_scope, script)
+                });
+                if let Err(e) = &result {
                     if let Ok(mut toast_w) = toast.lock() {
                         toast_w
-                            .info(format!("脚本执行错误:{}-{}", e.position(), e.to_string()))
+                            .info(format!("脚本执行错误:{}-{}", e.position(), script_error(e)))
                             .set_duration(Some(Duration::from_secs(5)));
                     }
                 }
+                event_tests.extend(assertion::finish(result.err().as_deref()));
+                resp_dn.resp.tests = event_tests;
             }
         }
 
@@ -536,21 +717,76 @@ impl eframe::App for TemplateApp {
                     if &resp_rs.3.code == "999" {
                         resp_dn.load_test.result.error += 1.0;
                     }
+                    resp_dn.load_test.result.send += resp_rs.3.send_size as f32 / 1024.0;
+                    resp_dn.load_test.result.recived +=
+                        (resp_rs.3.header_size + resp_rs.3.size) as f32 / 1024.0;
                     resp_dn.load_test.update_process();
                     resp_dn.load_test.add_result(resp_rs.1, resp_rs.2);
                     resp_dn.load_test.recode_time(resp_rs.2);
                 }
             }
         }
+        if let Ok((collection_id, result)) = unsafe { SCHEMA_CHANNEL.1.try_recv() } {
+            let msg = match result {
+                Ok(schema) => match self.api_data.collections.get_mut(&collection_id) {
+                    //Schema存在集合上,不在集合里的请求或集合已被删除时不保存
+                    Some(collection) => {
+                        let msg = format!("已获取Schema,{}个类型", schema.types.len());
+                        collection.graphql_schema = Some(schema);
+                        msg
+                    }
+                    None => "请求不在集合里,Schema未保存".to_owned(),
+                },
+                Err(e) => format!("获取Schema失败:{}", e),
+            };
+            if let Ok(mut toast_w) = toast.lock() {
+                toast_w
+                    .info(msg)
+                    .set_duration(Some(Duration::from_secs(5)));
+            }
+        }
+        while let Ok((ws_id, event)) = unsafe { WS_CHANNEL.1.try_recv() } {
+            let Some(ws) = self.api_data.websockets.get_mut(&ws_id) else {
+                continue;
+            };
+            let log = match event {
+                WsEvent::Connected => {
+                    WsLogItem::new(WsDirection::Info, WsMessageKind::Text, "已连接".to_owned())
+                }
+                WsEvent::Closed => {
+                    WsLogItem::new(WsDirection::Info, WsMessageKind::Text, "已断开".to_owned())
+                }
+                WsEvent::Error(e) => {
+                    WsLogItem::new(WsDirection::Info, WsMessageKind::Text, format!("错误:{}", e))
+                }
+                WsEvent::Received(log) => {
+                    let _limit = ScriptLimit::start(false);
+                    let script_scope = &mut Scope::new();
+                    push_env_vars(script_scope);
+                    script_scope.push("_msg", log.content.clone());
+                    script_scope.push("_msg_binary", log.kind == WsMessageKind::Binary);
+                    if let Err(e) = SCRIPT_ENGINE.run_with_scope(script_scope, &ws.script) {
+                        if let Ok(mut toast_w) = toast.lock() {
+                            toast_w
+                                .info(format!("脚本执行错误:{}-{}", e.position(), script_error(&e)))
+                                .set_duration(Some(Duration::from_secs(5)));
+                        }
+                    }
+                    log
+                }
+            };
+            ws.log.push(log);
+        }
         if let Ok(mut toast_w) = toast.lock() {
             toast_w.show(ctx);
         }
     }
 }
 
-fn load_test_sender(req: &RequestData, id: u64, reqs: u32, round: u32) {
+fn load_test_sender(req: &RequestData, id: u64, reqs: u32, round: u32, client: Client) {
     let capacity: usize = (round as usize) * (reqs as usize);
     let mut sender_requsets: Vec<(usize, Request)> = Vec::with_capacity(capacity);
+    let auth = req.auth.clone();
     for i in 0..capacity {
         let req_clone = req.clone();
         if let Ok(real_req) = req_clone.try_into() {
@@ -558,36 +794,48 @@ fn load_test_sender(req: &RequestData, id: u64, reqs: u32, round: u32) {
         }
     }
     sender_requsets.reverse();
+    let task = start_task(id, TaskKind::LoadTest);
 
     let _ = thread::Builder::new()
         .name("send_req_thread".to_string())
         .spawn(move || {
             let _respui = TOKIO_RT.block_on(async move {
-                for _ in 0..round {
-                    // let client = reqwest::Client::new();
-                    let start = std::time::SystemTime::now();
-                    let mut f_vec = Vec::new();
-                    for _ in 0..reqs {
-                        let req = sender_requsets.pop().unwrap();
-                        let f = send_load_test_request(req, id);
-                        // let _tf = tokio::task::spawn(f);
-                        f_vec.push(f);
+                let rounds = async {
+                    for _ in 0..round {
+                        // let client = reqwest::Client::new();
+                        let start = std::time::SystemTime::now();
+                        let mut f_vec = Vec::new();
+                        for _ in 0..reqs {
+                            let req = sender_requsets.pop().unwrap();
+                            let f = send_load_test_request(req, id, &client, &auth);
+                            // let _tf = tokio::task::spawn(f);
+                            f_vec.push(f);
+                        }
+                        // println!("生成完成：{}-{}",f_vec.len(),duration);
+                        // let result = stream::iter(f_vec)
+                        //                         .buffer_unordered(16)
+                        //                         .collect::<Vec<_>>().await;
+                        // let _tf = tokio::spawn(
+                        tokio_stream::iter(f_vec)
+                            .buffered(reqs as usize)
+                            .collect::<Vec<_>>()
+                            .await;
+                        // );
+                        let duration = start.elapsed().unwrap().as_millis() as u64;
+                        if duration < 1000 {
+                            tokio::time::sleep(Duration::from_millis(1000 - duration)).await;
+                        }
                     }
-                    // println!("生成完成：{}-{}",f_vec.len(),duration);
-                    // let result = stream::iter(f_vec)
-                    //                         .buffer_unordered(16)
-                    //                         .collect::<Vec<_>>().await;
-                    // let _tf = tokio::spawn(
-                    tokio_stream::iter(f_vec)
-                        .buffered(reqs as usize)
-                        .collect::<Vec<_>>()
-                        .await;
-                    // );
-                    let duration = start.elapsed().unwrap().as_millis() as u64;
-                    if duration < 1000 {
-                        tokio::time::sleep(Duration::from_millis(1000 - duration)).await;
+                };
+                tokio::select! {
+                    _ = task.token.cancelled() => {
+                        //已中止,界面状态在点击停止时已重置
+                        info!("压测已中止：{}", id);
+                        return;
                     }
+                    _ = rounds => {}
                 }
+                drop(task);
                 let _rs = unsafe {
                     M_RESULTE_CHANNEL
                         .0
@@ -599,35 +847,92 @@ fn load_test_sender(req: &RequestData, id: u64, reqs: u32, round: u32) {
     //发送一个完成的数据
 }
 
-async fn send_load_test_request(ireq: (usize, Request), id: u64) {
-    let start = Local::now().timestamp_millis();
-    let (index, req) = ireq;
-    match unsafe { CLIENT.execute(req) }.await {
+///发送请求,记录收发的字节数和各阶段耗时,传了stream_id时流式响应会边收边发给界面
+async fn execute_timed(
+    client: &Client,
+    req: Request,
+    auth: &AuthData,
+    stream_id: Option<u64>,
+) -> ResponseData {
+    let send_size = request_size(&req);
+    let (result, timing) = timing::measure(auth::execute(client, req, auth)).await;
+    let mut resp = match result {
         Ok(rep) => {
-            let resp_ui = covert_to_ui(rep).await;
-            let end = Local::now().timestamp_millis();
-            let _ = unsafe {
-                M_RESULTE_CHANNEL
-                    .0
-                    .send((id, index, end - start, resp_ui))
-                    .await
-            };
+            let kind = rep
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(StreamKind::detect);
+            match (stream_id, kind) {
+                (Some(id), Some(kind)) => read_stream(id, rep, kind).await,
+                _ => covert_to_ui(rep).await,
+            }
         }
-        Err(err) => {
-            let resp_ui = ResponseData {
-                headers: Default::default(),
-                body: err.to_string(),
-                size: 0,
-                code: "999".to_owned(),
-                time: 0,
-            };
-            let end = Local::now().timestamp_millis();
-            let _ = unsafe {
-                M_RESULTE_CHANNEL
-                    .0
-                    .send((id, index, end - start, resp_ui))
-                    .await
-            };
+        Err(err) => ResponseData {
+            body: err,
+            code: "999".to_owned(),
+            ..Default::default()
+        },
+    };
+    resp.send_size = send_size;
+    resp.timing = Timing {
+        download: resp.timing.download,
+        ..timing
+    };
+    resp
+}
+
+async fn read_stream(id: u64, mut rep: Response, kind: StreamKind) -> ResponseData {
+    let mut resp = response_head(&rep);
+    let _ = unsafe { STREAM_CHANNEL.0.send((id, StreamUpdate::Start(resp.clone()))).await };
+    let start = std::time::Instant::now();
+    let mut parser = StreamParser::new(kind);
+    let mut decoder = Utf8Decoder::default();
+    let mut body = Vec::new();
+    loop {
+        let (text, size, events, done) = match rep.chunk().await {
+            Ok(Some(chunk)) => {
+                body.extend_from_slice(&chunk);
+                (decoder.feed(&chunk), chunk.len(), parser.feed(&chunk), false)
+            }
+            Ok(None) => (decoder.finish(), 0, parser.finish(), true),
+            Err(e) => {
+                resp.code = "999".to_owned();
+                let text = format!("\n读取响应失败:{}", e);
+                body.extend_from_slice(text.as_bytes());
+                let text = decoder.finish() + text.as_str();
+                (text, 0, parser.finish(), true)
+            }
+        };
+        resp.events.extend(events.iter().cloned());
+        if !text.is_empty() || size > 0 || !events.is_empty() {
+            let update = StreamUpdate::Chunk { text, size, events };
+            let _ = unsafe { STREAM_CHANNEL.0.send((id, update)).await };
+        }
+        if done {
+            break;
         }
     }
+    resp.size = body.len() as u64;
+    resp.body = String::from_utf8_lossy(&body).into_owned();
+    resp.timing.download = start.elapsed().as_millis() as i64;
+    resp
+}
+
+async fn send_load_test_request(
+    ireq: (usize, Request),
+    id: u64,
+    client: &Client,
+    auth: &AuthData,
+) {
+    let start = Local::now().timestamp_millis();
+    let (index, req) = ireq;
+    let resp_ui = execute_timed(client, req, auth, None).await;
+    let end = Local::now().timestamp_millis();
+    let _ = unsafe {
+        M_RESULTE_CHANNEL
+            .0
+            .send((id, index, end - start, resp_ui))
+            .await
+    };
 }
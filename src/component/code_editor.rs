@@ -1,10 +1,18 @@
-use egui::{epaint::ahash::HashMap, text::CursorRange, Id, Pos2, TextBuffer};
+use egui::text_edit::TextEditOutput;
+use egui::{
+    epaint::ahash::HashMap, pos2, text::CursorRange, Align2, Color32, FontId, Id, Pos2, Rect,
+    Sense, TextBuffer,
+};
 use log::debug;
+use std::collections::BTreeSet;
 use std::ops::Range;
 use weighted_trie::WeightedTrie;
 
 use super::syntax_highlight::{highlight, CodeTheme};
 
+///断点和行号栏的宽度
+const GUTTER_WIDTH: f32 = 36.0;
+
 #[derive(serde::Deserialize, serde::Serialize)]
 #[serde(default)]
 pub struct TextEdit {
@@ -114,6 +122,19 @@ impl TextEdit {
         }
     }
 
+    ///words是Schema里的类型、字段和参数名
+    pub fn new_graphql(words: &[String]) -> Self {
+        let sug = AutoSuggester::graphql(words);
+        Self {
+            language: "graphql".to_owned(),
+            suggest: sug,
+            sug_pos: None,
+            selected_sug: "".to_owned(),
+            sug_str: None,
+            selected_range: None,
+        }
+    }
+
     pub fn new_template() -> Self {
         let sug = AutoSuggester::template();
         Self {
@@ -127,6 +148,84 @@ impl TextEdit {
    }
 
     pub fn ui(&mut self, ui: &mut egui::Ui, text: &mut String, id: u64) {
+        self.show(ui, text, id);
+    }
+
+    ///左边带行号的编辑器,点行号切换断点,current是调试暂停的行,行号从1开始
+    pub fn ui_with_breakpoints(
+        &mut self,
+        ui: &mut egui::Ui,
+        text: &mut String,
+        id: u64,
+        breakpoints: &mut BTreeSet<usize>,
+        current: Option<usize>,
+    ) {
+        let full = ui.available_rect_before_wrap();
+        let mut gutter = full;
+        gutter.max.x = gutter.min.x + GUTTER_WIDTH;
+        let mut editor_rect = full;
+        editor_rect.min.x = gutter.max.x;
+        let mut editor_ui = ui.child_ui(editor_rect, *ui.layout());
+        let old_text = text.clone();
+        let output = self.show(&mut editor_ui, text, id);
+        if output.response.changed() {
+            shift_breakpoints(breakpoints, &old_text, text);
+        }
+        gutter.max.y = output.response.rect.max.y;
+        ui.allocate_rect(gutter.union(output.response.rect), Sense::hover());
+
+        let gutter_resp = ui.interact(gutter, ui.id().with(id).with("gutter"), Sense::click());
+        let click_y = gutter_resp
+            .clicked()
+            .then(|| gutter_resp.interact_pointer_pos())
+            .flatten()
+            .map(|pos| pos.y);
+        let painter = ui.painter();
+        let font_id = FontId::monospace(10.0);
+        let mut line = 1;
+        let mut line_start = true;
+        for row in output.galley.rows.iter() {
+            let rect = row.rect.translate(output.galley_pos.to_vec2());
+            if line_start {
+                if click_y.map_or(false, |y| rect.y_range().contains(y))
+                    && !breakpoints.remove(&line)
+                {
+                    breakpoints.insert(line);
+                }
+                if current == Some(line) {
+                    let line_rect = Rect::from_x_y_ranges(
+                        gutter.min.x..=output.response.rect.max.x,
+                        rect.y_range(),
+                    );
+                    painter.rect_filled(
+                        line_rect,
+                        0.0,
+                        Color32::from_rgba_unmultiplied(255, 220, 0, 40),
+                    );
+                }
+                if breakpoints.contains(&line) {
+                    painter.circle_filled(
+                        pos2(gutter.min.x + 6.0, rect.center().y),
+                        4.0,
+                        Color32::RED,
+                    );
+                }
+                painter.text(
+                    pos2(gutter.max.x - 4.0, rect.center().y),
+                    Align2::RIGHT_CENTER,
+                    line.to_string(),
+                    font_id.clone(),
+                    Color32::GRAY,
+                );
+            }
+            line_start = row.ends_with_newline;
+            if line_start {
+                line += 1;
+            }
+        }
+    }
+
+    fn show(&mut self, ui: &mut egui::Ui, text: &mut String, id: u64) -> TextEditOutput {
         let Self {
             language,
             suggest,
@@ -354,6 +453,7 @@ impl TextEdit {
                 Self::set_cursor_index(ui, text_edit_id, text.chars().count())
             }
         }
+        output
     }
 
     fn set_cursor_index(ui: &mut egui::Ui, text_edit_id: Id, index: usize) {
@@ -562,6 +662,21 @@ impl AutoSuggester {
         sug
     }
 
+    pub fn graphql(words: &[String]) -> Self {
+        let mut sug = gen_suggest!(
+            "query" => "query {\n  \n}",
+            "mutation" => "mutation {\n  \n}",
+            "subscription" => "subscription {\n  \n}",
+            "fragment" => "fragment Name on Type {\n  \n}",
+            "__typename"
+        );
+        for word in words {
+            let value = word.clone();
+            sug.insert(word.clone(), Box::new(move |_s| value.clone()), 2);
+        }
+        sug
+    }
+
     pub fn json_schema() -> Self {
         let sug = gen_suggest!(
             "type",
@@ -646,6 +761,44 @@ fn add_brackets(s: &str) -> String {
     "<".to_owned() + s + ">"
 }
 
+///增删行后断点跟着原来的行走,被删掉的行上的断点去掉
+fn shift_breakpoints(breakpoints: &mut BTreeSet<usize>, old: &str, new: &str) {
+    let old_lines: Vec<&str> = old.split('\n').collect();
+    let new_lines: Vec<&str> = new.split('\n').collect();
+    if old_lines.len() == new_lines.len() {
+        return;
+    }
+    let shortest = old_lines.len().min(new_lines.len());
+    let prefix = old_lines
+        .iter()
+        .zip(&new_lines)
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(shortest);
+    let suffix = old_lines
+        .iter()
+        .rev()
+        .zip(new_lines.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count()
+        .min(shortest - prefix);
+    //改动范围是prefix之后、倒数suffix行之前,行号从1开始
+    let old_end = old_lines.len() - suffix;
+    let new_end = new_lines.len() - suffix;
+    *breakpoints = breakpoints
+        .iter()
+        .filter_map(|&line| {
+            if line <= prefix || (line <= old_end && line <= new_end) {
+                Some(line)
+            } else if line > old_end {
+                Some(line + new_end - old_end)
+            } else {
+                None
+            }
+        })
+        .collect();
+}
+
 fn add_quoter(s: &str) -> String {
     "\"".to_owned() + s + "\""
 }
@@ -665,4 +818,15 @@ mod tests {
         println!("{}", sug.get_action("keys").unwrap()("ss"));
         println!("{}", sug.get_action("key1").unwrap()("ss"));
     }
+
+    #[test]
+    fn test_shift_breakpoints() {
+        let mut breakpoints = BTreeSet::from([1, 3, 4]);
+        shift_breakpoints(&mut breakpoints, "a\nb\nc\nd", "a\nb\nc\nx\ny\nd");
+        assert_eq!(breakpoints, BTreeSet::from([1, 3, 6]));
+        shift_breakpoints(&mut breakpoints, "a\nb\nc\nx\ny\nd", "a\nd");
+        assert_eq!(breakpoints, BTreeSet::from([1, 2]));
+        shift_breakpoints(&mut breakpoints, "a\nd", "a\nd");
+        assert_eq!(breakpoints, BTreeSet::from([1, 2]));
+    }
 }
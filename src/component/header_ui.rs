@@ -3,7 +3,7 @@
 
 use super::syntax_highlight::{highlight_temp_key};
 
-#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
 pub struct SelectKeyValueItem {
     pub selected: bool,
     pub key: String,
@@ -24,6 +24,7 @@ pub enum Action {
 pub enum NodeType {
     Collection,
     Node,
+    WebSocket,
 }
 
 #[derive(serde::Deserialize, serde::Serialize)]
@@ -62,6 +63,40 @@ impl TreeUi {
         self.selected
     }
 
+    ///节点到根的id路径,和选中时的顺序一致:自己在前,根(0)在最后
+    pub fn node_path(&self, id: u64) -> Vec<u64> {
+        self.sub_node.find_path(id).unwrap_or_else(|| vec![id])
+    }
+
+    ///节点下所有子孙节点的id,按树的顺序
+    pub fn descendants(&self, id: u64) -> Vec<u64> {
+        let mut ids = Vec::new();
+        if let Some(node) = self.sub_node.find(id) {
+            node.collect_ids(&mut ids);
+        }
+        ids
+    }
+
+    ///直接子节点的id
+    pub fn children(&self, id: u64) -> Vec<u64> {
+        self.sub_node
+            .find(id)
+            .map(|node| node.sub_items.iter().map(|sub| sub.id).collect())
+            .unwrap_or_default()
+    }
+
+    ///任意节点的标题
+    pub fn title(&self, id: u64) -> Option<String> {
+        self.sub_node.find(id).map(|node| node.title.clone())
+    }
+
+    ///修改任意节点的标题
+    pub fn set_title(&mut self, id: u64, title: &str) {
+        let mut path = self.node_path(id);
+        let _node_id = path.pop();
+        self.sub_node.rename(path, title);
+    }
+
     pub fn pre_action(&self) -> Action {
         self.action_tmp.clone()
     }
@@ -103,6 +138,16 @@ impl TreeUi {
         self.sub_node.add_recusive(add, new_id, title, node_type)
     }
 
+    ///用指定的标题添加节点,返回新节点的id,导入时使用
+    pub fn add_with_title(&mut self, mut add: Vec<u64>, title: &str, node_type: NodeType) -> Option<u64> {
+        let _node_id = add.pop();
+        self.id_count = self.id_count + 1;
+        let new_id = self.id_count;
+        self.sub_node
+            .add_recusive(add, new_id, title, node_type)
+            .then_some(new_id)
+    }
+
     pub fn ui_impl(&mut self, ui: &mut Ui) -> Action {
         //传递打开动作
         let mut open_action = None;
@@ -159,8 +204,13 @@ impl TreeUi {
                         Action::Keep
                     }
                 }
-                Action::Add((adds, NodeType::Node)) => {
-                    if let Some(add_resp) = Window::new("添加节点")
+                Action::Add((adds, node_type @ (NodeType::Node | NodeType::WebSocket))) => {
+                    let title = if node_type == NodeType::WebSocket {
+                        "添加WebSocket"
+                    } else {
+                        "添加节点"
+                    };
+                    if let Some(add_resp) = Window::new(title)
                         .anchor(Align2::CENTER_CENTER, (1.0, 1.0))
                         .collapsible(false)
                         .show(ui.ctx(), |ui| {
@@ -168,8 +218,8 @@ impl TreeUi {
                             ui.horizontal(|ui| {
                                 if ui.button("确认").clicked() {
                                     self.popup = false;
-                                    if self.add(adds, NodeType::Node) {
-                                        return Action::Add((vec![self.id_count], NodeType::Node));
+                                    if self.add(adds, node_type) {
+                                        return Action::Add((vec![self.id_count], node_type));
                                     }
                                 }
                                 if ui.button("取消").clicked() {
@@ -307,6 +357,31 @@ impl TreeUiNode {
         true
     }
 
+    pub fn find_path(&self, id: u64) -> Option<Vec<u64>> {
+        if self.id == id {
+            return Some(vec![id]);
+        }
+        self.sub_items.iter().find_map(|sub| {
+            let mut path = sub.find_path(id)?;
+            path.push(self.id);
+            Some(path)
+        })
+    }
+
+    pub fn find(&self, id: u64) -> Option<&TreeUiNode> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.sub_items.iter().find_map(|sub| sub.find(id))
+    }
+
+    fn collect_ids(&self, ids: &mut Vec<u64>) {
+        for sub in self.sub_items.iter() {
+            ids.push(sub.id);
+            sub.collect_ids(ids);
+        }
+    }
+
     pub fn find_node(&mut self, id: u64) -> Option<&mut TreeUiNode> {
         self.sub_items.iter_mut().find(|node| node.id == id)
     }
@@ -405,7 +480,7 @@ impl TreeUiNode {
         // }
 
         match self.node_type {
-            NodeType::Node => {
+            NodeType::Node | NodeType::WebSocket => {
                 if !self.title.contains(flilter) {
                     return Action::Keep;
                 }
@@ -418,8 +493,12 @@ impl TreeUiNode {
                     }
 
                     let mut context_resp = Option::None;
-                    let select_resp = ui
-                        .toggle_value(&mut selected, self.title.clone());
+                    let title = if self.node_type == NodeType::WebSocket {
+                        format!("🌐 {}", self.title)
+                    } else {
+                        self.title.clone()
+                    };
+                    let select_resp = ui.toggle_value(&mut selected, title);
 
 
                         select_resp.context_menu(|ui| {
@@ -428,7 +507,7 @@ impl TreeUiNode {
                             //     context_resp =
                             //         Some(Action::Add((vec![self.id], NodeType::Collection)));
                             // }
-                            if ui.button("复制节点").clicked() {
+                            if self.node_type == NodeType::Node && ui.button("复制节点").clicked() {
                                 ui.close_menu();
                                 context_resp = Some(Action::Copy((self.id, self.title.clone())));
                             }
@@ -488,6 +567,11 @@ impl TreeUiNode {
                                     context_resp =
                                         Some(Action::Add((vec![self.id], NodeType::Node)));
                                 }
+                                if ui.button("添加WebSocket").clicked() {
+                                    ui.close_menu();
+                                    context_resp =
+                                        Some(Action::Add((vec![self.id], NodeType::WebSocket)));
+                                }
                                 if ui.button("粘贴节点").clicked() {
                                     ui.close_menu();
                                     context_resp = Some(Action::Parse(vec![self.id]));
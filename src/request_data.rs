@@ -1,10 +1,17 @@
 use std::{collections::HashMap, fmt::Debug, str::FromStr};
 
-use crate::{component::header_ui::SelectKeyValueItem, utils::template::rander_template};
+use crate::{
+    component::header_ui::SelectKeyValueItem,
+    utils::{
+        assertion::TestResult, auth::AuthData, client::ClientSettings,
+        template::rander_template,
+    },
+};
 use hdrhistogram::Histogram;
 use log::info;
 use minijinja::value::Value as JValue;
-use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_TYPE};
 #[cfg(not(target_arch = "wasm32"))]
 use reqwest::{Request, Response};
 use serde::{Deserialize, Serialize};
@@ -22,7 +29,28 @@ pub struct RequestData {
     pub url: String,
     pub method: Method,
     pub headers: Vec<SelectKeyValueItem>,
+    ///查询参数,和url里的查询串双向同步
+    #[serde(default)]
+    pub params: Vec<SelectKeyValueItem>,
+    ///路径参数,对应url里的`:id`和`{id}`
+    #[serde(default)]
+    pub path_params: Vec<SelectKeyValueItem>,
     pub body: String,
+    #[serde(default)]
+    pub body_type: BodyType,
+    #[serde(default)]
+    pub form: Vec<SelectKeyValueItem>,
+    #[serde(default)]
+    pub multipart: Vec<MultipartItem>,
+    ///二进制请求体的文件路径
+    #[serde(default)]
+    pub binary: String,
+    #[serde(default)]
+    pub graphql: GraphQLBody,
+    #[serde(default)]
+    pub auth: AuthData,
+    #[serde(default)]
+    pub settings: ClientSettings,
 }
 impl Default for RequestData {
     fn default() -> Self {
@@ -31,7 +59,99 @@ impl Default for RequestData {
             url: Default::default(),
             method: Method::GET,
             headers: Default::default(),
+            params: Default::default(),
+            path_params: Default::default(),
             body: Default::default(),
+            body_type: Default::default(),
+            form: Default::default(),
+            multipart: Default::default(),
+            binary: Default::default(),
+            graphql: Default::default(),
+            auth: Default::default(),
+            settings: Default::default(),
+        }
+    }
+}
+
+///请求体类型
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum BodyType {
+    None,
+    ///原样发送
+    Raw,
+    Json,
+    ///按JSON5解析后转成JSON发送
+    #[default]
+    Json5,
+    FormUrlencoded,
+    Multipart,
+    ///从文件读取
+    Binary,
+    ///query和variables分开编辑,发送时组装成JSON
+    GraphQL,
+}
+
+impl BodyType {
+    pub const ALL: [BodyType; 8] = [
+        BodyType::None,
+        BodyType::Raw,
+        BodyType::Json,
+        BodyType::Json5,
+        BodyType::FormUrlencoded,
+        BodyType::Multipart,
+        BodyType::Binary,
+        BodyType::GraphQL,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BodyType::None => "none",
+            BodyType::Raw => "raw",
+            BodyType::Json => "json",
+            BodyType::Json5 => "json5",
+            BodyType::FormUrlencoded => "x-www-form-urlencoded",
+            BodyType::Multipart => "form-data",
+            BodyType::Binary => "binary",
+            BodyType::GraphQL => "graphql",
+        }
+    }
+
+    ///自动设置的Content-Type,multipart的由boundary决定
+    pub fn content_type(&self) -> Option<&'static str> {
+        match self {
+            BodyType::None | BodyType::Raw | BodyType::Multipart => None,
+            BodyType::Json | BodyType::Json5 | BodyType::GraphQL => Some("application/json"),
+            BodyType::FormUrlencoded => Some("application/x-www-form-urlencoded"),
+            BodyType::Binary => Some("application/octet-stream"),
+        }
+    }
+}
+
+///GraphQL请求体,定义在request_data里是因为要跟着请求保存
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GraphQLBody {
+    pub query: String,
+    ///JSON5格式
+    pub variables: String,
+}
+
+///multipart/form-data的一项,文件项的value是文件路径
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+pub struct MultipartItem {
+    pub selected: bool,
+    pub is_file: bool,
+    pub key: String,
+    pub value: String,
+}
+
+impl MultipartItem {
+    pub fn new(key: &str, value: &str, is_file: bool) -> Self {
+        Self {
+            selected: true,
+            is_file,
+            key: key.into(),
+            value: value.into(),
         }
     }
 }
@@ -40,9 +160,52 @@ impl Default for RequestData {
 pub struct ResponseData {
     pub headers: Vec<SelectKeyValueItem>,
     pub body: String,
+    ///实际收到的响应体字节数
     pub size: u64,
     pub code: String,
     pub time: i64,
+    ///状态行和响应头的字节数
+    #[serde(default)]
+    pub header_size: u64,
+    ///发出的请求字节数,含请求行、请求头和请求体
+    #[serde(default)]
+    pub send_size: u64,
+    #[serde(default)]
+    pub timing: Timing,
+    ///SSE事件或NDJSON的每一行,流式响应才有
+    #[serde(default)]
+    pub events: Vec<StreamEvent>,
+    ///提取变量失败的原因,在响应面板里显示
+    #[serde(default)]
+    pub extract_errors: Vec<String>,
+    ///后置脚本里断言的结果
+    #[serde(default)]
+    pub tests: Vec<TestResult>,
+}
+
+///流式响应里的一条事件,NDJSON的一行只有data
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct StreamEvent {
+    pub time: String,
+    pub id: String,
+    pub event: String,
+    pub data: String,
+    pub retry: Option<u64>,
+}
+
+///请求各阶段耗时,毫秒
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Timing {
+    pub dns: i64,
+    ///TCP连接,复用连接时为0
+    pub connect: i64,
+    ///TLS握手,明文HTTP或复用连接时为0
+    pub tls: i64,
+    ///从发出请求到收到响应头
+    pub ttfb: i64,
+    pub download: i64,
 }
 
 #[derive(Debug, Clone, Default, serde::Deserialize, serde::Serialize)]
@@ -53,6 +216,9 @@ pub struct LoadTestData {
     #[serde(skip)]
     pub result: LoadTestResult,
     pub result_list: Vec<i64>,
+    ///手动停止的压测,结果只包含已完成的请求
+    #[serde(default)]
+    pub aborted: bool,
 }
 
 impl LoadTestData {
@@ -162,6 +328,343 @@ impl std::fmt::Display for Method {
     }
 }
 
+impl RequestData {
+    ///导入的节点标题
+    pub fn default_title(&self) -> String {
+        let path = reqwest::Url::parse(&self.url)
+            .map(|u| u.path().to_owned())
+            .unwrap_or_else(|_| self.url.clone());
+        format!("{} {}", self.method, path)
+    }
+
+    ///url改动后同步查询参数和路径参数,未勾选的查询参数保留
+    pub fn sync_params_from_url(&mut self) {
+        let mut params: Vec<SelectKeyValueItem> = parse_query(&self.url)
+            .into_iter()
+            .map(|(k, v)| SelectKeyValueItem::new(&k, &v))
+            .collect();
+        params.extend(self.params.drain(..).filter(|p| !p.selected));
+        self.params = params;
+
+        let path_params = path_param_names(&self.url)
+            .into_iter()
+            .map(|name| {
+                let value = self
+                    .path_params
+                    .iter()
+                    .find(|p| p.key == name)
+                    .map(|p| p.value.clone())
+                    .unwrap_or_default();
+                SelectKeyValueItem::new(&name, &value)
+            })
+            .collect();
+        self.path_params = path_params;
+    }
+
+    ///查询参数改动后重写url里的查询串,键和值会编码,#后面的部分保留
+    pub fn sync_url_from_params(&mut self) {
+        let (url, fragment) = match self.url.split_once('#') {
+            Some((url, fragment)) => (url, Some(fragment)),
+            None => (self.url.as_str(), None),
+        };
+        let base = url.split('?').next().unwrap_or_default();
+        let query = self
+            .params
+            .iter()
+            .filter(|p| p.selected)
+            .map(|p| {
+                if p.value.is_empty() {
+                    encode_query_part(&p.key)
+                } else {
+                    format!(
+                        "{}={}",
+                        encode_query_part(&p.key),
+                        encode_query_part(&p.value)
+                    )
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("&");
+        let mut new_url = base.to_owned();
+        if !query.is_empty() {
+            new_url.push('?');
+            new_url.push_str(&query);
+        }
+        if let Some(fragment) = fragment {
+            new_url.push('#');
+            new_url.push_str(fragment);
+        }
+        self.url = new_url;
+    }
+
+    ///渲染模板,替换路径参数并编码查询参数后的真实url
+    pub fn render_url(&self) -> String {
+        let (base, query) = match self.url.split_once('?') {
+            Some((base, query)) => (base, Some(query)),
+            None => (self.url.as_str(), None),
+        };
+        let mut url = rander_template(base).unwrap_or_else(|_| base.to_owned());
+        for param in self.path_params.iter() {
+            let value = rander_template(&param.value).unwrap_or_else(|_| param.value.clone());
+            let encoded = utf8_percent_encode(&value, PATH_SEGMENT).to_string();
+            url = replace_path_param(&url, &param.key, &encoded);
+        }
+        if self.params.is_empty() {
+            if let Some(query) = query {
+                let query = rander_template(query).unwrap_or_else(|_| query.to_owned());
+                url.push('?');
+                url.push_str(&query);
+            }
+        } else {
+            let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+            for param in self.params.iter().filter(|p| p.selected) {
+                let value = rander_template(&param.value).unwrap_or_else(|_| param.value.clone());
+                serializer.append_pair(&param.key, &value);
+            }
+            let query = serializer.finish();
+            if !query.is_empty() {
+                url.push('?');
+                url.push_str(&query);
+            }
+        }
+        url
+    }
+
+    ///按请求体类型渲染模板并编码,返回(自动的Content-Type,请求体)
+    pub fn render_body(&self) -> Result<(Option<String>, Option<Vec<u8>>), String> {
+        let content_type = self.body_type.content_type().map(str::to_owned);
+        match self.body_type {
+            BodyType::None => Ok((None, None)),
+            BodyType::Raw | BodyType::Json => {
+                if self.body.is_empty() {
+                    return Ok((None, None));
+                }
+                let deal_temp = rander_template(&self.body).unwrap_or_else(|_| self.body.clone());
+                Ok((content_type, Some(deal_temp.into_bytes())))
+            }
+            BodyType::Json5 => {
+                if self.body.is_empty() {
+                    return Ok((None, None));
+                }
+                let deal_temp = rander_template(&self.body).unwrap_or_else(|_| self.body.clone());
+                let paser_body = match json5::from_str::<Value>(&deal_temp) {
+                    Ok(json_body) => {
+                        serde_json::to_string_pretty(&json_body).unwrap_or(deal_temp)
+                    }
+                    Err(_) => deal_temp,
+                };
+                Ok((content_type, Some(paser_body.into_bytes())))
+            }
+            BodyType::FormUrlencoded => {
+                let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+                for item in self.form.iter().filter(|item| item.selected) {
+                    let value = rander_template(&item.value).unwrap_or_else(|_| item.value.clone());
+                    serializer.append_pair(&item.key, &value);
+                }
+                Ok((content_type, Some(serializer.finish().into_bytes())))
+            }
+            BodyType::Multipart => {
+                let boundary = format!("----HttpTestToolBoundary{}", uuid::Uuid::new_v4().simple());
+                let body = encode_multipart(&self.multipart, &boundary)?;
+                Ok((
+                    Some(format!("multipart/form-data; boundary={}", boundary)),
+                    Some(body),
+                ))
+            }
+            BodyType::Binary => {
+                if self.binary.is_empty() {
+                    return Ok((None, None));
+                }
+                let bytes = std::fs::read(&self.binary)
+                    .map_err(|e| format!("读取文件{}失败:{}", self.binary, e))?;
+                Ok((content_type, Some(bytes)))
+            }
+            BodyType::GraphQL => {
+                let body = self.graphql.render()?;
+                Ok((content_type, Some(body.to_string().into_bytes())))
+            }
+        }
+    }
+}
+
+///路径段里需要编码的字符
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+///查询串里的键和值要编码的字符,和parse_query的解码对应
+const QUERY_COMPONENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'&')
+    .add(b'+')
+    .add(b'=')
+    .add(b'<')
+    .add(b'>')
+    .add(b'`');
+
+///编码查询参数的键或值,`${…}`模板原样保留,发送时再渲染
+fn encode_query_part(text: &str) -> String {
+    let mut encoded = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        let end = start + len + 1;
+        encoded.extend(utf8_percent_encode(&rest[..start], QUERY_COMPONENT));
+        encoded.push_str(&rest[start..end]);
+        rest = &rest[end..];
+    }
+    encoded.extend(utf8_percent_encode(rest, QUERY_COMPONENT));
+    encoded
+}
+
+///解析url里的查询串,没有值的键保留为空字符串
+pub fn parse_query(url: &str) -> Vec<(String, String)> {
+    match url.split_once('?') {
+        Some((_, query)) => {
+            let query = query.split('#').next().unwrap_or_default();
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+///url路径里的参数名,支持`:id`和`{id}`,不会把模板`${id}`当成参数
+pub fn path_param_names(url: &str) -> Vec<String> {
+    let path = url.split('?').next().unwrap_or_default();
+    let path = match path.find("://") {
+        Some(p) => match path[p + 3..].find('/') {
+            Some(s) => &path[p + 3 + s..],
+            None => "",
+        },
+        None => path,
+    };
+    let mut names = Vec::new();
+    for segment in path.split('/') {
+        if let Some(name) = segment.strip_prefix(':') {
+            if !name.is_empty() && !names.iter().any(|n| n == name) {
+                names.push(name.to_owned());
+            }
+            continue;
+        }
+        let mut rest = segment;
+        let mut prev = None;
+        while let Some(start) = rest.find('{') {
+            let before = rest[..start].chars().last().or(prev);
+            let after = &rest[start + 1..];
+            let Some(end) = after.find('}') else {
+                break;
+            };
+            if !matches!(before, Some('$') | Some('%') | Some('#')) {
+                let name = &after[..end];
+                if !name.is_empty() && !names.iter().any(|n| n == name) {
+                    names.push(name.to_owned());
+                }
+            }
+            prev = Some('}');
+            rest = &after[end + 1..];
+        }
+    }
+    names
+}
+
+fn replace_path_param(url: &str, name: &str, value: &str) -> String {
+    let brace = format!("{{{}}}", name);
+    let colon = format!(":{}", name);
+    let mut result = String::new();
+    let mut rest = url;
+    while let Some(start) = rest.find(&brace) {
+        let escaped = rest[..start].ends_with(['$', '%', '#']);
+        result.push_str(&rest[..start]);
+        if escaped {
+            result.push_str(&brace);
+        } else {
+            result.push_str(value);
+        }
+        rest = &rest[start + brace.len()..];
+    }
+    result.push_str(rest);
+    result
+        .split('/')
+        .map(|segment| if segment == colon { value } else { segment })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+///把content_type里的boundary换成generated里的
+fn replace_boundary(content_type: &str, generated: &str) -> String {
+    let boundary = generated
+        .split(';')
+        .map(str::trim)
+        .find(|param| param.starts_with("boundary="));
+    let mut params: Vec<&str> = content_type
+        .split(';')
+        .map(str::trim)
+        .filter(|param| {
+            !param.is_empty()
+                && !param
+                    .get(..9)
+                    .is_some_and(|name| name.eq_ignore_ascii_case("boundary="))
+        })
+        .collect();
+    params.extend(boundary);
+    params.join("; ")
+}
+
+fn encode_multipart(items: &[MultipartItem], boundary: &str) -> Result<Vec<u8>, String> {
+    let mut body: Vec<u8> = Vec::new();
+    for item in items.iter().filter(|item| item.selected) {
+        body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+        let name = item.key.replace('"', "%22");
+        if item.is_file {
+            let path = std::path::Path::new(&item.value);
+            let file_name = path
+                .file_name()
+                .map(|f| f.to_string_lossy().replace('"', "%22"))
+                .unwrap_or_default();
+            let bytes =
+                std::fs::read(path).map_err(|e| format!("读取文件{}失败:{}", item.value, e))?;
+            let mime = mime_guess::from_path(path).first_or_octet_stream();
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                    name, file_name, mime
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(&bytes);
+        } else {
+            let value = rander_template(&item.value).unwrap_or_else(|_| item.value.clone());
+            body.extend_from_slice(
+                format!(
+                    "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                    name
+                )
+                .as_bytes(),
+            );
+            body.extend_from_slice(value.as_bytes());
+        }
+        body.extend_from_slice(b"\r\n");
+    }
+    body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+    Ok(body)
+}
+
 #[cfg(not(target_arch = "wasm32"))]
 impl TryInto<Request> for RequestData {
     type Error = String;
@@ -169,10 +672,9 @@ impl TryInto<Request> for RequestData {
     fn try_into(self) -> Result<Request, Self::Error> {
         let mth_bytes = self.method.to_string();
         let mth = reqwest::Method::from_bytes(mth_bytes.as_bytes()).expect("请求方法解析错误");
-        let url_tmp = self.url.as_str();
-        let rander_url = rander_template(url_tmp).unwrap_or_else(|_| url_tmp.to_owned());
+        let rander_url = self.render_url();
         let url = reqwest::Url::parse(&rander_url).map_err(|e| e.to_string())?;
-        let headers = self.headers.into_iter().filter(|slk| slk.selected).fold(
+        let headers = self.headers.iter().filter(|slk| slk.selected).fold(
             HeaderMap::new(),
             |mut headmap, slk| {
                 let k = HeaderName::from_str(slk.key.as_str()).unwrap();
@@ -191,24 +693,32 @@ impl TryInto<Request> for RequestData {
             },
         );
         let mut req = Request::new(mth, url);
+        let (content_type, body) = self.render_body()?;
         *req.headers_mut() = headers;
-        if !self.body.is_empty() {
-            let deal_temp = match rander_template(&self.body) {
-                Ok(parsed_temp) => parsed_temp,
-                Err(e) => {
-                    let mut msg = "模板语法错误：".to_string();
-                    msg.push_str(e.to_string().as_str());
-                    self.body.clone()
-                }
-            };
-            let paser_body = match json5::from_str::<Value>(&deal_temp) {
-                Ok(json_body) => {
-                    serde_json::to_string_pretty(&json_body).unwrap_or(self.body.clone())
+        if let Some(content_type) = content_type {
+            let user = req
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok());
+            let value = match user {
+                //请求体按生成的boundary编码,手动写的Content-Type只保留类型和其他参数
+                Some(user) if self.body_type == BodyType::Multipart => {
+                    Some(replace_boundary(user, &content_type))
                 }
-                Err(_) => self.body.clone(),
+                Some(_) => None,
+                None => Some(content_type),
             };
-            *req.body_mut() = Some(paser_body.into());
+            if let Some(value) = value {
+                req.headers_mut().insert(
+                    CONTENT_TYPE,
+                    HeaderValue::from_str(&value).map_err(|e| e.to_string())?,
+                );
+            }
+        }
+        if let Some(body) = body {
+            *req.body_mut() = Some(body.into());
         }
+        self.auth.apply(&mut req);
         Ok(req)
     }
 }
@@ -227,40 +737,47 @@ impl Into<PreRequest> for &RequestData {
                 headmap
             },
         );
-        let mut parse_querys = url.as_str();
-        let mut querys_map: HashMap<String, String> = HashMap::new();
-        if let Some(q) = parse_querys.find('?') {
-            if q + 1 < parse_querys.len() {
-                parse_querys = &parse_querys[q + 1..];
-                loop {
-                    let querys;
-                    if parse_querys.is_empty() {
-                        break;
-                    }
-                    if let Some(g) = parse_querys.find('&') {
-                        querys = &parse_querys[..g];
-                        parse_querys = &parse_querys[g + 1..];
-                    } else {
-                        querys = parse_querys;
-                        parse_querys = "";
-                    }
-                    if !querys.ends_with('=') {
-                        if let Some(eq_p) = querys.find('=') {
-                            let key = &querys[..eq_p];
-                            let value = &querys[eq_p + 1..];
-                            querys_map.insert(key.to_string(), value.to_string());
-                        }
-                    }
+        let querys_map: HashMap<String, String> = if self.params.is_empty() {
+            parse_query(&url).into_iter().collect()
+        } else {
+            self.params
+                .iter()
+                .filter(|slk| slk.selected)
+                .map(|slk| (slk.key.clone(), slk.value.clone()))
+                .collect()
+        };
+
+        let body: JValue = match self.body_type {
+            BodyType::FormUrlencoded => {
+                let form: HashMap<String, String> = self
+                    .form
+                    .iter()
+                    .filter(|slk| slk.selected)
+                    .map(|slk| (slk.key.clone(), slk.value.clone()))
+                    .collect();
+                JValue::from_serializable(&form)
+            }
+            BodyType::Multipart => {
+                let form: HashMap<String, String> = self
+                    .multipart
+                    .iter()
+                    .filter(|item| item.selected)
+                    .map(|item| (item.key.clone(), item.value.clone()))
+                    .collect();
+                JValue::from_serializable(&form)
+            }
+            BodyType::GraphQL => match self.graphql.render() {
+                Ok(body) => JValue::from_serializable(&body),
+                Err(_) => JValue::from_serializable(&self.graphql),
+            },
+            _ => {
+                if let Ok(json_value) = serde_json::from_str::<JValue>(&self.body) {
+                    json_value
+                } else {
+                    JValue::from_serializable(&self.body)
                 }
             }
-        }
-
-        let body: JValue;
-        if let Ok(json_value) = serde_json::from_str::<JValue>(&self.body) {
-            body = json_value;
-        } else {
-            body = JValue::from_serializable(&self.body);
-        }
+        };
         PreRequest {
             method: mth_bytes,
             querys: querys_map,
@@ -297,11 +814,11 @@ impl Into<PreResponse> for &ResponseData {
     }
 }
 
+///只有状态码和响应头,响应体另外读取
 #[cfg(not(target_arch = "wasm32"))]
-pub async fn covert_to_ui(value: Response) -> ResponseData {
+pub fn response_head(value: &Response) -> ResponseData {
     let code = value.status().to_string();
     let mut headers: Vec<SelectKeyValueItem> = Vec::new();
-    let mut is_json = false;
     for (key, value) in value.headers().into_iter() {
         let mut item = SelectKeyValueItem::new("", "");
         item.key = key.to_string();
@@ -309,16 +826,29 @@ pub async fn covert_to_ui(value: Response) -> ResponseData {
             Ok(ok) => ok.to_string(),
             Err(er) => er.to_string(),
         };
-        if item.key.eq_ignore_ascii_case("content-type") && item.value.contains("application/json")
-        {
-            is_json = true;
-        }
         headers.push(item);
     }
+    ResponseData {
+        headers,
+        code,
+        header_size: response_header_size(value),
+        ..Default::default()
+    }
+}
 
-    let size = value.content_length().unwrap_or(0);
+#[cfg(not(target_arch = "wasm32"))]
+pub async fn covert_to_ui(value: Response) -> ResponseData {
+    let mut resp = response_head(&value);
+    let is_json = resp.headers.iter().any(|item| {
+        item.key.eq_ignore_ascii_case("content-type") && item.value.contains("application/json")
+    });
 
-    let body: String = match value.text().await {
+    let start = std::time::Instant::now();
+    let mut size = 0;
+    let body: String = match value.bytes().await.map(|bytes| {
+        size = bytes.len() as u64;
+        String::from_utf8_lossy(&bytes).into_owned()
+    }) {
         Ok(body) => {
             if is_json {
                 if let Ok(json) = serde_json::from_str::<Value>(body.as_str()) {
@@ -333,13 +863,43 @@ pub async fn covert_to_ui(value: Response) -> ResponseData {
         Err(err) => err.to_string(),
     };
 
-    ResponseData {
-        headers,
-        body,
-        size,
-        code,
-        time: 0,
-    }
+    resp.body = body;
+    resp.size = size;
+    resp.timing.download = start.elapsed().as_millis() as i64;
+    resp
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn headers_size(headers: &HeaderMap) -> u64 {
+    headers
+        .iter()
+        .map(|(k, v)| (k.as_str().len() + v.len() + 4) as u64)
+        .sum()
+}
+
+///按HTTP/1.1的格式估算响应头大小
+#[cfg(not(target_arch = "wasm32"))]
+fn response_header_size(resp: &Response) -> u64 {
+    let status_line = format!("{:?} {}\r\n", resp.version(), resp.status());
+    status_line.len() as u64 + headers_size(resp.headers()) + 2
+}
+
+///按HTTP/1.1的格式计算请求的字节数,流式的请求体不计入
+#[cfg(not(target_arch = "wasm32"))]
+pub fn request_size(req: &Request) -> u64 {
+    let url = req.url();
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_owned(),
+    };
+    let request_line = format!("{} {} HTTP/1.1\r\n", req.method(), target);
+    let host = format!("host: {}\r\n", url.host_str().unwrap_or_default());
+    let body = req
+        .body()
+        .and_then(|b| b.as_bytes())
+        .map(|b| b.len())
+        .unwrap_or(0);
+    (request_line.len() + host.len() + 2 + body) as u64 + headers_size(req.headers())
 }
 
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -363,3 +923,165 @@ pub struct PreResponse {
     pub body: JValue,
     pub code: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_form_urlencoded_body() {
+        let mut req = RequestData::default();
+        req.body_type = BodyType::FormUrlencoded;
+        req.form.push(SelectKeyValueItem::new("name", "张 三"));
+        req.form.push(SelectKeyValueItem::new("a&b", "1=2"));
+        let mut skip = SelectKeyValueItem::new("skip", "x");
+        skip.selected = false;
+        req.form.push(skip);
+        let (content_type, body) = req.render_body().unwrap();
+        assert_eq!(
+            content_type.as_deref(),
+            Some("application/x-www-form-urlencoded")
+        );
+        let body = String::from_utf8(body.unwrap()).unwrap();
+        assert_eq!(body, "name=%E5%BC%A0+%E4%B8%89&a%26b=1%3D2");
+    }
+
+    #[test]
+    fn test_multipart_body() {
+        let mut req = RequestData::default();
+        req.body_type = BodyType::Multipart;
+        req.multipart.push(MultipartItem::new("field", "value", false));
+        let (content_type, body) = req.render_body().unwrap();
+        let content_type = content_type.unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        let body = String::from_utf8(body.unwrap()).unwrap();
+        assert_eq!(
+            body,
+            format!(
+                "--{b}\r\nContent-Disposition: form-data; name=\"field\"\r\n\r\nvalue\r\n--{b}--\r\n",
+                b = boundary
+            )
+        );
+    }
+
+    #[test]
+    fn test_multipart_user_content_type() {
+        let mut req = RequestData {
+            url: "http://localhost/upload".to_owned(),
+            body_type: BodyType::Multipart,
+            ..Default::default()
+        };
+        req.headers.push(SelectKeyValueItem::new(
+            "Content-Type",
+            "multipart/form-data; boundary=stale",
+        ));
+        req.multipart
+            .push(MultipartItem::new("field", "value", false));
+        let request: Request = req.try_into().unwrap();
+        let content_type = request.headers()[CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type
+            .strip_prefix("multipart/form-data; boundary=")
+            .unwrap();
+        assert_ne!(boundary, "stale");
+        let body = String::from_utf8_lossy(request.body().unwrap().as_bytes().unwrap());
+        assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+
+        assert_eq!(
+            replace_boundary(
+                "multipart/related; Boundary=x; type=\"a/b\"",
+                "m; boundary=y"
+            ),
+            "multipart/related; type=\"a/b\"; boundary=y"
+        );
+    }
+
+    #[test]
+    fn test_sync_params() {
+        let mut req = RequestData::default();
+        req.url = "http://localhost:8080/users/:id/{name}/${host}?a=1&b&c=${x}".to_owned();
+        req.sync_params_from_url();
+        let params: Vec<(&str, &str)> = req
+            .params
+            .iter()
+            .map(|p| (p.key.as_str(), p.value.as_str()))
+            .collect();
+        assert_eq!(params, vec![("a", "1"), ("b", ""), ("c", "${x}")]);
+        let names: Vec<&str> = req.path_params.iter().map(|p| p.key.as_str()).collect();
+        assert_eq!(names, vec!["id", "name"]);
+
+        req.params[0].selected = false;
+        req.params.push(SelectKeyValueItem::new("d", "4"));
+        req.sync_url_from_params();
+        assert_eq!(
+            req.url,
+            "http://localhost:8080/users/:id/{name}/${host}?b&c=${x}&d=4"
+        );
+        req.sync_params_from_url();
+        assert_eq!(req.params.len(), 4);
+        assert!(!req.params[3].selected);
+    }
+
+    #[test]
+    fn test_sync_params_round_trip() {
+        let mut req = RequestData::default();
+        req.url = "http://localhost/search#top".to_owned();
+        let values = ["a&b", "x=y", "c#d", "1+1", "a b", "50%", "${token}&${id}"];
+        req.params = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| SelectKeyValueItem::new(&format!("k {}", i), v))
+            .collect();
+        req.sync_url_from_params();
+        assert!(req.url.ends_with("&k%206=${token}%26${id}#top"));
+        req.sync_params_from_url();
+        let params: Vec<(String, String)> = req
+            .params
+            .iter()
+            .map(|p| (p.key.clone(), p.value.clone()))
+            .collect();
+        let expected: Vec<(String, String)> = values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (format!("k {}", i), v.to_string()))
+            .collect();
+        assert_eq!(params, expected);
+    }
+
+    #[test]
+    fn test_render_url() {
+        let mut req = RequestData::default();
+        req.url = "http://localhost/users/:id/files/{name}?q=x".to_owned();
+        req.sync_params_from_url();
+        req.path_params[0].value = "1".to_owned();
+        req.path_params[1].value = "a b/c".to_owned();
+        req.params[0].value = "中 文&".to_owned();
+        assert_eq!(
+            req.render_url(),
+            "http://localhost/users/1/files/a%20b%2Fc?q=%E4%B8%AD+%E6%96%87%26"
+        );
+    }
+
+    #[test]
+    fn test_json5_body() {
+        let mut req = RequestData::default();
+        req.body = "{a:1,}".to_owned();
+        let (content_type, body) = req.render_body().unwrap();
+        assert_eq!(content_type.as_deref(), Some("application/json"));
+        let body: Value = serde_json::from_slice(&body.unwrap()).unwrap();
+        assert_eq!(body["a"], 1);
+    }
+
+    #[test]
+    fn test_request_size() {
+        let mut req = RequestData::default();
+        req.url = "http://example.com/a?b=1".to_owned();
+        req.method = Method::POST;
+        req.body_type = BodyType::Raw;
+        req.body = "hello".to_owned();
+        let real: Request = req.try_into().unwrap();
+        //请求行 + host + 空行 + 请求体
+        assert_eq!(request_size(&real), 22 + 19 + 2 + 5);
+    }
+}
@@ -0,0 +1,157 @@
+use egui::{Color32, RichText};
+
+use crate::component::header_ui::{HeaderUi, SelectKeyValueItem};
+use crate::component::password::password;
+use crate::utils::environment::Environments;
+use crate::utils::secret::{self, SecretVar, MASK};
+
+///环境的切换和编辑,改动后由调用方apply
+#[derive(Default)]
+pub struct EnvironmentUi {
+    ///正在编辑的环境,为空时编辑全局变量
+    editing: String,
+    new_name: String,
+    ///主密码输入框,用完就清空
+    master: String,
+    ///要设置给加密变量的值,用完就清空
+    secret_value: String,
+    error: String,
+}
+
+impl EnvironmentUi {
+    ///顶部菜单里的切换,返回是否切换了环境
+    pub fn switcher(ui: &mut egui::Ui, envs: &mut Environments) -> bool {
+        let mut changed = ui
+            .radio_value(&mut envs.active, String::new(), "不使用环境")
+            .clicked();
+        let names: Vec<String> = envs.envs.iter().map(|env| env.name.clone()).collect();
+        for name in names {
+            changed |= ui
+                .radio_value(&mut envs.active, name.clone(), name)
+                .clicked();
+        }
+        changed
+    }
+
+    ///返回变量是否有改动
+    pub fn editor(&mut self, ui: &mut egui::Ui, envs: &mut Environments) -> bool {
+        let before = envs.clone();
+        ui.horizontal_wrapped(|ui| {
+            ui.selectable_value(&mut self.editing, String::new(), "全局变量");
+            for env in envs.envs.iter() {
+                ui.selectable_value(&mut self.editing, env.name.clone(), &env.name);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.new_name)
+                    .hint_text("环境名")
+                    .desired_width(120.),
+            );
+            if ui.small_button("新建环境").clicked() && !self.new_name.trim().is_empty() {
+                self.editing = envs.add(self.new_name.trim());
+                self.new_name.clear();
+            }
+            if !self.editing.is_empty() && ui.small_button("删除环境").clicked() {
+                envs.remove(&self.editing);
+                self.editing.clear();
+            }
+        });
+        ui.separator();
+        self.vault_ui(ui, envs);
+        let vars = if self.editing.is_empty() {
+            Some((&mut envs.globals, &mut envs.secrets))
+        } else {
+            envs.envs
+                .iter_mut()
+                .find(|env| env.name == self.editing)
+                .map(|env| (&mut env.vars, &mut env.secrets))
+        };
+        match vars {
+            Some((vars, secrets)) => {
+                ui.horizontal(|ui| {
+                    ui.label("变量");
+                    if ui.small_button("➕").clicked() {
+                        vars.push(SelectKeyValueItem::new("", ""));
+                    }
+                    if ui.small_button("➖").clicked() {
+                        vars.retain(|item| item.selected);
+                    }
+                });
+                HeaderUi::ui_grid_input(ui, "environment_vars_grid", vars);
+                self.secrets_ui(ui, secrets);
+            }
+            None => self.editing.clear(),
+        }
+        *envs != before
+    }
+
+    fn vault_ui(&mut self, ui: &mut egui::Ui, envs: &mut Environments) {
+        ui.horizontal(|ui| {
+            if secret::is_unlocked() {
+                ui.label("主密码已解锁");
+                if ui.small_button("锁定").clicked() {
+                    secret::lock();
+                }
+                return;
+            }
+            ui.label("主密码");
+            ui.add(password(&mut self.master));
+            let result = if !envs.vault.is_initialized() {
+                if !ui.small_button("设置主密码").clicked() {
+                    return;
+                }
+                envs.vault.init(&self.master)
+            } else {
+                if !ui.small_button("解锁").clicked() {
+                    return;
+                }
+                envs.vault.unlock(&self.master)
+            };
+            self.master.clear();
+            self.error = result.err().unwrap_or_default();
+        });
+        if !self.error.is_empty() {
+            ui.label(RichText::new(&self.error).color(Color32::RED));
+        }
+    }
+
+    ///加密变量只显示掩码,新的值加密后才保存
+    fn secrets_ui(&mut self, ui: &mut egui::Ui, secrets: &mut Vec<SecretVar>) {
+        ui.horizontal(|ui| {
+            ui.label("加密变量");
+            if ui.small_button("➕").clicked() {
+                secrets.push(SecretVar::default());
+            }
+            if ui.small_button("➖").clicked() {
+                secrets.retain(|item| item.selected);
+            }
+        });
+        let unlocked = secret::is_unlocked();
+        ui.horizontal(|ui| {
+            ui.label("新的值");
+            ui.add_enabled_ui(unlocked, |ui| ui.add(password(&mut self.secret_value)));
+        });
+        egui::Grid::new("environment_secrets_grid")
+            .num_columns(4)
+            .show(ui, |ui| {
+                for item in secrets.iter_mut() {
+                    ui.checkbox(&mut item.selected, "");
+                    ui.add(egui::TextEdit::singleline(&mut item.key).desired_width(120.));
+                    ui.label(if item.cipher.is_empty() { "未设置" } else { MASK });
+                    let set = ui.add_enabled(
+                        unlocked && !self.secret_value.is_empty(),
+                        egui::Button::new("设置值").small(),
+                    );
+                    if set.clicked() {
+                        match secret::seal(&self.secret_value) {
+                            Ok(cipher) => item.cipher = cipher,
+                            Err(e) => self.error = e,
+                        }
+                        self.secret_value.clear();
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::BTreeMap;
+
+use egui::RichText;
+
+use crate::utils::extractor::{ExtractScope, ExtractSource, Extractor};
+
+pub struct ExtractorUi {}
+
+impl ExtractorUi {
+    ///collection是请求所在集合提取到的变量
+    pub fn ui(
+        ui: &mut egui::Ui,
+        extractors: &mut Vec<Extractor>,
+        extracted: &mut BTreeMap<String, String>,
+        collection: Option<&mut BTreeMap<String, String>>,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label("提取器");
+            if ui.small_button("➕").clicked() {
+                extractors.push(Extractor::default());
+            }
+            if ui.small_button("➖").clicked() {
+                extractors.retain(|item| item.selected);
+            }
+        });
+        ui.label(
+            RichText::new("响应到达后执行,JSONPath如$.data.token,正则有分组时取第一个分组")
+                .small()
+                .weak(),
+        );
+        ui.group(|ui| {
+            egui::Grid::new("extractor_grid")
+                .num_columns(5)
+                .min_col_width(10.)
+                .min_row_height(20.)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.label(RichText::new("变量名").strong());
+                    ui.label(RichText::new("来源").strong());
+                    ui.label(RichText::new("表达式").strong());
+                    ui.label(RichText::new("作用域").strong());
+                    ui.end_row();
+                    for (i, item) in extractors.iter_mut().enumerate() {
+                        ui.checkbox(&mut item.selected, "");
+                        ui.add(egui::TextEdit::singleline(&mut item.name).desired_width(100.));
+                        egui::ComboBox::from_id_source(("extractor_source", i))
+                            .selected_text(item.source.label())
+                            .show_ui(ui, |ui| {
+                                for source in ExtractSource::ALL {
+                                    ui.selectable_value(&mut item.source, source, source.label());
+                                }
+                            });
+                        ui.add_enabled(
+                            item.source != ExtractSource::Status,
+                            egui::TextEdit::singleline(&mut item.expr).desired_width(200.),
+                        );
+                        egui::ComboBox::from_id_source(("extractor_scope", i))
+                            .selected_text(item.scope.label())
+                            .show_ui(ui, |ui| {
+                                for scope in ExtractScope::ALL {
+                                    ui.selectable_value(&mut item.scope, scope, scope.label());
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+        });
+        Self::extracted_ui(ui, "请求变量", extracted);
+        if let Some(collection) = collection {
+            Self::extracted_ui(ui, "集合变量", collection);
+        }
+    }
+
+    ///已经提取到的值,环境变量在管理环境里看
+    fn extracted_ui(ui: &mut egui::Ui, title: &str, extracted: &mut BTreeMap<String, String>) {
+        if extracted.is_empty() {
+            return;
+        }
+        ui.collapsing(format!("{}({})", title, extracted.len()), |ui| {
+            let mut removed = None;
+            egui::Grid::new(("extracted_grid", title))
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for (key, value) in extracted.iter() {
+                        ui.label(key);
+                        ui.label(value);
+                        if ui.small_button("清除").clicked() {
+                            removed = Some(key.clone());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(key) = removed {
+                extracted.remove(&key);
+            }
+        });
+    }
+}
@@ -1,5 +1,10 @@
 // pub mod editor_dock_tab;
+pub mod environment_ui;
+pub mod extractor_ui;
 pub mod request_ui;
+pub mod test_ui;
+pub mod variable_ui;
+pub mod websocket_ui;
 
 #[cfg(test)]
 mod tests {
@@ -1,3 +1,5 @@
+use crate::app::cancel_task;
+use crate::app::TaskKind;
 use crate::app::REQ_UI_ID;
 use crate::app::TASK_CHANNEL;
 use crate::app::TOASTS;
@@ -5,11 +7,23 @@ use crate::app::TOKIO_RT;
 use crate::component::code_editor::TextEdit;
 use crate::component::header_ui::HeaderUi;
 use crate::component::header_ui::SelectKeyValueItem;
+use crate::component::password::password;
+use crate::utils::environment::mask_secrets;
 use crate::component::syntax_highlight::code_view_ui;
 use crate::component::syntax_highlight::highlight_temp_key;
+use crate::ui::test_ui::TestUi;
+use crate::request_data::BodyType;
 use crate::request_data::LoadTestData;
 use crate::request_data::Method;
+use crate::request_data::MultipartItem;
 use crate::request_data::ResponseData;
+use crate::request_data::StreamEvent;
+use crate::request_data::Timing;
+use crate::utils::auth::{AuthData, AuthType};
+use crate::utils::graphql::GraphQLSchema;
+use crate::utils::client::{ClientSettings, ProxySettings, RedirectPolicy};
+use crate::utils::codegen::CodeLang;
+use crate::utils::debugger::{DebugCommand, DebugSession, DebugSnapshot, DebugState};
 use crate::utils::template::rander_template;
 use crate::{
     request_data::RequestData,
@@ -19,6 +33,7 @@ use egui::Id;
 use egui::RichText;
 use egui_commonmark::CommonMarkCache;
 use egui_commonmark::CommonMarkViewer;
+use egui_file::FileDialog;
 use egui_json_tree::JsonTree;
 use egui_plot::Bar;
 use egui_plot::BarChart;
@@ -27,19 +42,58 @@ use egui_plot::Line;
 use egui_plot::Plot;
 use once_cell::sync::OnceCell;
 use serde_json::Value;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
 
 static  JSON_VIEWER_ID:OnceCell<Id> = OnceCell::new();
 pub struct RequestUi {
     pub editor: TextEdit,
+    ///选择文件的弹框,None是二进制请求体,Some是multipart的第几项
+    file_dialog: Option<(Option<usize>, FileDialog)>,
+    graphql_editor: TextEdit,
+    variables_editor: TextEdit,
+    ///当前补全用的Schema的获取时间
+    schema_fetched_at: i64,
+    ///点了获取Schema,由ApiContext发内省请求
+    pub introspect: bool,
+    ///点了复制curl,需要上级的认证和设置,由ApiContext生成
+    pub copy_curl: bool,
 }
 
 impl Default for RequestUi {
     fn default() -> Self {
-        Self { editor: TextEdit::new_template() }
+        Self {
+            editor: TextEdit::new_template(),
+            file_dialog: None,
+            graphql_editor: TextEdit::new_graphql(&[]),
+            variables_editor: TextEdit::new_template(),
+            schema_fetched_at: 0,
+            introspect: false,
+            copy_curl: false,
+        }
     }
 }
 
 impl RequestUi {
+    ///Schema变了才重建补全
+    pub fn set_graphql_schema(&mut self, schema: Option<&GraphQLSchema>) {
+        let fetched_at = schema.map(|s| s.fetched_at).unwrap_or(0);
+        if fetched_at == self.schema_fetched_at {
+            return;
+        }
+        self.schema_fetched_at = fetched_at;
+        let words: Vec<String> = schema
+            .map(|s| {
+                s.types
+                    .iter()
+                    .chain(s.fields.iter())
+                    .chain(s.arguments.iter())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.graphql_editor = TextEdit::new_graphql(&words);
+    }
 
     pub fn ui(&mut self, ui: &mut egui::Ui, request_data: &mut RequestData, id: u64) {
         let RequestData {
@@ -47,11 +101,21 @@ impl RequestUi {
             url,
             method,
             headers,
+            params,
+            path_params,
             body,
+            body_type,
+            form,
+            multipart,
+            binary,
+            graphql,
+            ..
         } = request_data;
         let ui_id = REQ_UI_ID.get_or_init(|| ui.id());
         let req_id = ui_id.with(id);
         let mut send_state = ui.data_mut(|d| d.get_temp::<bool>(req_id).unwrap_or(false));
+        let mut url_changed = false;
+        let params_before = params.clone();
 
         ui.vertical(|ui| {
             // ui.add(editable_label(remark));
@@ -62,6 +126,9 @@ impl RequestUi {
                         send
                     })
                     .inner;
+                if ui.button("📋").on_hover_text("复制为curl").clicked() {
+                    self.copy_curl = true;
+                }
                 egui::ComboBox::from_label("🌐")
                     .selected_text(format!("{:?}", method))
                     .show_ui(ui, |ui| {
@@ -79,10 +146,12 @@ impl RequestUi {
                     ui.fonts(|f| f.layout_job(layout_job))
                 };
 
-                egui::TextEdit::singleline(url)
+                url_changed = egui::TextEdit::singleline(url)
                     .desired_width(ui.available_width() - 24.0)
                     .hint_text("请求路径").layouter(&mut layouter)
-                    .show(ui);
+                    .show(ui)
+                    .response
+                    .changed();
                 if send.clicked() {
                     send_state = true;
                     let task_sender = unsafe { TASK_CHANNEL.0.clone() };
@@ -95,6 +164,10 @@ impl RequestUi {
                 }
                 if send_state {
                     ui.spinner();
+                    if ui.button("停止⏹").clicked() {
+                        cancel_task(id, TaskKind::Send);
+                        send_state = false;
+                    }
                 }
             });
 
@@ -130,6 +203,60 @@ impl RequestUi {
                         HeaderUi::ui_grid_input(ui, "request_body_grid_1", headers);
                     });
 
+                    let params_id = ui.make_persistent_id("net_test_requset_params_ui");
+                    egui::collapsing_header::CollapsingState::load_with_default_open(
+                        ui.ctx(),
+                        params_id,
+                        false,
+                    )
+                    .show_header(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label("查询参数");
+                            if ui.small_button("➕").clicked() {
+                                params.push(SelectKeyValueItem::new("", ""));
+                            }
+                            if ui.small_button("➖").clicked() {
+                                params.retain(|item| item.selected);
+                            }
+                        });
+                    })
+                    .body(|ui| {
+                        HeaderUi::ui_grid_input(ui, "request_params_grid_1", params);
+                    });
+
+                    if !path_params.is_empty() {
+                        ui.collapsing("路径参数", |ui| {
+                            ui.group(|ui| {
+                                egui::Grid::new("request_path_params_grid_1")
+                                    .num_columns(2)
+                                    .min_col_width(10.)
+                                    .min_row_height(20.)
+                                    .show(ui, |ui| {
+                                        for SelectKeyValueItem { key, value, .. } in
+                                            path_params.iter_mut()
+                                        {
+                                            ui.add_sized(
+                                                [120., 20.],
+                                                egui::widgets::Label::new(key.as_str()),
+                                            );
+                                            let mut layouter =
+                                                |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+                                                    let layout_job =
+                                                        highlight_temp_key(ui.ctx(), string);
+                                                    ui.fonts(|f| f.layout_job(layout_job))
+                                                };
+                                            ui.add_sized(
+                                                ui.available_size(),
+                                                egui::text_edit::TextEdit::singleline(value)
+                                                    .layouter(&mut layouter),
+                                            );
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        });
+                    }
+
                     let state_id = ui.id().with(id.to_string() + "body");
                     let (mut show_plaintext, mut template_str) = ui.data(|d| {
                         d.get_temp::<(bool, String)>(state_id)
@@ -137,12 +264,46 @@ impl RequestUi {
                     });
                     ui.horizontal(|ui| {
                         ui.label("请求体：");
+                        egui::ComboBox::from_id_source("request_body_type")
+                            .selected_text(body_type.label())
+                            .show_ui(ui, |ui| {
+                                for t in BodyType::ALL {
+                                    ui.selectable_value(body_type, t, t.label());
+                                }
+                            });
+                        match body_type {
+                            BodyType::FormUrlencoded => {
+                                if ui.small_button("➕").clicked() {
+                                    form.push(SelectKeyValueItem::new("", ""));
+                                }
+                                if ui.small_button("➖").clicked() {
+                                    form.retain(|item| item.selected);
+                                }
+                            }
+                            BodyType::Multipart => {
+                                if ui.small_button("➕").clicked() {
+                                    multipart.push(MultipartItem::new("", "", false));
+                                }
+                                if ui.small_button("➖").clicked() {
+                                    multipart.retain(|item| item.selected);
+                                }
+                            }
+                            BodyType::GraphQL => {
+                                if ui.button("获取Schema").clicked() {
+                                    self.introspect = true;
+                                }
+                            }
+                            _ => {}
+                        }
+                        if !matches!(body_type, BodyType::Raw | BodyType::Json | BodyType::Json5) {
+                            return;
+                        }
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             if show_plaintext {
                                 if ui.button("🔃").clicked() {
                                     if show_plaintext {
                                         match rander_template(body.as_str()) {
-                                            Ok(parsed_temp) => template_str = parsed_temp,
+                                            Ok(parsed_temp) => template_str = mask_secrets(&parsed_temp),
                                             Err(e) => {
                                                 if let Ok(mut toast_w) =
                                                     TOASTS.get().unwrap().lock()
@@ -179,6 +340,7 @@ impl RequestUi {
                                             body.clone()
                                         }
                                     };
+                                    let deal_temp = mask_secrets(&deal_temp);
                                     template_str = match json5::from_str::<Value>(&deal_temp) {
 
                                         Ok(json_body) => serde_json::to_string_pretty(&json_body)
@@ -212,10 +374,39 @@ impl RequestUi {
                         });
                     });
 
-                    if show_plaintext {
-                        code_view_ui(ui, &template_str, "json");
-                    } else {
-                        self.editor.ui(ui, body, id);
+                    match body_type {
+                        BodyType::None => {}
+                        BodyType::Raw | BodyType::Json | BodyType::Json5 => {
+                            if show_plaintext {
+                                code_view_ui(ui, &template_str, "json");
+                            } else {
+                                self.editor.ui(ui, body, id);
+                            }
+                        }
+                        BodyType::FormUrlencoded => {
+                            HeaderUi::ui_grid_input(ui, "request_form_grid_1", form);
+                        }
+                        BodyType::Multipart => {
+                            self.multipart_ui(ui, multipart);
+                        }
+                        BodyType::GraphQL => {
+                            ui.columns(2, |columns| {
+                                columns[0].label("Query");
+                                self.graphql_editor.ui(&mut columns[0], &mut graphql.query, id);
+                                columns[1].label("Variables");
+                                self.variables_editor
+                                    .ui(&mut columns[1], &mut graphql.variables, id);
+                            });
+                        }
+                        BodyType::Binary => {
+                            ui.horizontal(|ui| {
+                                ui.label("文件：");
+                                ui.text_edit_singleline(binary);
+                                if ui.button("选择文件").clicked() {
+                                    self.open_file_dialog(None, binary);
+                                }
+                            });
+                        }
                     }
                     ui.data_mut(|data| data.insert_temp(state_id, (show_plaintext, template_str)));
 
@@ -227,6 +418,91 @@ impl RequestUi {
                 })
         });
         ui.data_mut(|d| d.insert_temp(req_id, send_state));
+
+        if let Some((index, dialog)) = &mut self.file_dialog {
+            if dialog.show(ui.ctx()).selected() {
+                if let Some(file) = dialog.path() {
+                    let path = file.to_string_lossy().to_string();
+                    match index {
+                        Some(i) => {
+                            if let Some(item) = multipart.get_mut(*i) {
+                                item.value = path;
+                            }
+                        }
+                        None => *binary = path,
+                    }
+                }
+                self.file_dialog = None;
+            }
+        }
+
+        if url_changed {
+            request_data.sync_params_from_url();
+        } else if request_data.params != params_before {
+            request_data.sync_url_from_params();
+        }
+    }
+
+    fn open_file_dialog(&mut self, index: Option<usize>, current: &str) {
+        let init_path = if current.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(current))
+        };
+        let mut dialog = FileDialog::open_file(init_path).show_rename(false);
+        dialog.open();
+        self.file_dialog = Some((index, dialog));
+    }
+
+    fn multipart_ui(&mut self, ui: &mut egui::Ui, inputs: &mut [MultipartItem]) {
+        let mut open_index = None;
+        ui.group(|ui| {
+            egui::Grid::new("request_multipart_grid_1")
+                .num_columns(4)
+                .min_col_width(10.)
+                .min_row_height(20.)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.add_sized(
+                        [120., 20.],
+                        egui::widgets::Label::new(RichText::new("键").strong()),
+                    );
+                    ui.label(RichText::new("文件").strong());
+                    ui.label(RichText::new("值").strong());
+                    ui.end_row();
+                    for (i, item) in inputs.iter_mut().enumerate() {
+                        ui.checkbox(&mut item.selected, "");
+                        ui.add_sized(
+                            [120., 20.],
+                            egui::text_edit::TextEdit::singleline(&mut item.key),
+                        );
+                        ui.checkbox(&mut item.is_file, "");
+                        ui.horizontal(|ui| {
+                            if item.is_file {
+                                ui.text_edit_singleline(&mut item.value);
+                                if ui.small_button("📂").clicked() {
+                                    open_index = Some(i);
+                                }
+                            } else {
+                                let mut layouter =
+                                    |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+                                        let layout_job = highlight_temp_key(ui.ctx(), string);
+                                        ui.fonts(|f| f.layout_job(layout_job))
+                                    };
+                                ui.add(
+                                    egui::text_edit::TextEdit::singleline(&mut item.value)
+                                        .layouter(&mut layouter),
+                                );
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+        });
+        if let Some(i) = open_index {
+            let current = inputs[i].value.clone();
+            self.open_file_dialog(Some(i), &current);
+        }
     }
 }
 
@@ -263,12 +539,15 @@ impl CollectionUi {
 
 pub struct ScriptUi {
     pre_script_editor: TextEdit,
+    ///请求脚本的断点行号,key是(请求id,是否后置脚本)
+    breakpoints: HashMap<(u64, bool), BTreeSet<usize>>,
 }
 
 impl Default for ScriptUi {
     fn default() -> Self {
         ScriptUi {
-            pre_script_editor: TextEdit::new_rhai()
+            pre_script_editor: TextEdit::new_rhai(),
+            breakpoints: HashMap::default(),
         }
     }
 }
@@ -277,18 +556,393 @@ impl ScriptUi {
     pub fn ui(&mut self, ui: &mut egui::Ui, data: &mut String, id: u64) {
         self.pre_script_editor.ui(ui, data, id);
     }
+
+    pub fn breakpoints(&self, id: u64, after: bool) -> Vec<usize> {
+        self.breakpoints
+            .get(&(id, after))
+            .map(|lines| lines.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    ///可以调试的请求脚本,点行号设置断点,返回true时开始调试
+    pub fn debug_ui(
+        &mut self,
+        ui: &mut egui::Ui,
+        data: &mut String,
+        id: u64,
+        after: bool,
+        session: &mut Option<DebugSession>,
+    ) -> bool {
+        let target = (id, after);
+        let mut start = false;
+        let mut close = false;
+        if let Some(session) = session.as_mut().filter(|s| s.target == target) {
+            Self::debug_toolbar(ui, session, &mut close);
+            if let DebugState::Finished(_, tests) = &session.state {
+                if !tests.is_empty() {
+                    ui.collapsing("测试结果", |ui| TestUi::ui(ui, tests));
+                }
+            }
+        } else if ui
+            .button("调试")
+            .on_hover_text("点行号设置断点,没有断点时停在第一行。集合上的脚本会先直接执行,只单步调试当前这段脚本。调试时脚本不发送请求")
+            .clicked()
+        {
+            start = true;
+        }
+        if close {
+            *session = None;
+        }
+
+        let breakpoints = self.breakpoints.entry(target).or_default();
+        let paused = session
+            .as_ref()
+            .filter(|s| s.target == target)
+            .and_then(|s| s.paused());
+        match paused {
+            Some(snapshot) => {
+                ui.columns(2, |columns| {
+                    self.pre_script_editor.ui_with_breakpoints(
+                        &mut columns[0],
+                        data,
+                        id,
+                        breakpoints,
+                        Some(snapshot.line),
+                    );
+                    Self::debug_panel(&mut columns[1], snapshot);
+                });
+            }
+            None => {
+                self.pre_script_editor
+                    .ui_with_breakpoints(ui, data, id, breakpoints, None);
+            }
+        }
+        start
+    }
+
+    fn debug_toolbar(ui: &mut egui::Ui, session: &mut DebugSession, close: &mut bool) {
+        ui.horizontal(|ui| match &session.state {
+            DebugState::Finished(result, _) => {
+                match result {
+                    Ok(value) if value.is_empty() => {
+                        ui.label(RichText::new("执行完成").color(Color32::GREEN));
+                    }
+                    Ok(value) => {
+                        ui.label(
+                            RichText::new(format!("执行完成,返回:{}", value)).color(Color32::GREEN),
+                        );
+                    }
+                    Err(e) => {
+                        ui.label(RichText::new(e).color(Color32::RED));
+                    }
+                }
+                if ui.button("关闭").clicked() {
+                    *close = true;
+                }
+            }
+            state => {
+                let paused = matches!(state, DebugState::Paused(_));
+                let commands = [
+                    ("继续", DebugCommand::Continue),
+                    ("单步跳过", DebugCommand::StepOver),
+                    ("单步进入", DebugCommand::StepInto),
+                    ("单步跳出", DebugCommand::StepOut),
+                ];
+                let mut clicked = None;
+                for (label, command) in commands {
+                    if ui.add_enabled(paused, egui::Button::new(label)).clicked() {
+                        clicked = Some(command);
+                    }
+                }
+                if ui.button("停止").clicked() {
+                    clicked = Some(DebugCommand::Stop);
+                }
+                if !paused {
+                    ui.spinner();
+                    ui.label("运行中");
+                }
+                if let Some(command) = clicked {
+                    session.command(command);
+                }
+            }
+        });
+    }
+
+    ///暂停时的作用域变量和调用栈
+    fn debug_panel(ui: &mut egui::Ui, snapshot: &DebugSnapshot) {
+        ui.label(format!(
+            "{}:第{}行第{}列",
+            snapshot.reason, snapshot.line, snapshot.column
+        ));
+        egui::ScrollArea::vertical()
+            .id_source("debug_panel_scroller")
+            .show(ui, |ui| {
+                ui.collapsing(format!("变量({})", snapshot.vars.len()), |ui| {
+                    egui::Grid::new("debug_vars")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            ui.strong("名称");
+                            ui.strong("类型");
+                            ui.strong("值");
+                            ui.end_row();
+                            for var in snapshot.vars.iter() {
+                                if var.constant {
+                                    ui.label(format!("{}(常量)", var.name));
+                                } else {
+                                    ui.label(&var.name);
+                                }
+                                ui.label(RichText::new(&var.type_name).weak());
+                                ui.label(var.preview()).on_hover_text(&var.value);
+                                ui.end_row();
+                            }
+                        });
+                });
+                ui.collapsing(format!("调用栈({})", snapshot.call_stack.len()), |ui| {
+                    if snapshot.call_stack.is_empty() {
+                        ui.label("顶层脚本");
+                    }
+                    for frame in snapshot.call_stack.iter().rev() {
+                        ui.label(frame);
+                    }
+                });
+            });
+    }
+}
+
+///生成代码需要上级的认证和前置脚本,由ApiContext生成后放回来
+#[derive(Default)]
+pub struct CodeGenUi {
+    pub lang: CodeLang,
+    ///渲染模板,否则保留${}原文
+    pub render: bool,
+    code: String,
+    ///生成代码时选中的请求
+    id: u64,
+}
+
+impl CodeGenUi {
+    pub fn set_code(&mut self, code: String) {
+        self.code = code;
+    }
+
+    ///返回是否需要重新生成
+    pub fn ui(&mut self, ui: &mut egui::Ui, id: u64) -> bool {
+        let mut regenerate = std::mem::replace(&mut self.id, id) != id;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source("code_gen_lang")
+                .selected_text(self.lang.label())
+                .show_ui(ui, |ui| {
+                    for lang in CodeLang::ALL {
+                        regenerate |= ui
+                            .selectable_value(&mut self.lang, lang, lang.label())
+                            .changed();
+                    }
+                });
+            regenerate |= ui.checkbox(&mut self.render, "渲染模板").changed();
+            regenerate |= ui.button("刷新").clicked();
+            if ui.button("复制").clicked() {
+                ui.ctx().output_mut(|o| o.copied_text = self.code.clone());
+            }
+        });
+        egui::ScrollArea::both()
+            .id_source("code_gen_scroller")
+            .show(ui, |ui| {
+                code_view_ui(ui, &self.code, self.lang.extension());
+            });
+        regenerate
+    }
+}
+
+pub struct AuthUi {}
+
+impl AuthUi {
+    pub fn ui(ui: &mut egui::Ui, auth: &mut AuthData, id: u64) {
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.label("认证方式：");
+                egui::ComboBox::from_id_source(("auth_type", id))
+                    .selected_text(auth.auth_type.label())
+                    .show_ui(ui, |ui| {
+                        for t in AuthType::ALL {
+                            ui.selectable_value(&mut auth.auth_type, t, t.label());
+                        }
+                    });
+            });
+            egui::Grid::new("auth_ui_grid_1")
+                .num_columns(2)
+                .min_col_width(80.)
+                .min_row_height(20.)
+                .show(ui, |ui| match auth.auth_type {
+                    AuthType::Inherit => {
+                        ui.label("使用上级集合的认证");
+                        ui.end_row();
+                    }
+                    AuthType::None => {}
+                    AuthType::Basic | AuthType::Digest => {
+                        ui.label("用户名：");
+                        ui.text_edit_singleline(&mut auth.username);
+                        ui.end_row();
+                        ui.label("密码：");
+                        ui.add(password(&mut auth.password));
+                        ui.end_row();
+                    }
+                    AuthType::Bearer => {
+                        ui.label("Token：");
+                        ui.add(password(&mut auth.token));
+                        ui.end_row();
+                    }
+                    AuthType::ApiKey => {
+                        ui.label("键：");
+                        ui.text_edit_singleline(&mut auth.api_key);
+                        ui.end_row();
+                        ui.label("值：");
+                        ui.add(password(&mut auth.api_value));
+                        ui.end_row();
+                        ui.label("位置：");
+                        ui.horizontal(|ui| {
+                            ui.radio_value(&mut auth.api_key_in_query, false, "请求头");
+                            ui.radio_value(&mut auth.api_key_in_query, true, "查询参数");
+                        });
+                        ui.end_row();
+                    }
+                    AuthType::OAuth2ClientCredentials | AuthType::OAuth2Password => {
+                        ui.label("令牌地址：");
+                        ui.text_edit_singleline(&mut auth.token_url);
+                        ui.end_row();
+                        ui.label("Client ID：");
+                        ui.text_edit_singleline(&mut auth.client_id);
+                        ui.end_row();
+                        ui.label("Client Secret：");
+                        ui.add(password(&mut auth.client_secret));
+                        ui.end_row();
+                        if auth.auth_type == AuthType::OAuth2Password {
+                            ui.label("用户名：");
+                            ui.text_edit_singleline(&mut auth.username);
+                            ui.end_row();
+                            ui.label("密码：");
+                            ui.add(password(&mut auth.password));
+                            ui.end_row();
+                        }
+                        ui.label("Scope：");
+                        ui.text_edit_singleline(&mut auth.scope);
+                        ui.end_row();
+                    }
+                });
+            if auth.has_literal_credentials() {
+                ui.label(
+                    RichText::new("直接填写的凭证不会保存和导出,重启后需要重新填写;要保存请写成${加密变量}")
+                        .weak(),
+                );
+            }
+        });
+    }
+}
+
+pub struct ClientSettingsUi {}
+
+impl ClientSettingsUi {
+    ///未勾选的项使用上级的设置
+    pub fn ui(ui: &mut egui::Ui, settings: &mut ClientSettings, id: &str) {
+        egui::Grid::new(("client_settings_grid", id))
+            .num_columns(3)
+            .min_col_width(80.)
+            .min_row_height(20.)
+            .show(ui, |ui| {
+                Self::override_row(ui, "连接超时(ms)：", &mut settings.connect_timeout, 10_000, |ui, v| {
+                    ui.add(egui::DragValue::new(v).speed(100));
+                });
+                Self::override_row(ui, "读取超时(ms)：", &mut settings.read_timeout, 60_000, |ui, v| {
+                    ui.add(egui::DragValue::new(v).speed(100));
+                });
+                Self::override_row(
+                    ui,
+                    "重定向：",
+                    &mut settings.redirect,
+                    RedirectPolicy::default(),
+                    |ui, v| {
+                        ui.horizontal(|ui| {
+                            let mut follow = matches!(v, RedirectPolicy::Follow(_));
+                            if ui.checkbox(&mut follow, "跟随").changed() {
+                                *v = if follow {
+                                    RedirectPolicy::default()
+                                } else {
+                                    RedirectPolicy::None
+                                };
+                            }
+                            if let RedirectPolicy::Follow(max) = v {
+                                ui.label("最多：");
+                                ui.add(egui::DragValue::new(max).clamp_range(0..=100));
+                            }
+                        });
+                    },
+                );
+                Self::override_row(
+                    ui,
+                    "代理：",
+                    &mut settings.proxy,
+                    ProxySettings::default(),
+                    |ui, v| {
+                        ui.vertical(|ui| {
+                            ui.checkbox(&mut v.disabled, "不使用代理(忽略系统代理)");
+                            ui.add_enabled_ui(!v.disabled, |ui| {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut v.url)
+                                        .hint_text("为空时使用系统代理"),
+                                );
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut v.no_proxy)
+                                        .hint_text("不走代理的主机,逗号分隔"),
+                                );
+                            });
+                        });
+                    },
+                );
+                Self::override_row(ui, "校验证书：", &mut settings.verify_tls, false, |ui, v| {
+                    ui.checkbox(v, "");
+                });
+            });
+    }
+
+    fn override_row<T>(
+        ui: &mut egui::Ui,
+        label: &str,
+        value: &mut Option<T>,
+        default: T,
+        edit: impl FnOnce(&mut egui::Ui, &mut T),
+    ) {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").changed() {
+            *value = if enabled { Some(default) } else { None };
+        }
+        ui.label(label);
+        match value {
+            Some(v) => edit(ui, v),
+            None => {
+                ui.label("继承");
+            }
+        }
+        ui.end_row();
+    }
 }
 
 pub struct ResponseUi {}
 
 impl ResponseUi {
-    pub fn ui(ui: &mut egui::Ui, data: &mut ResponseData, _id: u64) {
+    ///graphql为true时把响应分成data和errors显示
+    pub fn ui(ui: &mut egui::Ui, data: &mut ResponseData, _id: u64, graphql: bool) {
         let ResponseData {
             headers,
             body,
             size,
             code,
             time,
+            header_size,
+            send_size,
+            timing,
+            events,
+            extract_errors,
+            tests,
         } = data;
         let id = Id::new("json_viewer_id");
 
@@ -316,11 +970,25 @@ impl ResponseUi {
                 };
                 ui.label(code_rich_text);
                 ui.label("响应时间：");
-                ui.label(time.to_string());
+                ui.label(time.to_string())
+                    .on_hover_ui(|ui| Self::timing_ui(ui, timing));
                 ui.label("毫秒");
                 ui.label("响应大小：");
-                ui.label(size.to_string());
+                ui.label(size.to_string()).on_hover_text(format!(
+                    "响应体：{} B\n响应头：{} B\n发送：{} B",
+                    size, header_size, send_size
+                ));
                 ui.label("B");
+                if !tests.is_empty() {
+                    let passed = tests.iter().filter(|t| t.passed).count();
+                    let color = if passed == tests.len() {
+                        Color32::GREEN
+                    } else {
+                        Color32::RED
+                    };
+                    ui.label("测试：");
+                    ui.label(RichText::new(format!("{}/{}", passed, tests.len())).color(color));
+                }
                 ui.spacing();
                 ui.toggle_value(&mut json_viewer, egui_nerdfonts::regular::CODE_JSON);
             });
@@ -337,10 +1005,33 @@ impl ResponseUi {
                             ui.collapsing("响应头", |ui| {
                                 HeaderUi::ui_grid(ui, "response_grid_ui_1", headers);
                             });
+                            if !extract_errors.is_empty() {
+                                egui::CollapsingHeader::new(
+                                    RichText::new(format!("提取失败({})", extract_errors.len()))
+                                        .color(Color32::RED),
+                                )
+                                .default_open(true)
+                                .show(ui, |ui| {
+                                    for error in extract_errors.iter() {
+                                        ui.label(error);
+                                    }
+                                });
+                            }
+                            if !events.is_empty() {
+                                egui::CollapsingHeader::new(format!("事件({})", events.len()))
+                                    .default_open(true)
+                                    .show(ui, |ui| Self::events_ui(ui, events));
+                            }
                             // ui.add_sized(
                             // ui.available_size(),
                             
-                            if json_viewer {
+                            let graphql_value = graphql
+                                .then(|| serde_json::from_str::<Value>(body).ok())
+                                .flatten()
+                                .filter(|v| v.get("data").is_some() || v.get("errors").is_some());
+                            if let Some(value) = graphql_value {
+                                Self::graphql_ui(ui, &value, json_viewer);
+                            } else if json_viewer {
                                 let value = match serde_json::from_str::<Value>(body) {
                                     Ok(json_body) => json_body,
                                     Err(e) => Value::String(e.to_string()),
@@ -358,6 +1049,75 @@ impl ResponseUi {
             });
         });
     }
+
+    fn graphql_ui(ui: &mut egui::Ui, value: &Value, json_viewer: bool) {
+        if let Some(errors) = value.get("errors").and_then(|e| e.as_array()) {
+            let title = RichText::new(format!("errors({})", errors.len())).color(Color32::RED);
+            egui::CollapsingHeader::new(title)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for error in errors {
+                        let message = error["message"].as_str().unwrap_or_default();
+                        ui.label(RichText::new(message).color(Color32::RED))
+                            .on_hover_text(serde_json::to_string_pretty(error).unwrap_or_default());
+                    }
+                });
+        }
+        let data = value.get("data").unwrap_or(&Value::Null);
+        egui::CollapsingHeader::new("data")
+            .default_open(true)
+            .show(ui, |ui| {
+                if json_viewer {
+                    JsonTree::new("graphql-data-tree", data).show(ui);
+                } else {
+                    let pretty = serde_json::to_string_pretty(data).unwrap_or_default();
+                    code_view_ui(ui, &pretty, "json");
+                }
+            });
+    }
+
+    fn events_ui(ui: &mut egui::Ui, events: &[StreamEvent]) {
+        egui::ScrollArea::vertical()
+            .id_source("response_events_scroller")
+            .max_height(240.)
+            .stick_to_bottom(true)
+            .show(ui, |ui| {
+                egui::Grid::new("response_events_grid")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for event in events {
+                            ui.label(RichText::new(&event.time).color(Color32::GRAY));
+                            ui.label(&event.id);
+                            ui.label(RichText::new(&event.event).color(Color32::LIGHT_BLUE));
+                            ui.label(&event.data).on_hover_ui(|ui| {
+                                if let Some(retry) = event.retry {
+                                    ui.label(format!("retry: {}", retry));
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+    }
+
+    fn timing_ui(ui: &mut egui::Ui, timing: &Timing) {
+        egui::Grid::new("response_timing_grid")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (label, value) in [
+                    ("DNS解析：", timing.dns),
+                    ("TCP连接：", timing.connect),
+                    ("TLS握手：", timing.tls),
+                    ("首字节：", timing.ttfb),
+                    ("下载：", timing.download),
+                ] {
+                    ui.label(label);
+                    ui.label(format!("{} 毫秒", value));
+                    ui.end_row();
+                }
+            });
+    }
 }
 
 pub struct LoadTestUi {}
@@ -380,6 +1140,8 @@ impl LoadTestUi {
                         let round = data.round;
                         data.result_list = vec![0].repeat((reqs * round) as usize);
                         data.process = 0.0;
+                        data.aborted = false;
+                        data.result = Default::default();
                         let task_sender = unsafe { TASK_CHANNEL.0.clone() };
                         TOKIO_RT.spawn(async move {
                             if let Err(_) = task_sender.send((id, reqs, round)).await {
@@ -394,6 +1156,14 @@ impl LoadTestUi {
                     ui.add(egui::DragValue::new(&mut data.round).speed(1));
                     if send_state {
                         ui.spinner();
+                        if ui.button("停止").clicked() {
+                            cancel_task(id, TaskKind::LoadTest);
+                            send_state = false;
+                            data.aborted = true;
+                        }
+                    }
+                    if data.aborted {
+                        ui.label(RichText::new("已中止").color(Color32::RED));
                     }
                     ui.add(egui::ProgressBar::new(data.process));
                 });
@@ -0,0 +1,42 @@
+use egui::{Color32, RichText};
+
+use crate::utils::assertion::TestResult;
+
+pub struct TestUi {}
+
+impl TestUi {
+    pub fn ui(ui: &mut egui::Ui, results: &[TestResult]) {
+        if results.is_empty() {
+            ui.label("没有测试结果,在后置脚本里用test()和expect()添加断言");
+            return;
+        }
+        let passed = results.iter().filter(|r| r.passed).count();
+        ui.horizontal(|ui| {
+            ui.label(RichText::new(format!("通过 {}", passed)).color(Color32::GREEN));
+            ui.label(RichText::new(format!("失败 {}", results.len() - passed)).color(Color32::RED));
+        });
+        ui.separator();
+        egui::ScrollArea::vertical()
+            .id_source("test_result_scroller")
+            .show(ui, |ui| {
+                for result in results {
+                    ui.horizontal(|ui| {
+                        if result.passed {
+                            ui.label(RichText::new("✔").color(Color32::GREEN));
+                        } else {
+                            ui.label(RichText::new("✖").color(Color32::RED));
+                        }
+                        ui.label(&result.name);
+                        if !result.position.is_empty() {
+                            ui.label(RichText::new(&result.position).small().weak());
+                        }
+                    });
+                    if !result.message.is_empty() {
+                        ui.indent(("test_message", &result.name, &result.position), |ui| {
+                            ui.label(RichText::new(&result.message).color(Color32::LIGHT_RED));
+                        });
+                    }
+                }
+            });
+    }
+}
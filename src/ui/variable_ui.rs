@@ -0,0 +1,96 @@
+use egui::{Color32, RichText};
+use egui_json_tree::JsonTree;
+
+use crate::utils::environment::{VarEntry, VarSource};
+
+///修改要交给ApiContext,环境变量需要写回Environments
+#[derive(Debug, Clone, PartialEq)]
+pub enum VarAction {
+    Keep,
+    Set(VarSource, String, serde_json::Value),
+    Clear(VarSource, String),
+}
+
+#[derive(Default)]
+pub struct VariableUi {
+    filter: String,
+    ///(来源,名字,编辑中的JSON)
+    editing: Option<(VarSource, String, String)>,
+}
+
+impl VariableUi {
+    pub fn ui(&mut self, ui: &mut egui::Ui, entries: &[VarEntry]) -> VarAction {
+        let mut action = VarAction::Keep;
+        ui.horizontal(|ui| {
+            ui.label("过滤");
+            ui.text_edit_singleline(&mut self.filter);
+        });
+        ui.label(
+            RichText::new("同名时的优先级:脚本 > 加密 > 环境 > 全局,灰色的被覆盖")
+                .small()
+                .weak(),
+        );
+        ui.separator();
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for entry in entries.iter().filter(|e| e.name.contains(self.filter.as_str())) {
+                let mut title = RichText::new(format!("{} [{}]", entry.name, entry.source.label()));
+                if entry.shadowed {
+                    title = title.strikethrough().weak();
+                }
+                let id = ui.make_persistent_id(("variable", entry.source.label(), &entry.name));
+                egui::collapsing_header::CollapsingState::load_with_default_open(
+                    ui.ctx(),
+                    id,
+                    false,
+                )
+                .show_header(ui, |ui| {
+                    ui.label(title);
+                    if entry.source != VarSource::Secret && ui.small_button("编辑").clicked() {
+                        let text = serde_json::to_string_pretty(&entry.value).unwrap_or_default();
+                        self.editing = Some((entry.source, entry.name.clone(), text));
+                    }
+                    if ui.small_button("清除").clicked() {
+                        action = VarAction::Clear(entry.source, entry.name.clone());
+                    }
+                })
+                .body(|ui| {
+                    JsonTree::new(id.with("tree"), &entry.value).show(ui);
+                });
+            }
+        });
+        if let Some(set) = self.edit_window(ui.ctx()) {
+            action = set;
+        }
+        action
+    }
+
+    ///不是合法JSON时按字符串保存
+    fn edit_window(&mut self, ctx: &egui::Context) -> Option<VarAction> {
+        let (source, name, text) = self.editing.as_mut()?;
+        let mut open = true;
+        let mut save = false;
+        egui::Window::new(format!("编辑{}", name))
+            .open(&mut open)
+            .default_width(360.)
+            .show(ctx, |ui| {
+                ui.add(
+                    egui::TextEdit::multiline(text)
+                        .code_editor()
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+                if serde_json::from_str::<serde_json::Value>(text).is_err() {
+                    ui.label(RichText::new("不是JSON,将保存为字符串").color(Color32::GRAY));
+                }
+                save = ui.button("保存").clicked();
+            });
+        let action = save.then(|| {
+            let value = serde_json::from_str(text).unwrap_or_else(|_| text.clone().into());
+            VarAction::Set(*source, name.clone(), value)
+        });
+        if save || !open {
+            self.editing = None;
+        }
+        action
+    }
+}
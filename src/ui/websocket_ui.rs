@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use egui::{Color32, RichText};
+
+use crate::app::TOASTS;
+use crate::component::header_ui::{HeaderUi, SelectKeyValueItem};
+use crate::component::syntax_highlight::highlight_temp_key;
+use crate::utils::websocket::{self, WebSocketData, WsDirection, WsMessageKind};
+
+///连接需要用到上级的客户端设置,交给ApiContext处理
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsAction {
+    Keep,
+    Connect,
+    Disconnect,
+}
+
+pub struct WebSocketUi {}
+
+impl WebSocketUi {
+    pub fn ui(ui: &mut egui::Ui, data: &mut WebSocketData, id: u64) -> WsAction {
+        let mut action = WsAction::Keep;
+        let connected = websocket::is_connected(id);
+        if connected {
+            //消息是后台收到的,定时刷新
+            ui.ctx().request_repaint_after(Duration::from_millis(200));
+        }
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                if connected {
+                    if ui.button("断开").clicked() {
+                        action = WsAction::Disconnect;
+                    }
+                } else if ui.button("连接").clicked() {
+                    action = WsAction::Connect;
+                }
+                let mut layouter = |ui: &egui::Ui, string: &str, _wrap_width: f32| {
+                    let layout_job = highlight_temp_key(ui.ctx(), string);
+                    ui.fonts(|f| f.layout_job(layout_job))
+                };
+                ui.add(
+                    egui::TextEdit::singleline(&mut data.url)
+                        .desired_width(ui.available_width())
+                        .hint_text("ws://或wss://开头")
+                        .layouter(&mut layouter),
+                );
+            });
+
+            let headers_id = ui.make_persistent_id("ws_headers_ui");
+            egui::collapsing_header::CollapsingState::load_with_default_open(
+                ui.ctx(),
+                headers_id,
+                false,
+            )
+            .show_header(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("请求头");
+                    if ui.small_button("➕").clicked() {
+                        data.headers.push(SelectKeyValueItem::new("", ""));
+                    }
+                    if ui.small_button("➖").clicked() {
+                        data.headers.retain(|item| item.selected);
+                    }
+                });
+            })
+            .body(|ui| {
+                HeaderUi::ui_grid_input(ui, "ws_headers_grid_1", &mut data.headers);
+            });
+
+            ui.collapsing("已保存的消息", |ui| {
+                let mut removed = None;
+                for (i, msg) in data.saved.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("➖").clicked() {
+                            removed = Some(i);
+                        }
+                        if ui.button(&msg.name).on_hover_text(&msg.content).clicked() {
+                            data.draft = msg.clone();
+                        }
+                        ui.label(msg.kind.label());
+                    });
+                }
+                if let Some(i) = removed {
+                    data.saved.remove(i);
+                }
+            });
+
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_source(("ws_message_kind", id))
+                        .selected_text(data.draft.kind.label())
+                        .show_ui(ui, |ui| {
+                            for kind in WsMessageKind::ALL {
+                                ui.selectable_value(&mut data.draft.kind, kind, kind.label());
+                            }
+                        });
+                    ui.add(
+                        egui::TextEdit::singleline(&mut data.draft.name)
+                            .desired_width(120.)
+                            .hint_text("消息名称"),
+                    );
+                    if ui.button("保存").clicked() && !data.draft.name.is_empty() {
+                        match data.saved.iter_mut().find(|m| m.name == data.draft.name) {
+                            Some(saved) => *saved = data.draft.clone(),
+                            None => data.saved.push(data.draft.clone()),
+                        }
+                    }
+                    if ui.add_enabled(connected, egui::Button::new("发送")).clicked() {
+                        match websocket::send(id, &data.draft) {
+                            Ok(log) => data.log.push(log),
+                            Err(e) => {
+                                if let Ok(mut toast_w) = TOASTS.get().unwrap().lock() {
+                                    toast_w
+                                        .error(format!("发送失败:{}", e))
+                                        .set_duration(Some(Duration::from_secs(5)));
+                                }
+                            }
+                        }
+                    }
+                });
+                let mut layouter = |ui: &egui::Ui, string: &str, wrap_width: f32| {
+                    let mut layout_job = highlight_temp_key(ui.ctx(), string);
+                    layout_job.wrap.max_width = wrap_width;
+                    ui.fonts(|f| f.layout_job(layout_job))
+                };
+                ui.add(
+                    egui::TextEdit::multiline(&mut data.draft.content)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(4)
+                        .code_editor()
+                        .layouter(&mut layouter),
+                );
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("消息记录");
+                if ui.small_button("清空").clicked() {
+                    data.log.clear();
+                }
+            });
+            egui::ScrollArea::vertical()
+                .id_source("ws_log_scroller")
+                .auto_shrink([false, false])
+                .stick_to_bottom(true)
+                .show(ui, |ui| {
+                    egui::Grid::new("ws_log_grid")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for item in data.log.iter() {
+                                ui.label(RichText::new(&item.time).color(Color32::GRAY));
+                                let direction = match item.direction {
+                                    WsDirection::Incoming => {
+                                        RichText::new("⬇").color(Color32::LIGHT_GREEN)
+                                    }
+                                    WsDirection::Outgoing => {
+                                        RichText::new("⬆").color(Color32::LIGHT_BLUE)
+                                    }
+                                    WsDirection::Info => RichText::new("ℹ").color(Color32::GOLD),
+                                };
+                                ui.label(direction).on_hover_text(item.kind.label());
+                                ui.label(&item.content);
+                                ui.end_row();
+                            }
+                        });
+                });
+        });
+        action
+    }
+}
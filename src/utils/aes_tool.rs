@@ -55,6 +55,17 @@ fn aes_enc_cbc(key_size: KeySize, key: &[u8], input: &[u8], iv: &[u8]) -> Result
     Ok(result)
 }
 
+///按字节的密钥加密,密钥来自派生而不是字符串时使用
+pub fn aes_enc_cbc_bytes(key: &[u8], input: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    let key_size = get_key_size(key)?;
+    aes_enc_cbc(key_size, key, input, iv)
+}
+
+pub fn aes_dec_cbc_bytes(key: &[u8], input: &[u8], iv: &[u8]) -> Result<Vec<u8>, String> {
+    let key_size = get_key_size(key)?;
+    aes_dec_cbc(key_size, key, input, iv)
+}
+
 pub fn aes_enc_ctr_string(key: &str, input: &str, iv: &str) -> Result<Vec<u8>, String> {
     let input = input.as_bytes();
     let key_bytes = key.as_bytes();
@@ -0,0 +1,541 @@
+use std::cell::RefCell;
+
+use regex::Regex;
+use rhai::{Dynamic, Engine, EvalAltResult, FnPtr, NativeCallContext, Position, INT};
+use serde_json::Value;
+
+use crate::request_data::ResponseData;
+use crate::utils::rhai_script::script_error;
+
+///一条断言或者一个test()的结果
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    ///失败的原因
+    pub message: String,
+    ///脚本里的位置,如`line 3, position 5`
+    pub position: String,
+}
+
+#[derive(Default)]
+struct TestState {
+    ///断言针对的响应
+    resp: ResponseData,
+    results: Vec<TestResult>,
+    ///在test()里时断言失败直接抛错,由test()记录
+    depth: usize,
+}
+
+thread_local! {
+    ///每个线程一份,调试线程里的断言不会和界面线程上的后置脚本混在一起
+    static STATE: RefCell<TestState> = RefCell::new(TestState::default());
+}
+
+///expect(x)的返回值,后面接to_equal等断言
+#[derive(Debug, Clone)]
+pub struct Expectation {
+    value: Dynamic,
+}
+
+///后置脚本执行前调用,清掉上次的结果
+pub fn begin(resp: &ResponseData) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.resp = resp.clone();
+        state.results.clear();
+        state.depth = 0;
+    });
+}
+
+///后置脚本执行后取出结果,脚本出错中断时也记成一条失败
+pub fn finish(error: Option<&EvalAltResult>) -> Vec<TestResult> {
+    let mut results = STATE.with(|state| std::mem::take(&mut state.borrow_mut().results));
+    if let Some(e) = error {
+        let inner = innermost(e);
+        results.push(TestResult {
+            name: "脚本错误".to_owned(),
+            passed: false,
+            message: error_message(inner),
+            position: position_text(inner.position()),
+        });
+    }
+    results
+}
+
+pub fn register_assertions(engine: &mut Engine) {
+    engine.register_type_with_name::<Expectation>("Expectation");
+    engine.register_fn("test", test);
+    engine.register_fn("expect", |value: Dynamic| Expectation { value });
+    engine.register_fn("to_equal", to_equal);
+    engine.register_fn("to_not_equal", to_not_equal);
+    engine.register_fn("to_be_true", to_be_true);
+    engine.register_fn("to_be_false", to_be_false);
+    engine.register_fn("to_exist", to_exist);
+    engine.register_fn("to_contain", to_contain);
+    engine.register_fn("to_match", to_match);
+    engine.register_fn("to_be_above", to_be_above);
+    engine.register_fn("to_be_below", to_be_below);
+    engine.register_fn("expect_status", expect_status);
+    engine.register_fn("expect_header", expect_header);
+    engine.register_fn("expect_header", expect_header_value);
+    engine.register_fn("expect_body_contains", expect_body_contains);
+    engine.register_fn("expect_json_schema", expect_json_schema);
+    engine.register_fn("expect_time_below", expect_time_below);
+}
+
+fn position_text(pos: Position) -> String {
+    if pos.is_none() {
+        String::new()
+    } else {
+        pos.to_string()
+    }
+}
+
+///闭包里的错误会包在ErrorInFunctionCall里,取最里面的
+fn innermost(e: &EvalAltResult) -> &EvalAltResult {
+    match e {
+        EvalAltResult::ErrorInFunctionCall(_, _, inner, _) => innermost(inner),
+        other => other,
+    }
+}
+
+fn error_message(e: &EvalAltResult) -> String {
+    match e {
+        EvalAltResult::ErrorRuntime(value, _) => value.to_string(),
+        other => script_error(other),
+    }
+}
+
+fn set_depth(enter: bool) {
+    STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.depth = if enter {
+            state.depth + 1
+        } else {
+            state.depth.saturating_sub(1)
+        };
+    });
+}
+
+fn test(ctx: NativeCallContext, name: &str, f: FnPtr) -> Result<(), Box<EvalAltResult>> {
+    set_depth(true);
+    let result = f.call_within_context::<Dynamic>(&ctx, ());
+    set_depth(false);
+    let record = match result {
+        Ok(_) => TestResult {
+            name: name.to_owned(),
+            passed: true,
+            message: String::new(),
+            position: position_text(ctx.position()),
+        },
+        Err(e) => {
+            let inner = innermost(&e);
+            TestResult {
+                name: name.to_owned(),
+                passed: false,
+                message: error_message(inner),
+                position: position_text(inner.position()),
+            }
+        }
+    };
+    STATE.with(|state| state.borrow_mut().results.push(record));
+    Ok(())
+}
+
+///在test()里失败时抛错,在外面时直接记一条结果,后面的断言继续执行
+fn check(
+    ctx: &NativeCallContext,
+    name: String,
+    result: Result<(), String>,
+) -> Result<(), Box<EvalAltResult>> {
+    let depth = STATE.with(|state| state.borrow().depth);
+    if depth > 0 {
+        return result
+            .map_err(|msg| EvalAltResult::ErrorRuntime(msg.into(), ctx.position()).into());
+    }
+    let (passed, message) = match result {
+        Ok(()) => (true, String::new()),
+        Err(msg) => (false, msg),
+    };
+    let record = TestResult {
+        name,
+        passed,
+        message,
+        position: position_text(ctx.position()),
+    };
+    STATE.with(|state| state.borrow_mut().results.push(record));
+    Ok(())
+}
+
+fn number(value: &Dynamic) -> Option<f64> {
+    value
+        .as_int()
+        .map(|i| i as f64)
+        .or_else(|_| value.as_float())
+        .ok()
+}
+
+///数字按值比较,1和1.0相等,其他的按JSON比较
+fn dynamic_eq(a: &Dynamic, b: &Dynamic) -> bool {
+    if let (Some(a), Some(b)) = (number(a), number(b)) {
+        return a == b;
+    }
+    let a = rhai::serde::from_dynamic::<Value>(a).ok();
+    let b = rhai::serde::from_dynamic::<Value>(b).ok();
+    a.is_some() && a == b
+}
+
+fn to_equal(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    expected: Dynamic,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = if dynamic_eq(&exp.value, &expected) {
+        Ok(())
+    } else {
+        Err(format!("期望{},实际是{}", expected, exp.value))
+    };
+    check(&ctx, format!("等于{}", expected), result)
+}
+
+fn to_not_equal(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    expected: Dynamic,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = if dynamic_eq(&exp.value, &expected) {
+        Err(format!("不应该是{}", expected))
+    } else {
+        Ok(())
+    };
+    check(&ctx, format!("不等于{}", expected), result)
+}
+
+fn to_be_true(ctx: NativeCallContext, exp: &mut Expectation) -> Result<(), Box<EvalAltResult>> {
+    let result = match exp.value.as_bool() {
+        Ok(true) => Ok(()),
+        _ => Err(format!("期望true,实际是{}", exp.value)),
+    };
+    check(&ctx, "为true".to_owned(), result)
+}
+
+fn to_be_false(ctx: NativeCallContext, exp: &mut Expectation) -> Result<(), Box<EvalAltResult>> {
+    let result = match exp.value.as_bool() {
+        Ok(false) => Ok(()),
+        _ => Err(format!("期望false,实际是{}", exp.value)),
+    };
+    check(&ctx, "为false".to_owned(), result)
+}
+
+fn to_exist(ctx: NativeCallContext, exp: &mut Expectation) -> Result<(), Box<EvalAltResult>> {
+    let result = if exp.value.is_unit() {
+        Err("值不存在".to_owned())
+    } else {
+        Ok(())
+    };
+    check(&ctx, "存在".to_owned(), result)
+}
+
+///字符串包含子串,数组包含元素,对象包含键
+fn to_contain(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    item: Dynamic,
+) -> Result<(), Box<EvalAltResult>> {
+    let value = &exp.value;
+    let contains = if value.is_string() {
+        value.to_string().contains(&item.to_string())
+    } else if value.is_array() {
+        value
+            .read_lock::<rhai::Array>()
+            .is_some_and(|items| items.iter().any(|v| dynamic_eq(v, &item)))
+    } else if value.is_map() {
+        value
+            .read_lock::<rhai::Map>()
+            .is_some_and(|map| map.contains_key(item.to_string().as_str()))
+    } else {
+        false
+    };
+    let result = if contains {
+        Ok(())
+    } else {
+        Err(format!("{}里没有{}", value, item))
+    };
+    check(&ctx, format!("包含{}", item), result)
+}
+
+fn to_match(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    pattern: &str,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = match Regex::new(pattern) {
+        Ok(re) if re.is_match(&exp.value.to_string()) => Ok(()),
+        Ok(_) => Err(format!("{}不匹配{}", exp.value, pattern)),
+        Err(e) => Err(format!("正则错误:{}", e)),
+    };
+    check(&ctx, format!("匹配{}", pattern), result)
+}
+
+fn to_be_above(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    bound: Dynamic,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = match (number(&exp.value), number(&bound)) {
+        (Some(v), Some(b)) if v > b => Ok(()),
+        _ => Err(format!("{}不大于{}", exp.value, bound)),
+    };
+    check(&ctx, format!("大于{}", bound), result)
+}
+
+fn to_be_below(
+    ctx: NativeCallContext,
+    exp: &mut Expectation,
+    bound: Dynamic,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = match (number(&exp.value), number(&bound)) {
+        (Some(v), Some(b)) if v < b => Ok(()),
+        _ => Err(format!("{}不小于{}", exp.value, bound)),
+    };
+    check(&ctx, format!("小于{}", bound), result)
+}
+
+fn current_resp() -> ResponseData {
+    STATE.with(|state| state.borrow().resp.clone())
+}
+
+fn expect_status(ctx: NativeCallContext, code: INT) -> Result<(), Box<EvalAltResult>> {
+    let resp = current_resp();
+    let actual = resp.code.split_whitespace().next().unwrap_or_default();
+    let result = if actual == code.to_string() {
+        Ok(())
+    } else {
+        Err(format!("状态码是{}", resp.code))
+    };
+    check(&ctx, format!("状态码{}", code), result)
+}
+
+fn find_header(resp: &ResponseData, name: &str) -> Option<String> {
+    resp.headers
+        .iter()
+        .find(|item| item.key.eq_ignore_ascii_case(name))
+        .map(|item| item.value.clone())
+}
+
+fn expect_header(ctx: NativeCallContext, name: &str) -> Result<(), Box<EvalAltResult>> {
+    let result = find_header(&current_resp(), name)
+        .map(|_| ())
+        .ok_or(format!("没有响应头{}", name));
+    check(&ctx, format!("有响应头{}", name), result)
+}
+
+///响应头的值包含value
+fn expect_header_value(
+    ctx: NativeCallContext,
+    name: &str,
+    value: &str,
+) -> Result<(), Box<EvalAltResult>> {
+    let result = match find_header(&current_resp(), name) {
+        Some(actual) if actual.contains(value) => Ok(()),
+        Some(actual) => Err(format!("{}是{}", name, actual)),
+        None => Err(format!("没有响应头{}", name)),
+    };
+    check(&ctx, format!("响应头{}包含{}", name, value), result)
+}
+
+fn expect_body_contains(ctx: NativeCallContext, text: &str) -> Result<(), Box<EvalAltResult>> {
+    let result = if current_resp().body.contains(text) {
+        Ok(())
+    } else {
+        Err(format!("响应体里没有{}", text))
+    };
+    check(&ctx, format!("响应体包含{}", text), result)
+}
+
+///schema可以是对象也可以是JSON字符串
+fn expect_json_schema(ctx: NativeCallContext, schema: Dynamic) -> Result<(), Box<EvalAltResult>> {
+    let schema: Result<Value, String> = if schema.is_string() {
+        serde_json::from_str(&schema.to_string()).map_err(|e| format!("schema不是JSON:{}", e))
+    } else {
+        rhai::serde::from_dynamic(&schema).map_err(|e| e.to_string())
+    };
+    let body = serde_json::from_str::<Value>(&current_resp().body)
+        .map_err(|e| format!("响应不是JSON:{}", e));
+    let result = schema.and_then(|schema| {
+        let body = body?;
+        let mut errors = Vec::new();
+        validate_schema(&body, &schema, "$", &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    });
+    check(&ctx, "符合JSON Schema".to_owned(), result)
+}
+
+fn expect_time_below(ctx: NativeCallContext, ms: INT) -> Result<(), Box<EvalAltResult>> {
+    let time = current_resp().time;
+    let result = if time < ms {
+        Ok(())
+    } else {
+        Err(format!("响应时间{}毫秒", time))
+    };
+    check(&ctx, format!("响应时间小于{}毫秒", ms), result)
+}
+
+fn type_matches(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+///只支持常用的type、enum、required、properties、items和数值、长度范围
+fn validate_schema(value: &Value, schema: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else {
+        return;
+    };
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(name)) => vec![name.as_str()],
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|name| type_matches(value, name)) {
+        errors.push(format!("{}应该是{}", path, types.join("|")));
+        return;
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!("{}不在enum里", path));
+        }
+    }
+    if let Some(n) = value.as_f64() {
+        if let Some(min) = schema.get("minimum").and_then(Value::as_f64) {
+            if n < min {
+                errors.push(format!("{}小于{}", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maximum").and_then(Value::as_f64) {
+            if n > max {
+                errors.push(format!("{}大于{}", path, max));
+            }
+        }
+    }
+    if let Some(s) = value.as_str() {
+        let len = s.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if len < min {
+                errors.push(format!("{}长度小于{}", path, min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if len > max {
+                errors.push(format!("{}长度大于{}", path, max));
+            }
+        }
+    }
+    if let Value::Object(map) = value {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for key in required.iter().filter_map(Value::as_str) {
+                if !map.contains_key(key) {
+                    errors.push(format!("{}缺少{}", path, key));
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (key, sub) in properties {
+                if let Some(item) = map.get(key) {
+                    validate_schema(item, sub, &format!("{}.{}", path, key), errors);
+                }
+            }
+        }
+    }
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_schema(item, item_schema, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_schema() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "required": ["id", "name"],
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "tags": {"type": "array", "items": {"type": "string"}}
+            }
+        });
+        let mut errors = Vec::new();
+        validate_schema(
+            &serde_json::json!({"id": 1, "name": "a", "tags": ["x"]}),
+            &schema,
+            "$",
+            &mut errors,
+        );
+        assert!(errors.is_empty());
+        validate_schema(
+            &serde_json::json!({"id": 0, "tags": ["x", 2]}),
+            &schema,
+            "$",
+            &mut errors,
+        );
+        assert_eq!(
+            errors,
+            vec!["$缺少name", "$.id小于1", "$.tags[1]应该是string"]
+        );
+    }
+
+    #[test]
+    fn test_script_assertions() {
+        let mut engine = Engine::new();
+        register_assertions(&mut engine);
+        begin(&ResponseData {
+            code: "200 OK".to_owned(),
+            body: r#"{"id":1}"#.to_owned(),
+            time: 30,
+            ..Default::default()
+        });
+        let script = r#"
+expect_status(200);
+expect_time_below(10);
+test("id", || {
+    expect(1).to_equal(1.0);
+    expect("abc").to_contain("d");
+});
+test("schema", || expect_json_schema(#{type: "object", required: ["id"]}));
+"#;
+        let error = engine.run(script).err();
+        let results = finish(error.as_deref());
+        let summary: Vec<(&str, bool)> = results
+            .iter()
+            .map(|r| (r.name.as_str(), r.passed))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("状态码200", true),
+                ("响应时间小于10毫秒", false),
+                ("id", false),
+                ("schema", true),
+            ]
+        );
+        assert_eq!(results[2].message, "abc里没有d");
+        assert!(results[2].position.contains("line 6"));
+    }
+}
@@ -0,0 +1,628 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Local;
+use md5::Md5;
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Client, Request, Response, StatusCode};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::utils::template::rander_template;
+
+///缓存的oauth2令牌,键是令牌地址+客户端+用户+scope
+static TOKEN_CACHE: Lazy<Mutex<HashMap<String, OAuthToken>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum AuthType {
+    ///使用上级集合的认证
+    #[default]
+    Inherit,
+    None,
+    Basic,
+    Bearer,
+    ApiKey,
+    Digest,
+    OAuth2ClientCredentials,
+    OAuth2Password,
+}
+
+impl AuthType {
+    pub const ALL: [AuthType; 8] = [
+        AuthType::Inherit,
+        AuthType::None,
+        AuthType::Basic,
+        AuthType::Bearer,
+        AuthType::ApiKey,
+        AuthType::Digest,
+        AuthType::OAuth2ClientCredentials,
+        AuthType::OAuth2Password,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthType::Inherit => "继承上级",
+            AuthType::None => "无认证",
+            AuthType::Basic => "Basic",
+            AuthType::Bearer => "Bearer Token",
+            AuthType::ApiKey => "API Key",
+            AuthType::Digest => "Digest",
+            AuthType::OAuth2ClientCredentials => "OAuth2 客户端凭证",
+            AuthType::OAuth2Password => "OAuth2 密码模式",
+        }
+    }
+}
+
+///认证配置,各类型共用字段,切换类型时不丢失已填的值
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct AuthData {
+    pub auth_type: AuthType,
+    pub username: String,
+    pub password: String,
+    pub token: String,
+    pub api_key: String,
+    pub api_value: String,
+    ///API Key放在查询参数里,否则放在请求头里
+    pub api_key_in_query: bool,
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub scope: String,
+}
+
+#[derive(Debug, Clone)]
+struct OAuthToken {
+    access_token: String,
+    refresh_token: Option<String>,
+    ///过期的毫秒时间戳
+    expires_at: Option<i64>,
+}
+
+impl OAuthToken {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|at| at <= Local::now().timestamp_millis())
+    }
+}
+
+impl AuthData {
+    pub fn is_inherit(&self) -> bool {
+        self.auth_type == AuthType::Inherit
+    }
+
+    ///密码、令牌这类凭证字段
+    fn credentials_mut(&mut self) -> [&mut String; 4] {
+        [
+            &mut self.password,
+            &mut self.token,
+            &mut self.api_value,
+            &mut self.client_secret,
+        ]
+    }
+
+    ///凭证里有直接写的值,整个是`${变量}`引用的不算
+    pub fn has_literal_credentials(&self) -> bool {
+        self.clone().credentials_mut().iter().any(|v| is_literal(v))
+    }
+
+    ///直接写的凭证换成replacement,保存和导出时用,变量引用原样保留
+    pub fn without_credentials(&self, replacement: &str) -> AuthData {
+        let mut auth = self.clone();
+        for value in auth.credentials_mut() {
+            if is_literal(value) {
+                *value = replacement.to_owned();
+            }
+        }
+        auth
+    }
+
+    ///渲染所有字段里的模板
+    pub fn render(&self) -> AuthData {
+        let r = |s: &String| rander_template(s).unwrap_or_else(|_| s.clone());
+        AuthData {
+            auth_type: self.auth_type,
+            username: r(&self.username),
+            password: r(&self.password),
+            token: r(&self.token),
+            api_key: r(&self.api_key),
+            api_value: r(&self.api_value),
+            api_key_in_query: self.api_key_in_query,
+            token_url: r(&self.token_url),
+            client_id: r(&self.client_id),
+            client_secret: r(&self.client_secret),
+            scope: r(&self.scope),
+        }
+    }
+
+    ///不需要额外请求的认证直接写进请求里,已经手动设置Authorization的不处理
+    pub fn apply(&self, req: &mut Request) {
+        if req.headers().contains_key(AUTHORIZATION) {
+            return;
+        }
+        let auth = self.render();
+        match auth.auth_type {
+            AuthType::Basic => {
+                let encoded = STANDARD.encode(format!("{}:{}", auth.username, auth.password));
+                set_authorization(req, &format!("Basic {}", encoded));
+            }
+            AuthType::Bearer => {
+                set_authorization(req, &format!("Bearer {}", auth.token));
+            }
+            AuthType::ApiKey => {
+                if auth.api_key.is_empty() {
+                    return;
+                }
+                if auth.api_key_in_query {
+                    req.url_mut()
+                        .query_pairs_mut()
+                        .append_pair(&auth.api_key, &auth.api_value);
+                } else if let (Ok(k), Ok(v)) = (
+                    reqwest::header::HeaderName::from_bytes(auth.api_key.as_bytes()),
+                    HeaderValue::from_str(&auth.api_value),
+                ) {
+                    req.headers_mut().insert(k, v);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn cache_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.token_url, self.client_id, self.username, self.scope
+        )
+    }
+}
+
+fn set_authorization(req: &mut Request, value: &str) {
+    if let Ok(v) = HeaderValue::from_str(value) {
+        req.headers_mut().insert(AUTHORIZATION, v);
+    }
+}
+
+///发送请求,处理Digest质询和OAuth2令牌
+pub async fn execute(client: &Client, mut req: Request, auth: &AuthData) -> Result<Response, String> {
+    if req.headers().contains_key(AUTHORIZATION) {
+        return client.execute(req).await.map_err(|e| e.to_string());
+    }
+    let auth = auth.render();
+    match auth.auth_type {
+        AuthType::Digest => {
+            let retry = req.try_clone();
+            let resp = client.execute(req).await.map_err(|e| e.to_string())?;
+            if resp.status() != StatusCode::UNAUTHORIZED {
+                return Ok(resp);
+            }
+            let challenge = resp
+                .headers()
+                .get(WWW_AUTHENTICATE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(DigestChallenge::parse);
+            let (Some(mut retry), Some(challenge)) = (retry, challenge) else {
+                return Ok(resp);
+            };
+            let uri = match retry.url().query() {
+                Some(q) => format!("{}?{}", retry.url().path(), q),
+                None => retry.url().path().to_owned(),
+            };
+            let cnonce = uuid::Uuid::new_v4().simple().to_string();
+            let header = challenge.authorization(
+                &auth.username,
+                &auth.password,
+                retry.method().as_str(),
+                &uri,
+                &cnonce,
+            );
+            set_authorization(&mut retry, &header);
+            client.execute(retry).await.map_err(|e| e.to_string())
+        }
+        AuthType::OAuth2ClientCredentials | AuthType::OAuth2Password => {
+            let retry = req.try_clone();
+            let token = oauth2_token(client, &auth).await?;
+            set_authorization(&mut req, &format!("Bearer {}", token));
+            let resp = client.execute(req).await.map_err(|e| e.to_string())?;
+            //令牌可能被服务端提前作废,重新获取一次
+            if resp.status() != StatusCode::UNAUTHORIZED {
+                return Ok(resp);
+            }
+            let Some(mut retry) = retry else {
+                return Ok(resp);
+            };
+            if let Ok(mut cache) = TOKEN_CACHE.lock() {
+                cache.remove(&auth.cache_key());
+            }
+            let token = oauth2_token(client, &auth).await?;
+            set_authorization(&mut retry, &format!("Bearer {}", token));
+            client.execute(retry).await.map_err(|e| e.to_string())
+        }
+        _ => client.execute(req).await.map_err(|e| e.to_string()),
+    }
+}
+
+///取缓存的令牌,过期了有refresh_token就刷新,否则重新获取
+async fn oauth2_token(client: &Client, auth: &AuthData) -> Result<String, String> {
+    let key = auth.cache_key();
+    let cached = TOKEN_CACHE
+        .lock()
+        .ok()
+        .and_then(|cache| cache.get(&key).cloned());
+    let token = match cached {
+        Some(token) if !token.is_expired() => return Ok(token.access_token),
+        Some(OAuthToken {
+            refresh_token: Some(refresh),
+            ..
+        }) => match fetch_token(client, auth, Some(&refresh)).await {
+            Ok(token) => token,
+            Err(_) => fetch_token(client, auth, None).await?,
+        },
+        _ => fetch_token(client, auth, None).await?,
+    };
+    let access_token = token.access_token.clone();
+    if let Ok(mut cache) = TOKEN_CACHE.lock() {
+        cache.insert(key, token);
+    }
+    Ok(access_token)
+}
+
+async fn fetch_token(
+    client: &Client,
+    auth: &AuthData,
+    refresh_token: Option<&str>,
+) -> Result<OAuthToken, String> {
+    let mut form: Vec<(&str, &str)> = Vec::new();
+    match (refresh_token, auth.auth_type) {
+        (Some(refresh), _) => {
+            form.push(("grant_type", "refresh_token"));
+            form.push(("refresh_token", refresh));
+        }
+        (None, AuthType::OAuth2Password) => {
+            form.push(("grant_type", "password"));
+            form.push(("username", &auth.username));
+            form.push(("password", &auth.password));
+        }
+        (None, _) => {
+            form.push(("grant_type", "client_credentials"));
+        }
+    }
+    form.push(("client_id", &auth.client_id));
+    if !auth.client_secret.is_empty() {
+        form.push(("client_secret", &auth.client_secret));
+    }
+    if !auth.scope.is_empty() {
+        form.push(("scope", &auth.scope));
+    }
+    let resp = client
+        .post(&auth.token_url)
+        .form(&form)
+        .send()
+        .await
+        .map_err(|e| format!("获取令牌失败:{}", e))?;
+    let status = resp.status();
+    let body = resp.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("获取令牌失败:{} {}", status, body));
+    }
+    let json: Value =
+        serde_json::from_str(&body).map_err(|e| format!("令牌响应解析失败:{}", e))?;
+    let access_token = json["access_token"]
+        .as_str()
+        .ok_or_else(|| format!("令牌响应里没有access_token:{}", body))?
+        .to_owned();
+    let refresh_token = json["refresh_token"]
+        .as_str()
+        .map(str::to_owned)
+        .or_else(|| refresh_token.map(str::to_owned));
+    //提前10秒过期,避免请求途中失效
+    let expires_at = json["expires_in"]
+        .as_i64()
+        .map(|secs| Local::now().timestamp_millis() + (secs - 10).max(0) * 1000);
+    Ok(OAuthToken {
+        access_token,
+        refresh_token,
+        expires_at,
+    })
+}
+
+///WWW-Authenticate里的Digest质询
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DigestChallenge {
+    pub realm: String,
+    pub nonce: String,
+    pub opaque: Option<String>,
+    pub algorithm: Option<String>,
+    pub qop: Option<String>,
+}
+
+impl DigestChallenge {
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = header.trim();
+        if !header.get(..7)?.eq_ignore_ascii_case("digest ") {
+            return None;
+        }
+        let mut challenge = DigestChallenge::default();
+        let mut rest = header[7..].trim();
+        while !rest.is_empty() {
+            let eq = rest.find('=')?;
+            let key = rest[..eq].trim().to_ascii_lowercase();
+            rest = rest[eq + 1..].trim_start();
+            let value;
+            if let Some(quoted) = rest.strip_prefix('"') {
+                let end = quoted.find('"')?;
+                value = quoted[..end].to_owned();
+                rest = &quoted[end + 1..];
+            } else {
+                let end = rest.find(',').unwrap_or(rest.len());
+                value = rest[..end].trim().to_owned();
+                rest = &rest[end..];
+            }
+            rest = rest.trim_start().trim_start_matches(',').trim_start();
+            match key.as_str() {
+                "realm" => challenge.realm = value,
+                "nonce" => challenge.nonce = value,
+                "opaque" => challenge.opaque = Some(value),
+                "algorithm" => challenge.algorithm = Some(value),
+                "qop" => challenge.qop = Some(value),
+                _ => {}
+            }
+        }
+        Some(challenge)
+    }
+
+    ///计算Authorization头,nc固定为1
+    pub fn authorization(
+        &self,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+        cnonce: &str,
+    ) -> String {
+        let algorithm = self.algorithm.clone().unwrap_or_else(|| "MD5".to_owned());
+        let upper = algorithm.to_ascii_uppercase();
+        let hash = |data: String| -> String {
+            if upper.starts_with("SHA-256") {
+                format!("{:x}", Sha256::digest(data.as_bytes()))
+            } else {
+                format!("{:x}", Md5::digest(data.as_bytes()))
+            }
+        };
+        let nc = "00000001";
+        let mut ha1 = hash(format!("{}:{}:{}", username, self.realm, password));
+        if upper.ends_with("-SESS") {
+            ha1 = hash(format!("{}:{}:{}", ha1, self.nonce, cnonce));
+        }
+        let ha2 = hash(format!("{}:{}", method, uri));
+        let qop = self
+            .qop
+            .as_ref()
+            .filter(|q| q.split(',').any(|q| q.trim() == "auth"))
+            .map(|_| "auth");
+        let response = match qop {
+            Some(qop) => hash(format!(
+                "{}:{}:{}:{}:{}:{}",
+                ha1, self.nonce, nc, cnonce, qop, ha2
+            )),
+            None => hash(format!("{}:{}:{}", ha1, self.nonce, ha2)),
+        };
+        let mut header = format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{}\", algorithm={}, response=\"{}\"",
+            username, self.realm, self.nonce, uri, algorithm, response
+        );
+        if let Some(qop) = qop {
+            header.push_str(&format!(", qop={}, nc={}, cnonce=\"{}\"", qop, nc, cnonce));
+        }
+        if let Some(opaque) = &self.opaque {
+            header.push_str(&format!(", opaque=\"{}\"", opaque));
+        }
+        header
+    }
+}
+
+///不是空的,也不是整个引用一个`${变量}`
+pub fn is_literal(value: &str) -> bool {
+    let value = value.trim();
+    let reference = value
+        .strip_prefix("${")
+        .and_then(|v| v.strip_suffix('}'))
+        .is_some_and(|name| !name.contains(['{', '}']));
+    !value.is_empty() && !reference
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::*;
+
+    ///本地的假服务,handler收到(请求行和头,请求体)返回完整的响应
+    fn serve<F>(handler: F) -> String
+    where
+        F: Fn(&str, &str) -> String + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut head = String::new();
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                        break;
+                    }
+                    if let Some(v) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        len = v.trim().parse().unwrap_or(0);
+                    }
+                    head.push_str(&line);
+                }
+                let mut body = vec![0; len];
+                let _ = reader.read_exact(&mut body);
+                let resp = handler(&head, &String::from_utf8_lossy(&body));
+                let _ = stream.write_all(resp.as_bytes());
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    fn response(status: &str, extra: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            extra,
+            body.len(),
+            body
+        )
+    }
+
+    fn header_value<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+        head.lines().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            k.eq_ignore_ascii_case(name).then_some(v.trim())
+        })
+    }
+
+    #[test]
+    fn test_digest_rfc2617() {
+        let challenge = DigestChallenge::parse(
+            r#"Digest realm="testrealm@host.com", qop="auth,auth-int", nonce="dcd98b7102dd2f0e8b11d0f600bfb0c093", opaque="5ccc069c403ebaf9f0171e9517f40e41""#,
+        )
+        .unwrap();
+        assert_eq!(challenge.realm, "testrealm@host.com");
+        let header = challenge.authorization(
+            "Mufasa",
+            "Circle Of Life",
+            "GET",
+            "/dir/index.html",
+            "0a4f113b",
+        );
+        assert!(header.contains(r#"response="6629fae49393a05397450978507c4ef1""#));
+        assert!(header.contains(r#"opaque="5ccc069c403ebaf9f0171e9517f40e41""#));
+    }
+
+    #[test]
+    fn test_digest_execute() {
+        let base = serve(|head, _| match header_value(head, "authorization") {
+            Some(auth) if auth.starts_with("Digest ") => {
+                let cnonce = auth
+                    .split("cnonce=\"")
+                    .nth(1)
+                    .and_then(|s| s.split('"').next())
+                    .unwrap_or_default();
+                let challenge = DigestChallenge {
+                    realm: "local".to_owned(),
+                    nonce: "abc".to_owned(),
+                    qop: Some("auth".to_owned()),
+                    ..Default::default()
+                };
+                let expect = challenge.authorization("user", "pass", "GET", "/secret?a=1", cnonce);
+                if expect == auth {
+                    response("200 OK", "", "ok")
+                } else {
+                    response("403 Forbidden", "", "bad digest")
+                }
+            }
+            _ => response(
+                "401 Unauthorized",
+                "WWW-Authenticate: Digest realm=\"local\", nonce=\"abc\", qop=\"auth\"\r\n",
+                "",
+            ),
+        });
+        let auth = AuthData {
+            auth_type: AuthType::Digest,
+            username: "user".to_owned(),
+            password: "pass".to_owned(),
+            ..Default::default()
+        };
+        let client = Client::new();
+        let req = client
+            .get(format!("{}/secret?a=1", base))
+            .build()
+            .unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let resp = rt.block_on(execute(&client, req, &auth)).unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_oauth2_token_cache() {
+        let token_calls = Arc::new(AtomicUsize::new(0));
+        let calls = token_calls.clone();
+        let base = serve(move |head, body| {
+            if head.starts_with("POST /token") {
+                calls.fetch_add(1, Ordering::SeqCst);
+                if body.contains("grant_type=client_credentials") && body.contains("client_id=app")
+                {
+                    response(
+                        "200 OK",
+                        "Content-Type: application/json\r\n",
+                        r#"{"access_token":"t1","expires_in":3600}"#,
+                    )
+                } else {
+                    response("400 Bad Request", "", "bad grant")
+                }
+            } else if header_value(head, "authorization") == Some("Bearer t1") {
+                response("200 OK", "", "ok")
+            } else {
+                response("401 Unauthorized", "", "")
+            }
+        });
+        let auth = AuthData {
+            auth_type: AuthType::OAuth2ClientCredentials,
+            token_url: format!("{}/token", base),
+            client_id: "app".to_owned(),
+            client_secret: "secret".to_owned(),
+            ..Default::default()
+        };
+        let client = Client::new();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        for _ in 0..2 {
+            let req = client.get(format!("{}/api", base)).build().unwrap();
+            let resp = rt.block_on(execute(&client, req, &auth)).unwrap();
+            assert_eq!(resp.status(), StatusCode::OK);
+        }
+        assert_eq!(token_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_without_credentials() {
+        let auth = AuthData {
+            auth_type: AuthType::Basic,
+            username: "admin".to_owned(),
+            password: "p@ss".to_owned(),
+            token: "${token}".to_owned(),
+            ..Default::default()
+        };
+        assert!(auth.has_literal_credentials());
+        let masked = auth.without_credentials("");
+        assert_eq!(masked.username, "admin");
+        assert_eq!(masked.password, "");
+        assert_eq!(masked.token, "${token}");
+        assert!(!masked.has_literal_credentials());
+    }
+
+    #[test]
+    fn test_basic_apply() {
+        let auth = AuthData {
+            auth_type: AuthType::Basic,
+            username: "Aladdin".to_owned(),
+            password: "open sesame".to_owned(),
+            ..Default::default()
+        };
+        let mut req = Client::new().get("http://localhost/").build().unwrap();
+        auth.apply(&mut req);
+        assert_eq!(
+            req.headers().get(AUTHORIZATION).unwrap(),
+            "Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="
+        );
+    }
+}
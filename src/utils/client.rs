@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use reqwest::{redirect, Client, NoProxy, Proxy};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::client::Resumption;
+use rustls::crypto::{verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use rustls::{ClientConfig, DigitallySignedStruct, RootCertStore, SignatureScheme};
+
+use super::timing::{TimingLayer, TimingResolver, TlsStartStore};
+
+///每组不同的设置对应一个客户端,连接池可以复用
+static CLIENTS: Lazy<Mutex<HashMap<ResolvedClientSettings, Client>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum RedirectPolicy {
+    ///跟随重定向,最多跳转次数
+    Follow(u32),
+    None,
+}
+
+impl Default for RedirectPolicy {
+    fn default() -> Self {
+        RedirectPolicy::Follow(10)
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ProxySettings {
+    ///http://、https://或socks5://开头,为空时使用系统代理(HTTP_PROXY等环境变量)
+    pub url: String,
+    ///逗号分隔的不走代理的主机
+    pub no_proxy: String,
+    ///不使用任何代理,系统代理也忽略
+    pub disabled: bool,
+}
+
+///客户端设置,每一项为None时使用上级的设置,超时为毫秒,0表示不限制
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ClientSettings {
+    pub connect_timeout: Option<u64>,
+    ///从发出请求到读完响应的超时
+    pub read_timeout: Option<u64>,
+    pub redirect: Option<RedirectPolicy>,
+    pub proxy: Option<ProxySettings>,
+    pub verify_tls: Option<bool>,
+}
+
+impl ClientSettings {
+    ///全局默认设置,每一项都有值
+    pub fn global_default() -> Self {
+        Self {
+            connect_timeout: Some(10_000),
+            read_timeout: Some(60_000),
+            redirect: Some(RedirectPolicy::default()),
+            proxy: Some(ProxySettings::default()),
+            verify_tls: Some(false),
+        }
+    }
+
+    ///用下级的设置覆盖
+    pub fn merge(&self, child: &ClientSettings) -> ClientSettings {
+        ClientSettings {
+            connect_timeout: child.connect_timeout.or(self.connect_timeout),
+            read_timeout: child.read_timeout.or(self.read_timeout),
+            redirect: child.redirect.clone().or_else(|| self.redirect.clone()),
+            proxy: child.proxy.clone().or_else(|| self.proxy.clone()),
+            verify_tls: child.verify_tls.or(self.verify_tls),
+        }
+    }
+
+    pub fn resolve(&self) -> ResolvedClientSettings {
+        let settings = ClientSettings::global_default().merge(self);
+        ResolvedClientSettings {
+            connect_timeout: settings.connect_timeout.unwrap_or_default(),
+            read_timeout: settings.read_timeout.unwrap_or_default(),
+            redirect: settings.redirect.unwrap_or_default(),
+            proxy: settings.proxy.unwrap_or_default(),
+            verify_tls: settings.verify_tls.unwrap_or_default(),
+        }
+    }
+}
+
+///合并后实际生效的设置
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct ResolvedClientSettings {
+    pub connect_timeout: u64,
+    pub read_timeout: u64,
+    pub redirect: RedirectPolicy,
+    pub proxy: ProxySettings,
+    pub verify_tls: bool,
+}
+
+impl ResolvedClientSettings {
+    fn build(&self) -> Result<Client, String> {
+        let mut builder = Client::builder()
+            .dns_resolver(Arc::new(TimingResolver))
+            .connector_layer(TimingLayer)
+            .use_preconfigured_tls(tls_config(self.verify_tls)?);
+        if self.connect_timeout > 0 {
+            builder = builder.connect_timeout(Duration::from_millis(self.connect_timeout));
+        }
+        //每次读取的超时,不限制整个请求,流式响应可以一直接收
+        if self.read_timeout > 0 {
+            builder = builder.read_timeout(Duration::from_millis(self.read_timeout));
+        }
+        builder = match self.redirect {
+            RedirectPolicy::Follow(max) => {
+                builder.redirect(redirect::Policy::limited(max as usize))
+            }
+            RedirectPolicy::None => builder.redirect(redirect::Policy::none()),
+        };
+        if self.proxy.disabled {
+            builder = builder.no_proxy();
+        } else if !self.proxy.url.trim().is_empty() {
+            let proxy = Proxy::all(self.proxy.url.trim())
+                .map_err(|e| format!("代理地址错误:{}", e))?
+                .no_proxy(NoProxy::from_string(&self.proxy.no_proxy));
+            builder = builder.proxy(proxy);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+}
+
+///系统的根证书,只加载一次
+static ROOT_CERTS: Lazy<Arc<RootCertStore>> = Lazy::new(|| {
+    let result = rustls_native_certs::load_native_certs();
+    if !result.errors.is_empty() {
+        log::warn!("加载系统证书出错:{:?}", result.errors);
+    }
+    let mut roots = RootCertStore::empty();
+    roots.add_parsable_certificates(result.certs);
+    Arc::new(roots)
+});
+
+///用rustls建立TLS连接,会话缓存顺便记下握手开始的时间,verify为false时不校验证书
+fn tls_config(verify: bool) -> Result<ClientConfig, String> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+    let mut config = if verify {
+        builder.with_root_certificates(ROOT_CERTS.clone())
+    } else {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(NoVerifier(provider)))
+    }
+    .with_no_client_auth();
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    config.resumption = Resumption::store(Arc::new(TlsStartStore::new(256)));
+    Ok(config)
+}
+
+///不校验证书链和域名,签名仍然要对
+#[derive(Debug)]
+struct NoVerifier(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for NoVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        verify_tls12_signature(message, cert, dss, algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        let algorithms = &self.0.signature_verification_algorithms;
+        verify_tls13_signature(message, cert, dss, algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+///取对应设置的客户端,没有就新建一个缓存起来
+pub fn client_for(settings: &ResolvedClientSettings) -> Result<Client, String> {
+    let mut clients = CLIENTS.lock().map_err(|e| e.to_string())?;
+    if let Some(client) = clients.get(settings) {
+        return Ok(client.clone());
+    }
+    let client = settings.build()?;
+    clients.insert(settings.clone(), client.clone());
+    Ok(client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_settings() {
+        let global = ClientSettings {
+            connect_timeout: Some(1000),
+            verify_tls: Some(true),
+            ..Default::default()
+        };
+        let collection = ClientSettings {
+            connect_timeout: Some(2000),
+            redirect: Some(RedirectPolicy::None),
+            ..Default::default()
+        };
+        let request = ClientSettings {
+            verify_tls: Some(false),
+            ..Default::default()
+        };
+        let resolved = global.merge(&collection).merge(&request).resolve();
+        assert_eq!(resolved.connect_timeout, 2000);
+        assert_eq!(resolved.read_timeout, 60_000);
+        assert_eq!(resolved.redirect, RedirectPolicy::None);
+        assert!(!resolved.verify_tls);
+    }
+
+    #[test]
+    fn test_client_cache() {
+        let settings = ClientSettings::default().resolve();
+        let other = ResolvedClientSettings {
+            verify_tls: !settings.verify_tls,
+            ..settings.clone()
+        };
+        assert!(client_for(&settings).is_ok());
+        assert!(client_for(&settings).is_ok());
+        assert!(client_for(&other).is_ok());
+        let clients = CLIENTS.lock().unwrap();
+        assert!(clients.contains_key(&settings));
+        assert!(clients.contains_key(&other));
+    }
+}
@@ -0,0 +1,733 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Value};
+
+use crate::request_data::{BodyType, RequestData};
+use crate::utils::auth::{AuthData, AuthType};
+use crate::utils::template::rander_template;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CodeLang {
+    #[default]
+    RustReqwest,
+    PythonRequests,
+    JsFetch,
+    JavaHttpClient,
+    GoNetHttp,
+    Wget,
+    Httpie,
+}
+
+impl CodeLang {
+    pub const ALL: [CodeLang; 7] = [
+        CodeLang::RustReqwest,
+        CodeLang::PythonRequests,
+        CodeLang::JsFetch,
+        CodeLang::JavaHttpClient,
+        CodeLang::GoNetHttp,
+        CodeLang::Wget,
+        CodeLang::Httpie,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CodeLang::RustReqwest => "Rust reqwest",
+            CodeLang::PythonRequests => "Python requests",
+            CodeLang::JsFetch => "JavaScript fetch",
+            CodeLang::JavaHttpClient => "Java HttpClient",
+            CodeLang::GoNetHttp => "Go net/http",
+            CodeLang::Wget => "Shell wget",
+            CodeLang::Httpie => "Shell HTTPie",
+        }
+    }
+
+    ///语法高亮用的扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            CodeLang::RustReqwest => "rs",
+            CodeLang::PythonRequests => "py",
+            CodeLang::JsFetch => "js",
+            CodeLang::JavaHttpClient => "java",
+            CodeLang::GoNetHttp => "go",
+            CodeLang::Wget | CodeLang::Httpie => "sh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SnippetBody {
+    None,
+    Text(String),
+    Form(Vec<(String, String)>),
+    ///(名称,值,是否文件)
+    Multipart(Vec<(String, String, bool)>),
+    ///文件路径
+    File(String),
+}
+
+///生成代码用的请求,只包含勾选的请求头
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: SnippetBody,
+}
+
+impl Snippet {
+    ///render为false时保留模板原文,req的auth需要是已经解析过继承的
+    pub fn from_request(req: &RequestData, render: bool) -> Result<Self, String> {
+        let r = |s: &str| {
+            if render {
+                rander_template(s).unwrap_or_else(|_| s.to_owned())
+            } else {
+                s.to_owned()
+            }
+        };
+        let mut url = if render {
+            req.render_url()
+        } else {
+            req.url.clone()
+        };
+        let mut headers: Vec<(String, String)> = req
+            .headers
+            .iter()
+            .filter(|h| h.selected && !h.key.is_empty())
+            .map(|h| (h.key.clone(), r(&h.value)))
+            .collect();
+        let has_header = |headers: &[(String, String)], name: &str| {
+            headers.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+        };
+
+        let auth = if render {
+            req.auth.render()
+        } else {
+            req.auth.clone()
+        };
+        if !has_header(&headers, "Authorization") {
+            add_auth(&auth, &mut url, &mut headers);
+        }
+
+        let body = match req.body_type {
+            BodyType::None => SnippetBody::None,
+            BodyType::Raw | BodyType::Json if req.body.is_empty() => SnippetBody::None,
+            BodyType::Raw | BodyType::Json => SnippetBody::Text(r(&req.body)),
+            BodyType::Json5 if req.body.is_empty() => SnippetBody::None,
+            BodyType::Json5 => {
+                let body = r(&req.body);
+                //带着模板时可能解析不了,原样输出
+                match json5::from_str::<Value>(&body) {
+                    Ok(value) => {
+                        SnippetBody::Text(serde_json::to_string_pretty(&value).unwrap_or(body))
+                    }
+                    Err(_) => SnippetBody::Text(body),
+                }
+            }
+            BodyType::GraphQL => {
+                let value = if render {
+                    req.graphql.render()?
+                } else {
+                    let variables = json5::from_str::<Value>(&req.graphql.variables)
+                        .unwrap_or_else(|_| Value::String(req.graphql.variables.clone()));
+                    if req.graphql.variables.trim().is_empty() {
+                        json!({ "query": req.graphql.query })
+                    } else {
+                        json!({ "query": req.graphql.query, "variables": variables })
+                    }
+                };
+                SnippetBody::Text(serde_json::to_string_pretty(&value).map_err(|e| e.to_string())?)
+            }
+            BodyType::FormUrlencoded => SnippetBody::Form(
+                req.form
+                    .iter()
+                    .filter(|item| item.selected)
+                    .map(|item| (item.key.clone(), r(&item.value)))
+                    .collect(),
+            ),
+            BodyType::Multipart => SnippetBody::Multipart(
+                req.multipart
+                    .iter()
+                    .filter(|item| item.selected)
+                    .map(|item| {
+                        let value = if item.is_file {
+                            item.value.clone()
+                        } else {
+                            r(&item.value)
+                        };
+                        (item.key.clone(), value, item.is_file)
+                    })
+                    .collect(),
+            ),
+            BodyType::Binary if req.binary.is_empty() => SnippetBody::None,
+            BodyType::Binary => SnippetBody::File(req.binary.clone()),
+        };
+        if matches!(body, SnippetBody::Multipart(_)) {
+            //boundary由各语言的库生成
+            headers.retain(|(k, _)| !k.eq_ignore_ascii_case("content-type"));
+        } else if body != SnippetBody::None && !has_header(&headers, "Content-Type") {
+            if let Some(content_type) = req.body_type.content_type() {
+                headers.push(("Content-Type".to_owned(), content_type.to_owned()));
+            }
+        }
+        Ok(Self {
+            method: req.method.to_string(),
+            url,
+            headers,
+            body,
+        })
+    }
+
+    pub fn generate(&self, lang: CodeLang) -> String {
+        match lang {
+            CodeLang::RustReqwest => self.rust(),
+            CodeLang::PythonRequests => self.python(),
+            CodeLang::JsFetch => self.javascript(),
+            CodeLang::JavaHttpClient => self.java(),
+            CodeLang::GoNetHttp => self.go(),
+            CodeLang::Wget => self.wget(),
+            CodeLang::Httpie => self.httpie(),
+        }
+    }
+
+    fn rust(&self) -> String {
+        let mut code = String::new();
+        let _ = writeln!(code, "let client = reqwest::Client::new();");
+        if let SnippetBody::Multipart(items) = &self.body {
+            let mut lines = vec!["let form = reqwest::multipart::Form::new()".to_owned()];
+            for (k, v, is_file) in items {
+                if *is_file {
+                    lines.push(format!(
+                        "    .part({}, reqwest::multipart::Part::bytes(std::fs::read({})?).file_name({}))",
+                        rust_str(k),
+                        rust_str(v),
+                        rust_str(&file_name(v))
+                    ));
+                } else {
+                    lines.push(format!("    .text({}, {})", rust_str(k), rust_str(v)));
+                }
+            }
+            let _ = writeln!(code, "{};", lines.join("\n"));
+        }
+        let _ = writeln!(code, "let response = client");
+        let _ = writeln!(
+            code,
+            "    .request(reqwest::Method::{}, {})",
+            self.method,
+            rust_str(&self.url)
+        );
+        for (k, v) in &self.headers {
+            let _ = writeln!(code, "    .header({}, {})", rust_str(k), rust_str(v));
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Text(text) => {
+                let _ = writeln!(code, "    .body({})", rust_str(text));
+            }
+            SnippetBody::Form(items) => {
+                let pairs: Vec<String> = items
+                    .iter()
+                    .map(|(k, v)| format!("({}, {})", rust_str(k), rust_str(v)))
+                    .collect();
+                let _ = writeln!(code, "    .form(&[{}])", pairs.join(", "));
+            }
+            SnippetBody::Multipart(_) => {
+                let _ = writeln!(code, "    .multipart(form)");
+            }
+            SnippetBody::File(path) => {
+                let _ = writeln!(code, "    .body(std::fs::read({})?)", rust_str(path));
+            }
+        }
+        let _ = writeln!(code, "    .send()\n    .await?;");
+        let _ = write!(code, "println!(\"{{}}\", response.text().await?);");
+        code
+    }
+
+    fn python(&self) -> String {
+        let mut code = String::from("import requests\n\n");
+        let _ = writeln!(code, "url = {}", json_str(&self.url));
+        let mut args = vec!["url".to_owned()];
+        if !self.headers.is_empty() {
+            let _ = writeln!(code, "headers = {{");
+            for (k, v) in &self.headers {
+                let _ = writeln!(code, "    {}: {},", json_str(k), json_str(v));
+            }
+            let _ = writeln!(code, "}}");
+            args.push("headers=headers".to_owned());
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Text(text) => {
+                let _ = writeln!(code, "data = {}", json_str(text));
+                args.push("data=data.encode(\"utf-8\")".to_owned());
+            }
+            SnippetBody::Form(items) => {
+                let _ = writeln!(code, "data = [");
+                for (k, v) in items {
+                    let _ = writeln!(code, "    ({}, {}),", json_str(k), json_str(v));
+                }
+                let _ = writeln!(code, "]");
+                args.push("data=data".to_owned());
+            }
+            SnippetBody::Multipart(items) => {
+                let _ = writeln!(code, "files = [");
+                for (k, v, is_file) in items {
+                    if *is_file {
+                        let _ = writeln!(
+                            code,
+                            "    ({}, ({}, open({}, \"rb\"))),",
+                            json_str(k),
+                            json_str(&file_name(v)),
+                            json_str(v)
+                        );
+                    } else {
+                        let _ = writeln!(code, "    ({}, (None, {})),", json_str(k), json_str(v));
+                    }
+                }
+                let _ = writeln!(code, "]");
+                args.push("files=files".to_owned());
+            }
+            SnippetBody::File(path) => {
+                let _ = writeln!(code, "data = open({}, \"rb\")", json_str(path));
+                args.push("data=data".to_owned());
+            }
+        }
+        let _ = writeln!(
+            code,
+            "\nresponse = requests.request({}, {})",
+            json_str(&self.method),
+            args.join(", ")
+        );
+        code.push_str("print(response.text)");
+        code
+    }
+
+    fn javascript(&self) -> String {
+        let mut code = String::new();
+        if matches!(&self.body, SnippetBody::File(_))
+            || matches!(&self.body, SnippetBody::Multipart(items) if items.iter().any(|i| i.2))
+        {
+            code.push_str("import fs from \"node:fs\";\n\n");
+        }
+        let mut options = vec![format!("  method: {},", json_str(&self.method))];
+        if !self.headers.is_empty() {
+            let _ = writeln!(code, "const headers = new Headers();");
+            for (k, v) in &self.headers {
+                let _ = writeln!(code, "headers.append({}, {});", json_str(k), json_str(v));
+            }
+            options.push("  headers,".to_owned());
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Text(text) => {
+                let _ = writeln!(code, "const body = {};", json_str(text));
+            }
+            SnippetBody::Form(items) => {
+                let _ = writeln!(code, "const body = new URLSearchParams();");
+                for (k, v) in items {
+                    let _ = writeln!(code, "body.append({}, {});", json_str(k), json_str(v));
+                }
+            }
+            SnippetBody::Multipart(items) => {
+                let _ = writeln!(code, "const body = new FormData();");
+                for (k, v, is_file) in items {
+                    if *is_file {
+                        let _ = writeln!(
+                            code,
+                            "body.append({}, new Blob([fs.readFileSync({})]), {});",
+                            json_str(k),
+                            json_str(v),
+                            json_str(&file_name(v))
+                        );
+                    } else {
+                        let _ = writeln!(code, "body.append({}, {});", json_str(k), json_str(v));
+                    }
+                }
+            }
+            SnippetBody::File(path) => {
+                let _ = writeln!(code, "const body = fs.readFileSync({});", json_str(path));
+            }
+        }
+        if self.body != SnippetBody::None {
+            options.push("  body,".to_owned());
+        }
+        let _ = writeln!(
+            code,
+            "\nconst response = await fetch({}, {{\n{}\n}});",
+            json_str(&self.url),
+            options.join("\n")
+        );
+        code.push_str("console.log(await response.text());");
+        code
+    }
+
+    fn java(&self) -> String {
+        let mut code = String::from(
+            "import java.net.URI;\nimport java.net.http.HttpClient;\nimport java.net.http.HttpRequest;\nimport java.net.http.HttpResponse;\n",
+        );
+        if matches!(&self.body, SnippetBody::File(_) | SnippetBody::Multipart(_)) {
+            code.push_str("import java.nio.file.Path;\n");
+        }
+        if matches!(&self.body, SnippetBody::Form(_)) {
+            code.push_str(
+                "import java.net.URLEncoder;\nimport java.nio.charset.StandardCharsets;\n",
+            );
+        }
+        code.push('\n');
+        let publisher = match &self.body {
+            SnippetBody::None => "HttpRequest.BodyPublishers.noBody()".to_owned(),
+            SnippetBody::Text(text) => {
+                format!("HttpRequest.BodyPublishers.ofString({})", json_str(text))
+            }
+            SnippetBody::Form(items) => {
+                let pairs: Vec<String> = items
+                    .iter()
+                    .map(|(k, v)| {
+                        format!(
+                            "URLEncoder.encode({}, StandardCharsets.UTF_8) + \"=\" + URLEncoder.encode({}, StandardCharsets.UTF_8)",
+                            json_str(k),
+                            json_str(v)
+                        )
+                    })
+                    .collect();
+                let _ = writeln!(
+                    code,
+                    "String form = String.join(\"&\",\n    {});",
+                    pairs.join(",\n    ")
+                );
+                "HttpRequest.BodyPublishers.ofString(form)".to_owned()
+            }
+            SnippetBody::Multipart(items) => {
+                //HttpClient没有multipart,手动拼
+                let _ = writeln!(code, "String boundary = \"----HttpTestToolBoundary\";");
+                let mut parts = Vec::new();
+                for (k, v, is_file) in items {
+                    let name = k.replace('"', "%22");
+                    if *is_file {
+                        parts.push(format!(
+                            "HttpRequest.BodyPublishers.ofString(\"--\" + boundary + {})",
+                            json_str(&format!(
+                                "\r\nContent-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+                                name,
+                                file_name(v).replace('"', "%22")
+                            ))
+                        ));
+                        parts.push(format!(
+                            "HttpRequest.BodyPublishers.ofFile(Path.of({}))",
+                            json_str(v)
+                        ));
+                        parts.push("HttpRequest.BodyPublishers.ofString(\"\\r\\n\")".to_owned());
+                    } else {
+                        parts.push(format!(
+                            "HttpRequest.BodyPublishers.ofString(\"--\" + boundary + {})",
+                            json_str(&format!(
+                                "\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+                                name, v
+                            ))
+                        ));
+                    }
+                }
+                parts.push(
+                    "HttpRequest.BodyPublishers.ofString(\"--\" + boundary + \"--\\r\\n\")"
+                        .to_owned(),
+                );
+                format!(
+                    "HttpRequest.BodyPublishers.concat(\n        {})",
+                    parts.join(",\n        ")
+                )
+            }
+            SnippetBody::File(path) => {
+                format!(
+                    "HttpRequest.BodyPublishers.ofFile(Path.of({}))",
+                    json_str(path)
+                )
+            }
+        };
+        let _ = writeln!(code, "HttpClient client = HttpClient.newHttpClient();");
+        let _ = writeln!(code, "HttpRequest request = HttpRequest.newBuilder()");
+        let _ = writeln!(code, "    .uri(URI.create({}))", json_str(&self.url));
+        for (k, v) in &self.headers {
+            let _ = writeln!(code, "    .header({}, {})", json_str(k), json_str(v));
+        }
+        if matches!(&self.body, SnippetBody::Multipart(_)) {
+            let _ = writeln!(
+                code,
+                "    .header(\"Content-Type\", \"multipart/form-data; boundary=\" + boundary)"
+            );
+        }
+        let _ = writeln!(
+            code,
+            "    .method({}, {})",
+            json_str(&self.method),
+            publisher
+        );
+        let _ = writeln!(code, "    .build();");
+        let _ = writeln!(
+            code,
+            "HttpResponse<String> response = client.send(request, HttpResponse.BodyHandlers.ofString());"
+        );
+        code.push_str("System.out.println(response.body());");
+        code
+    }
+
+    fn go(&self) -> String {
+        let mut imports: BTreeSet<&str> = ["fmt", "io", "net/http"].into_iter().collect();
+        let mut body_code = String::new();
+        let body_var = match &self.body {
+            SnippetBody::None => "nil",
+            SnippetBody::Text(text) => {
+                imports.insert("strings");
+                let _ = writeln!(body_code, "\tbody := strings.NewReader({})", json_str(text));
+                "body"
+            }
+            SnippetBody::Form(items) => {
+                imports.insert("strings");
+                imports.insert("net/url");
+                let _ = writeln!(body_code, "\tform := url.Values{{}}");
+                for (k, v) in items {
+                    let _ = writeln!(body_code, "\tform.Add({}, {})", json_str(k), json_str(v));
+                }
+                let _ = writeln!(body_code, "\tbody := strings.NewReader(form.Encode())");
+                "body"
+            }
+            SnippetBody::Multipart(items) => {
+                imports.insert("bytes");
+                imports.insert("mime/multipart");
+                let _ = writeln!(body_code, "\tbody := &bytes.Buffer{{}}");
+                let _ = writeln!(body_code, "\twriter := multipart.NewWriter(body)");
+                for (k, v, is_file) in items {
+                    if *is_file {
+                        imports.insert("os");
+                        let _ = writeln!(
+                            body_code,
+                            "\tif file, err := os.Open({}); err == nil {{\n\t\tpart, _ := writer.CreateFormFile({}, {})\n\t\tio.Copy(part, file)\n\t\tfile.Close()\n\t}}",
+                            json_str(v),
+                            json_str(k),
+                            json_str(&file_name(v))
+                        );
+                    } else {
+                        let _ = writeln!(
+                            body_code,
+                            "\twriter.WriteField({}, {})",
+                            json_str(k),
+                            json_str(v)
+                        );
+                    }
+                }
+                let _ = writeln!(body_code, "\twriter.Close()");
+                "body"
+            }
+            SnippetBody::File(path) => {
+                imports.insert("os");
+                let _ = writeln!(
+                    body_code,
+                    "\tbody, err := os.Open({})\n\tif err != nil {{\n\t\tpanic(err)\n\t}}\n\tdefer body.Close()",
+                    json_str(path)
+                );
+                "body"
+            }
+        };
+        let mut code = String::from("package main\n\nimport (\n");
+        for import in imports {
+            let _ = writeln!(code, "\t\"{}\"", import);
+        }
+        code.push_str(")\n\nfunc main() {\n");
+        code.push_str(&body_code);
+        let _ = writeln!(
+            code,
+            "\treq, err := http.NewRequest({}, {}, {})",
+            json_str(&self.method),
+            json_str(&self.url),
+            body_var
+        );
+        code.push_str("\tif err != nil {\n\t\tpanic(err)\n\t}\n");
+        for (k, v) in &self.headers {
+            let _ = writeln!(code, "\treq.Header.Add({}, {})", json_str(k), json_str(v));
+        }
+        if matches!(&self.body, SnippetBody::Multipart(_)) {
+            code.push_str("\treq.Header.Set(\"Content-Type\", writer.FormDataContentType())\n");
+        }
+        code.push_str(
+            "\tresp, err := http.DefaultClient.Do(req)\n\tif err != nil {\n\t\tpanic(err)\n\t}\n",
+        );
+        code.push_str("\tdefer resp.Body.Close()\n\tdata, _ := io.ReadAll(resp.Body)\n\tfmt.Println(string(data))\n}");
+        code
+    }
+
+    fn wget(&self) -> String {
+        let mut parts = vec![
+            "wget --quiet".to_owned(),
+            format!("--method {}", self.method),
+        ];
+        for (k, v) in &self.headers {
+            parts.push(format!("--header {}", shell_str(&format!("{}: {}", k, v))));
+        }
+        match &self.body {
+            SnippetBody::None => {}
+            SnippetBody::Text(text) => parts.push(format!("--body-data {}", shell_str(text))),
+            SnippetBody::Form(items) => {
+                let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+                for (k, v) in items {
+                    serializer.append_pair(k, v);
+                }
+                parts.push(format!("--body-data {}", shell_str(&serializer.finish())));
+            }
+            SnippetBody::Multipart(_) => {
+                return "# wget不支持multipart/form-data,请使用curl或HTTPie".to_owned();
+            }
+            SnippetBody::File(path) => parts.push(format!("--body-file {}", shell_str(path))),
+        }
+        parts.push("--output-document -".to_owned());
+        parts.push(shell_str(&self.url));
+        parts.join(" \\\n  ")
+    }
+
+    fn httpie(&self) -> String {
+        let mut parts = vec!["http".to_owned()];
+        match &self.body {
+            SnippetBody::Form(_) => parts.push("--form".to_owned()),
+            SnippetBody::Multipart(_) => parts.push("--multipart".to_owned()),
+            SnippetBody::Text(text) => parts.push(format!("--raw {}", shell_str(text))),
+            SnippetBody::None | SnippetBody::File(_) => {}
+        }
+        parts.push(self.method.clone());
+        parts.push(shell_str(&self.url));
+        for (k, v) in &self.headers {
+            parts.push(shell_str(&format!("{}:{}", k, v)));
+        }
+        match &self.body {
+            SnippetBody::Form(items) => {
+                for (k, v) in items {
+                    parts.push(shell_str(&format!("{}={}", k, v)));
+                }
+            }
+            SnippetBody::Multipart(items) => {
+                for (k, v, is_file) in items {
+                    let sep = if *is_file { "@" } else { "=" };
+                    parts.push(shell_str(&format!("{}{}{}", k, sep, v)));
+                }
+            }
+            SnippetBody::File(path) => parts.push(format!("< {}", shell_str(path))),
+            SnippetBody::None | SnippetBody::Text(_) => {}
+        }
+        parts.join(" \\\n  ")
+    }
+}
+
+///不需要额外请求的认证写成请求头或查询参数,和AuthData::apply一致
+fn add_auth(auth: &AuthData, url: &mut String, headers: &mut Vec<(String, String)>) {
+    match auth.auth_type {
+        AuthType::Basic => {
+            let encoded = STANDARD.encode(format!("{}:{}", auth.username, auth.password));
+            headers.push(("Authorization".to_owned(), format!("Basic {}", encoded)));
+        }
+        AuthType::Bearer => {
+            headers.push(("Authorization".to_owned(), format!("Bearer {}", auth.token)));
+        }
+        AuthType::ApiKey if !auth.api_key.is_empty() => {
+            if auth.api_key_in_query {
+                let pair = url::form_urlencoded::Serializer::new(String::new())
+                    .append_pair(&auth.api_key, &auth.api_value)
+                    .finish();
+                url.push(if url.contains('?') { '&' } else { '?' });
+                url.push_str(&pair);
+            } else {
+                headers.push((auth.api_key.clone(), auth.api_value.clone()));
+            }
+        }
+        _ => {}
+    }
+}
+
+fn file_name(path: &str) -> String {
+    std::path::Path::new(path)
+        .file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_owned())
+}
+
+fn rust_str(s: &str) -> String {
+    format!("{:?}", s)
+}
+
+///Python、JavaScript、Java和Go都兼容JSON的字符串写法
+fn json_str(s: &str) -> String {
+    Value::String(s.to_owned()).to_string()
+}
+
+fn shell_str(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::header_ui::SelectKeyValueItem;
+    use crate::request_data::{Method, MultipartItem};
+
+    fn json_request() -> RequestData {
+        let mut req = RequestData {
+            method: Method::POST,
+            url: "https://api.example.com/users".to_owned(),
+            body_type: BodyType::Json5,
+            body: "{name: '${1+1}'}".to_owned(),
+            ..Default::default()
+        };
+        req.headers.push(SelectKeyValueItem::new("X-Trace", "abc"));
+        let mut disabled = SelectKeyValueItem::new("X-Disabled", "1");
+        disabled.selected = false;
+        req.headers.push(disabled);
+        req
+    }
+
+    #[test]
+    fn test_from_request() {
+        let snippet = Snippet::from_request(&json_request(), true).unwrap();
+        assert_eq!(snippet.method, "POST");
+        assert_eq!(
+            snippet.headers,
+            vec![
+                ("X-Trace".to_owned(), "abc".to_owned()),
+                ("Content-Type".to_owned(), "application/json".to_owned())
+            ]
+        );
+        assert_eq!(
+            snippet.body,
+            SnippetBody::Text("{\n  \"name\": \"2\"\n}".to_owned())
+        );
+
+        let raw = Snippet::from_request(&json_request(), false).unwrap();
+        assert_eq!(
+            raw.body,
+            SnippetBody::Text("{\n  \"name\": \"${1+1}\"\n}".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_generate() {
+        let snippet = Snippet::from_request(&json_request(), true).unwrap();
+        let python = snippet.generate(CodeLang::PythonRequests);
+        assert!(python.contains("requests.request(\"POST\", url, headers=headers, data="));
+        let go = snippet.generate(CodeLang::GoNetHttp);
+        assert!(go.contains("\t\"strings\"\n"));
+        assert!(!go.contains("\"os\""));
+        let rust = snippet.generate(CodeLang::RustReqwest);
+        assert!(rust.contains(".request(reqwest::Method::POST, \"https://api.example.com/users\")"));
+
+        let mut req = json_request();
+        req.body_type = BodyType::Multipart;
+        req.multipart.push(MultipartItem::new("name", "a", false));
+        req.multipart
+            .push(MultipartItem::new("file", "/tmp/a.png", true));
+        let snippet = Snippet::from_request(&req, true).unwrap();
+        assert!(snippet.headers.iter().all(|(k, _)| k != "Content-Type"));
+        assert!(snippet
+            .generate(CodeLang::Httpie)
+            .contains("'file@/tmp/a.png'"));
+        assert!(snippet.generate(CodeLang::Wget).starts_with('#'));
+        assert!(snippet
+            .generate(CodeLang::JsFetch)
+            .contains("fs.readFileSync(\"/tmp/a.png\")"));
+    }
+}
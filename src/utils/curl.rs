@@ -0,0 +1,470 @@
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Request;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{BodyType, MultipartItem, RequestData};
+use crate::utils::auth::{AuthData, AuthType};
+use crate::utils::client::{ProxySettings, RedirectPolicy, ResolvedClientSettings};
+use crate::utils::template::rander_template;
+
+///按shell的规则切分命令行,支持单双引号、$'...'和反斜杠续行
+fn split_args(cmd: &str) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = cmd.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err("单引号没有结束".to_owned()),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some('\n') => {}
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err("双引号没有结束".to_owned()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("双引号没有结束".to_owned()),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => current.push('\n'),
+                            Some('r') => current.push('\r'),
+                            Some('t') => current.push('\t'),
+                            Some('u') => {
+                                let hex: String = chars.by_ref().take(4).collect();
+                                let code = u32::from_str_radix(&hex, 16)
+                                    .map_err(|e| format!("\\u{}不合法:{}", hex, e))?;
+                                current.extend(char::from_u32(code));
+                            }
+                            Some(c) => current.push(c),
+                            None => return Err("$'没有结束".to_owned()),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err("$'没有结束".to_owned()),
+                    }
+                }
+            }
+            '\\' => match chars.next() {
+                //续行
+                Some('\n') | Some('\r') => {}
+                Some(c) => {
+                    in_arg = true;
+                    current.push(c);
+                }
+                None => {}
+            },
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+
+///解析curl命令,支持浏览器开发者工具里"复制为cURL"的格式
+pub fn parse_curl(cmd: &str) -> Result<RequestData, String> {
+    let args = split_args(cmd.trim())?;
+    let mut iter = args.into_iter();
+    match iter.next() {
+        Some(first) if first == "curl" => {}
+        _ => return Err("不是curl命令".to_owned()),
+    }
+
+    let mut req = RequestData {
+        body_type: BodyType::None,
+        ..Default::default()
+    };
+    let mut method: Option<String> = None;
+    let mut url: Option<String> = None;
+    let mut data: Vec<String> = Vec::new();
+    let mut get = false;
+    let mut follow = false;
+    let mut max_redirs: Option<u32> = None;
+    let mut no_proxy = String::new();
+    while let Some(arg) = iter.next() {
+        //-XPOST和--request=POST这样的写法
+        let (flag, inline) =
+            if let Some((flag, value)) = arg.strip_prefix("--").and_then(|a| a.split_once('=')) {
+                (format!("--{}", flag), Some(value.to_owned()))
+            } else if arg.len() > 2
+                && arg.starts_with('-')
+                && !arg.starts_with("--")
+                && arg.is_char_boundary(2)
+            {
+                (arg[..2].to_owned(), Some(arg[2..].to_owned()))
+            } else {
+                (arg.clone(), None)
+            };
+        let mut value = || -> Result<String, String> {
+            inline
+                .clone()
+                .or_else(|| iter.next())
+                .ok_or_else(|| format!("{}缺少参数", flag))
+        };
+        match flag.as_str() {
+            "-X" | "--request" => method = Some(value()?.to_uppercase()),
+            "-H" | "--header" => {
+                let header = value()?;
+                if let Some((k, v)) = header.split_once(':') {
+                    req.headers
+                        .push(SelectKeyValueItem::new(k.trim(), v.trim_start()));
+                }
+            }
+            "-d" | "--data" | "--data-raw" | "--data-binary" | "--data-ascii" => {
+                data.push(value()?)
+            }
+            "--data-urlencode" => {
+                let v = value()?;
+                let encoded = match v.split_once('=') {
+                    Some(("", content)) => encode_component(content),
+                    Some((name, content)) => format!("{}={}", name, encode_component(content)),
+                    None => encode_component(&v),
+                };
+                data.push(encoded);
+            }
+            "-F" | "--form" | "--form-string" => {
+                let v = value()?;
+                let (key, content) = v.split_once('=').unwrap_or((v.as_str(), ""));
+                let is_file = flag != "--form-string" && content.starts_with('@');
+                let content = if is_file {
+                    //去掉;type=和;filename=
+                    content[1..].split(';').next().unwrap_or_default()
+                } else {
+                    content
+                };
+                req.multipart
+                    .push(MultipartItem::new(key, content, is_file));
+            }
+            "-u" | "--user" => {
+                let v = value()?;
+                let (username, password) = v.split_once(':').unwrap_or((v.as_str(), ""));
+                req.auth.username = username.to_owned();
+                req.auth.password = password.to_owned();
+                if req.auth.auth_type != AuthType::Digest {
+                    req.auth.auth_type = AuthType::Basic;
+                }
+            }
+            "--digest" => req.auth.auth_type = AuthType::Digest,
+            "-b" | "--cookie" => req
+                .headers
+                .push(SelectKeyValueItem::new("Cookie", &value()?)),
+            "-A" | "--user-agent" => req
+                .headers
+                .push(SelectKeyValueItem::new("User-Agent", &value()?)),
+            "-e" | "--referer" => req
+                .headers
+                .push(SelectKeyValueItem::new("Referer", &value()?)),
+            "-k" | "--insecure" => req.settings.verify_tls = Some(false),
+            "-L" | "--location" => follow = true,
+            "--max-redirs" => {
+                let v = value()?;
+                max_redirs = Some(v.parse().map_err(|_| format!("--max-redirs不合法:{}", v))?);
+            }
+            "-x" | "--proxy" => {
+                let v = value()?;
+                //curl里没写协议的代理按http处理
+                let url = if v.contains("://") {
+                    v
+                } else {
+                    format!("http://{}", v)
+                };
+                req.settings.proxy = Some(ProxySettings {
+                    url,
+                    ..Default::default()
+                });
+            }
+            "--noproxy" => no_proxy = value()?,
+            //-m限制整个请求,这里没有总超时,按读取超时处理
+            "-m" | "--max-time" => {
+                req.settings.read_timeout = Some(seconds_to_ms(&flag, value()?)?)
+            }
+            "--connect-timeout" => {
+                req.settings.connect_timeout = Some(seconds_to_ms(&flag, value()?)?)
+            }
+            "-G" | "--get" => get = true,
+            "-I" | "--head" => method = Some("HEAD".to_owned()),
+            "--url" => url = Some(value()?),
+            //不加Accept-Encoding,服务器就不会压缩响应
+            "--compressed" => {}
+            "-o" | "--output" | "-w" | "--write-out" => {
+                value()?;
+            }
+            f if f.starts_with('-') => {}
+            _ => {
+                if url.is_none() {
+                    url = Some(arg);
+                }
+            }
+        }
+    }
+
+    if follow {
+        req.settings.redirect =
+            Some(max_redirs.map_or_else(RedirectPolicy::default, RedirectPolicy::Follow));
+    }
+    //--noproxy '*'表示所有主机都不走代理
+    if no_proxy == "*" {
+        req.settings.proxy = Some(ProxySettings {
+            disabled: true,
+            ..Default::default()
+        });
+    } else if let Some(proxy) = req.settings.proxy.as_mut() {
+        proxy.no_proxy = no_proxy;
+    }
+    let mut url = url.ok_or_else(|| "没有找到请求地址".to_owned())?;
+    if !data.is_empty() {
+        let joined = data.join("&");
+        if get {
+            url.push(if url.contains('?') { '&' } else { '?' });
+            url.push_str(&joined);
+        } else {
+            set_data_body(&mut req, joined);
+        }
+    } else if !req.multipart.is_empty() {
+        req.body_type = BodyType::Multipart;
+    }
+    let method = method.unwrap_or_else(|| {
+        if req.body_type == BodyType::None {
+            "GET".to_owned()
+        } else {
+            "POST".to_owned()
+        }
+    });
+    req.method = method.parse()?;
+    req.url = url;
+    req.sync_params_from_url();
+    Ok(req)
+}
+
+///curl的超时是秒,可以带小数
+fn seconds_to_ms(flag: &str, value: String) -> Result<u64, String> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs >= 0.)
+        .map(|secs| (secs * 1000.).round() as u64)
+        .ok_or_else(|| format!("{}不合法:{}", flag, value))
+}
+
+fn encode_component(s: &str) -> String {
+    url::form_urlencoded::byte_serialize(s.as_bytes()).collect()
+}
+
+///-d的内容,表单就拆成键值,其他的原样作为请求体
+pub fn set_data_body(req: &mut RequestData, data: String) {
+    let content_type = req
+        .headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.to_ascii_lowercase());
+    let is_form = match &content_type {
+        Some(ct) => ct.starts_with("application/x-www-form-urlencoded"),
+        None => serde_json::from_str::<serde_json::Value>(&data).is_err(),
+    };
+    if is_form {
+        req.body_type = BodyType::FormUrlencoded;
+        req.form = url::form_urlencoded::parse(data.as_bytes())
+            .map(|(k, v)| SelectKeyValueItem::new(&k, &v))
+            .collect();
+        //Content-Type由请求体类型自动设置
+        req.headers
+            .retain(|h| !h.key.eq_ignore_ascii_case("content-type"));
+    } else if content_type.is_some_and(|ct| ct.contains("json"))
+        || serde_json::from_str::<serde_json::Value>(&data).is_ok()
+    {
+        req.body_type = BodyType::Json;
+        req.body = data;
+    } else {
+        req.body_type = BodyType::Raw;
+        req.body = data;
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+///生成渲染完模板后的curl命令,req的auth需要是已经解析过继承的
+pub fn to_curl(req: &RequestData, settings: &ResolvedClientSettings) -> Result<String, String> {
+    let auth: AuthData = req.auth.render();
+    let multipart = req.body_type == BodyType::Multipart;
+    let binary = req.body_type == BodyType::Binary;
+    let request: Request = req.clone().try_into()?;
+
+    let mut parts = vec!["curl".to_owned()];
+    if request.method() != reqwest::Method::GET {
+        parts.push(format!("-X {}", request.method()));
+    }
+    parts.push(quote(request.url().as_str()));
+    for (k, v) in request.headers() {
+        //multipart由-F生成Content-Type和分隔符
+        if multipart && *k == CONTENT_TYPE {
+            continue;
+        }
+        let v = v
+            .to_str()
+            .map(str::to_owned)
+            .unwrap_or_else(|_| String::from_utf8_lossy(v.as_bytes()).into_owned());
+        parts.push(format!("-H {}", quote(&format!("{}: {}", k, v))));
+    }
+    if auth.auth_type == AuthType::Digest {
+        parts.push("--digest".to_owned());
+        parts.push(format!(
+            "-u {}",
+            quote(&format!("{}:{}", auth.username, auth.password))
+        ));
+    }
+    if multipart {
+        for item in req.multipart.iter().filter(|item| item.selected) {
+            let value = rander_template(&item.value).unwrap_or_else(|_| item.value.clone());
+            let value = if item.is_file {
+                format!("{}=@{}", item.key, value)
+            } else {
+                format!("{}={}", item.key, value)
+            };
+            parts.push(format!("-F {}", quote(&value)));
+        }
+    } else if binary {
+        parts.push(format!(
+            "--data-binary {}",
+            quote(&format!("@{}", req.binary))
+        ));
+    } else if let Some(body) = request.body().and_then(|b| b.as_bytes()) {
+        parts.push(format!(
+            "--data-raw {}",
+            quote(&String::from_utf8_lossy(body))
+        ));
+    }
+    if !settings.verify_tls {
+        parts.push("-k".to_owned());
+    }
+    if let RedirectPolicy::Follow(max) = settings.redirect {
+        parts.push(format!("-L --max-redirs {}", max));
+    }
+    if settings.proxy.disabled {
+        parts.push("--noproxy '*'".to_owned());
+    } else if !settings.proxy.url.trim().is_empty() {
+        parts.push(format!("-x {}", quote(settings.proxy.url.trim())));
+    }
+    Ok(parts.join(" \\\n  "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_data::Method;
+
+    #[test]
+    fn test_parse_chrome_curl() {
+        let cmd = r#"curl 'https://api.example.com/users?page=1' \
+  -H 'accept: application/json' \
+  -H $'x-note: it\'s' \
+  -H 'content-type: application/json' \
+  --data-raw '{"name":"张三"}' \
+  --compressed -k"#;
+        let req = parse_curl(cmd).unwrap();
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.url, "https://api.example.com/users?page=1");
+        assert_eq!(req.params[0].key, "page");
+        assert_eq!(req.headers.len(), 3);
+        assert_eq!(req.headers[1].value, "it's");
+        assert_eq!(req.body_type, BodyType::Json);
+        assert_eq!(req.body, r#"{"name":"张三"}"#);
+        assert_eq!(req.settings.verify_tls, Some(false));
+    }
+
+    #[test]
+    fn test_parse_form_and_auth() {
+        let cmd = "curl -XPUT http://localhost/a -u admin:secret -d a=1 --data-urlencode 'b=x y'";
+        let req = parse_curl(cmd).unwrap();
+        assert_eq!(req.method, Method::PUT);
+        assert_eq!(req.auth.auth_type, AuthType::Basic);
+        assert_eq!(req.auth.password, "secret");
+        assert_eq!(req.body_type, BodyType::FormUrlencoded);
+        assert_eq!(req.form[1].value, "x y");
+
+        let req =
+            parse_curl("curl http://localhost/up -F name=a -F 'file=@/tmp/a.png;type=image/png'")
+                .unwrap();
+        assert_eq!(req.body_type, BodyType::Multipart);
+        assert!(req.multipart[1].is_file);
+        assert_eq!(req.multipart[1].value, "/tmp/a.png");
+
+        assert!(parse_curl("wget http://localhost").is_err());
+    }
+
+    #[test]
+    fn test_parse_client_settings() {
+        let cmd = "curl -L --max-redirs 3 -x 127.0.0.1:8888 --noproxy localhost -m 1.5 \
+            --connect-timeout 2 https://example.com";
+        let req = parse_curl(cmd).unwrap();
+        assert_eq!(req.settings.redirect, Some(RedirectPolicy::Follow(3)));
+        let proxy = req.settings.proxy.unwrap();
+        assert_eq!(proxy.url, "http://127.0.0.1:8888");
+        assert_eq!(proxy.no_proxy, "localhost");
+        assert_eq!(req.settings.read_timeout, Some(1500));
+        assert_eq!(req.settings.connect_timeout, Some(2000));
+        assert_eq!(req.settings.verify_tls, None);
+        assert!(parse_curl("curl -m abc https://example.com").is_err());
+        let req = parse_curl("curl --noproxy '*' https://example.com").unwrap();
+        assert!(req.settings.proxy.unwrap().disabled);
+    }
+
+    #[test]
+    fn test_to_curl_round_trip() {
+        let mut req = RequestData {
+            method: Method::POST,
+            url: "http://localhost:8080/items".to_owned(),
+            body_type: BodyType::Raw,
+            body: r#"{"name":"it's"}"#.to_owned(),
+            ..Default::default()
+        };
+        req.headers
+            .push(SelectKeyValueItem::new("X-Token", "${1+1}"));
+        let settings = ResolvedClientSettings {
+            verify_tls: true,
+            redirect: RedirectPolicy::None,
+            ..Default::default()
+        };
+        let cmd = to_curl(&req, &settings).unwrap();
+        assert!(cmd.contains("-H 'x-token: 2'"));
+        let parsed = parse_curl(&cmd).unwrap();
+        assert_eq!(parsed.method, Method::POST);
+        assert_eq!(parsed.url, req.url);
+        assert_eq!(parsed.body, req.body);
+    }
+}
@@ -0,0 +1,406 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use rhai::debugger::{BreakPoint, DebuggerCommand, DebuggerEvent};
+use rhai::{ASTNode, Dynamic, EvalAltResult, EvalContext, Position, Scope};
+
+use crate::request_data::ResponseData;
+use crate::utils::assertion::{self, TestResult};
+use crate::utils::rhai_script::{new_engine, ScriptLimit};
+
+///变量值太长时界面上截断显示
+const VALUE_PREVIEW: usize = 120;
+
+///界面发给调试线程的命令
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugCommand {
+    Continue,
+    ///单步跳过,不进入函数
+    StepOver,
+    ///单步进入函数
+    StepInto,
+    ///执行到当前函数返回
+    StepOut,
+    Stop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugVar {
+    pub name: String,
+    pub type_name: String,
+    pub value: String,
+    pub constant: bool,
+}
+
+impl DebugVar {
+    pub fn preview(&self) -> String {
+        match self.value.char_indices().nth(VALUE_PREVIEW) {
+            Some((end, _)) => format!("{}…", &self.value[..end]),
+            None => self.value.clone(),
+        }
+    }
+}
+
+///暂停时的位置、作用域和调用栈
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DebugSnapshot {
+    ///从1开始
+    pub line: usize,
+    pub column: usize,
+    ///为什么暂停:断点、单步等
+    pub reason: String,
+    pub vars: Vec<DebugVar>,
+    ///最外层在前
+    pub call_stack: Vec<String>,
+}
+
+impl DebugSnapshot {
+    fn capture(context: &EvalContext, reason: &str, pos: Position) -> Self {
+        let vars = context
+            .scope()
+            .iter()
+            .map(|(name, constant, value)| DebugVar {
+                name: name.to_owned(),
+                type_name: value.type_name().to_owned(),
+                value: value.to_string(),
+                constant,
+            })
+            .collect();
+        let call_stack = context
+            .global_runtime_state()
+            .debugger()
+            .call_stack()
+            .iter()
+            .map(|frame| frame.to_string())
+            .collect();
+        Self {
+            line: pos.line().unwrap_or_default(),
+            column: pos.position().unwrap_or_default(),
+            reason: reason.to_owned(),
+            vars,
+            call_stack,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DebugState {
+    Running,
+    Paused(DebugSnapshot),
+    ///脚本的返回值或错误,调试后置脚本时还有断言结果
+    Finished(Result<String, String>, Vec<TestResult>),
+}
+
+///执行前放进作用域的变量和要执行的脚本
+#[derive(Debug, Clone, Default)]
+pub struct DebugStep {
+    pub vars: Vec<(String, String)>,
+    pub script: String,
+}
+
+///在单独的线程里执行脚本,暂停时线程阻塞等命令,界面每帧poll
+pub struct DebugSession {
+    ///调试的请求id和是否后置脚本
+    pub target: (u64, bool),
+    pub state: DebugState,
+    commands: Sender<DebugCommand>,
+    states: Receiver<DebugState>,
+    stop: Arc<AtomicBool>,
+}
+
+impl DebugSession {
+    ///setup里的上级脚本直接执行,只调试最后的step;没有断点时停在第一条语句。
+    ///resp是后置脚本断言针对的响应,断言结果记在调试线程自己的状态里
+    pub fn start(
+        target: (u64, bool),
+        scope: Scope<'static>,
+        setup: Vec<DebugStep>,
+        step: DebugStep,
+        breakpoints: Vec<usize>,
+        resp: Option<ResponseData>,
+    ) -> Self {
+        let (commands, command_rx) = mpsc::channel();
+        let (state_tx, states) = mpsc::channel();
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::spawn(move || {
+            //调试只看脚本怎么执行,call_req和http请求都不发出去
+            let _limit = ScriptLimit::offline();
+            if let Some(resp) = &resp {
+                assertion::begin(resp);
+            }
+            let result = run(
+                scope,
+                setup,
+                step,
+                breakpoints,
+                command_rx,
+                state_tx.clone(),
+                stopped,
+            );
+            let tests = match resp {
+                Some(_) => assertion::finish(None),
+                None => Vec::new(),
+            };
+            let _ = state_tx.send(DebugState::Finished(result, tests));
+        });
+        Self {
+            target,
+            state: DebugState::Running,
+            commands,
+            states,
+            stop,
+        }
+    }
+
+    ///取调试线程发来的最新状态,有变化时返回true
+    pub fn poll(&mut self) -> bool {
+        let mut changed = false;
+        while let Ok(state) = self.states.try_recv() {
+            self.state = state;
+            changed = true;
+        }
+        changed
+    }
+
+    ///只有暂停时命令才有意义
+    pub fn command(&mut self, command: DebugCommand) {
+        if command == DebugCommand::Stop {
+            self.stop();
+        } else if matches!(self.state, DebugState::Paused(_)) && self.commands.send(command).is_ok()
+        {
+            self.state = DebugState::Running;
+        }
+    }
+
+    ///暂停时马上结束,运行中的脚本在下一条语句前结束
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.commands.send(DebugCommand::Stop);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.state, DebugState::Finished(..))
+    }
+
+    pub fn paused(&self) -> Option<&DebugSnapshot> {
+        match &self.state {
+            DebugState::Paused(snapshot) => Some(snapshot),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for DebugSession {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn push_vars(scope: &mut Scope, vars: Vec<(String, String)>) {
+    for (key, value) in vars {
+        scope.push(key, value);
+    }
+}
+
+fn to_rhai_command(command: DebugCommand) -> Result<DebuggerCommand, Box<EvalAltResult>> {
+    match command {
+        DebugCommand::Continue => Ok(DebuggerCommand::Continue),
+        DebugCommand::StepOver => Ok(DebuggerCommand::Next),
+        DebugCommand::StepInto => Ok(DebuggerCommand::StepInto),
+        DebugCommand::StepOut => Ok(DebuggerCommand::FunctionExit),
+        DebugCommand::Stop => Err("调试已停止".into()),
+    }
+}
+
+fn run(
+    mut scope: Scope<'static>,
+    setup: Vec<DebugStep>,
+    step: DebugStep,
+    breakpoints: Vec<usize>,
+    commands: Receiver<DebugCommand>,
+    states: Sender<DebugState>,
+    stop: Arc<AtomicBool>,
+) -> Result<String, String> {
+    let engine = new_engine();
+    for DebugStep { vars, script } in setup {
+        push_vars(&mut scope, vars);
+        engine
+            .run_with_scope(&mut scope, &script)
+            .map_err(|e| format!("上级脚本错误:{}", e))?;
+    }
+    push_vars(&mut scope, step.vars);
+
+    let mut engine = new_engine();
+    let pause_at_start = breakpoints.is_empty();
+    //回调需要Sync,Receiver只能放进Mutex
+    let commands = Mutex::new(commands);
+    let states = Mutex::new(states);
+    //不暂停的事件要返回当前的模式,否则单步会变成继续
+    let mode = Mutex::new(DebugCommand::Continue);
+    engine.on_progress(move |_| {
+        stop.load(Ordering::Relaxed)
+            .then(|| Dynamic::from("调试已停止"))
+    });
+    engine.register_debugger(
+        move |_, mut debugger| {
+            for line in breakpoints.iter() {
+                debugger.break_points_mut().push(BreakPoint::AtPosition {
+                    source: None,
+                    pos: Position::new(*line as u16, 0),
+                    enabled: true,
+                });
+            }
+            debugger
+        },
+        move |context, event, node, _source, pos| {
+            let current = mode.lock().map(|m| *m).unwrap_or(DebugCommand::Continue);
+            let reason = match event {
+                DebuggerEvent::Start if pause_at_start => "开始",
+                DebuggerEvent::Step => "单步",
+                //同一行的表达式也会命中断点,只在语句上停
+                DebuggerEvent::BreakPoint(_) if matches!(node, ASTNode::Stmt(_)) => "断点",
+                DebuggerEvent::FunctionExitWithValue(_) => "函数返回",
+                DebuggerEvent::FunctionExitWithError(_) => "函数出错",
+                _ => return to_rhai_command(current),
+            };
+            let snapshot = DebugSnapshot::capture(&context, reason, pos);
+            if let Ok(states) = states.lock() {
+                let _ = states.send(DebugState::Paused(snapshot));
+            }
+            let command = commands
+                .lock()
+                .map_err(|e| e.to_string())?
+                .recv()
+                .unwrap_or(DebugCommand::Stop);
+            if let Ok(mut mode) = mode.lock() {
+                *mode = command;
+            }
+            to_rhai_command(command)
+        },
+    );
+    let value = engine
+        .eval_with_scope::<Dynamic>(&mut scope, &step.script)
+        .map_err(|e| e.to_string())?;
+    Ok(if value.is_unit() {
+        String::new()
+    } else {
+        value.to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    fn wait(session: &mut DebugSession, done: impl Fn(&DebugState) -> bool) {
+        let start = Instant::now();
+        while !done(&session.state) {
+            assert!(
+                start.elapsed() < Duration::from_secs(10),
+                "{:?}",
+                session.state
+            );
+            session.poll();
+            thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[test]
+    fn test_breakpoint_and_step() {
+        let script = "let a = token + \"-1\";\nlet b = 2;\nlet c = b * 3;\nc";
+        let mut session = DebugSession::start(
+            (1, false),
+            Scope::new(),
+            vec![DebugStep {
+                vars: vec![],
+                script: "let token = \"t\";".to_owned(),
+            }],
+            DebugStep {
+                vars: vec![("host".to_owned(), "localhost".to_owned())],
+                script: script.to_owned(),
+            },
+            vec![2],
+            None,
+        );
+        wait(&mut session, |s| matches!(s, DebugState::Paused(_)));
+        let snapshot = session.paused().unwrap().clone();
+        assert_eq!(snapshot.line, 2);
+        assert_eq!(snapshot.reason, "断点");
+        let value = |name: &str| {
+            snapshot
+                .vars
+                .iter()
+                .find(|v| v.name == name)
+                .map(|v| v.value.clone())
+        };
+        assert_eq!(value("token").as_deref(), Some("t"));
+        assert_eq!(value("host").as_deref(), Some("localhost"));
+        assert_eq!(value("a").as_deref(), Some("t-1"));
+        assert_eq!(value("b"), None);
+
+        session.command(DebugCommand::StepOver);
+        wait(&mut session, |s| matches!(s, DebugState::Paused(_)));
+        assert_eq!(session.paused().unwrap().line, 3);
+
+        session.command(DebugCommand::Continue);
+        wait(&mut session, |s| matches!(s, DebugState::Finished(..)));
+        assert_eq!(
+            session.state,
+            DebugState::Finished(Ok("6".to_owned()), Vec::new())
+        );
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut session = DebugSession::start(
+            (1, true),
+            Scope::new(),
+            vec![],
+            DebugStep {
+                vars: vec![],
+                script: "let a = 1;\na".to_owned(),
+            },
+            vec![],
+            None,
+        );
+        wait(&mut session, |s| matches!(s, DebugState::Paused(_)));
+        assert_eq!(session.paused().unwrap().line, 1);
+        session.command(DebugCommand::Stop);
+        wait(&mut session, |s| matches!(s, DebugState::Finished(..)));
+        assert!(matches!(session.state, DebugState::Finished(Err(_), _)));
+    }
+
+    #[test]
+    fn test_assertions_stay_in_session() {
+        assertion::begin(&ResponseData::default());
+        let resp = ResponseData {
+            code: "200 OK".to_owned(),
+            ..Default::default()
+        };
+        let mut session = DebugSession::start(
+            (1, true),
+            Scope::new(),
+            vec![],
+            DebugStep {
+                vars: vec![],
+                script: "expect_status(200);\nexpect(1).to_equal(2);".to_owned(),
+            },
+            vec![],
+            Some(resp),
+        );
+        wait(&mut session, |s| matches!(s, DebugState::Paused(_)));
+        session.command(DebugCommand::Continue);
+        wait(&mut session, |s| matches!(s, DebugState::Finished(..)));
+        let DebugState::Finished(Ok(_), tests) = &session.state else {
+            panic!("{:?}", session.state);
+        };
+        let passed: Vec<bool> = tests.iter().map(|t| t.passed).collect();
+        assert_eq!(passed, vec![true, false]);
+        assert!(assertion::finish(None).is_empty());
+    }
+}
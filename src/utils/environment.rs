@@ -0,0 +1,357 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use minijinja::value::Value;
+use once_cell::sync::Lazy;
+use rhai::Scope;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::utils::secret::{self, SecretVar, Vault, MASK};
+use crate::utils::template;
+
+///当前生效的变量,全局变量在前,环境变量覆盖同名的全局变量
+static ACTIVE_VARS: Lazy<RwLock<Vec<(String, String)>>> = Lazy::new(|| RwLock::new(Vec::new()));
+///当前生效的加密变量,保存的是密文,渲染时才解密
+static ACTIVE_SECRETS: Lazy<RwLock<Vec<(String, String)>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+///一组命名的变量,比如dev、test、prod
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Environment {
+    pub name: String,
+    pub vars: Vec<SelectKeyValueItem>,
+    pub secrets: Vec<SecretVar>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Environments {
+    ///所有环境共用的变量
+    pub globals: Vec<SelectKeyValueItem>,
+    pub envs: Vec<Environment>,
+    ///当前环境的名字,为空时只用全局变量
+    pub active: String,
+    ///全局的加密变量
+    pub secrets: Vec<SecretVar>,
+    pub vault: Vault,
+}
+
+impl Environments {
+    pub fn active_env(&self) -> Option<&Environment> {
+        self.envs.iter().find(|env| env.name == self.active)
+    }
+
+    ///合并后的变量,只取勾选的
+    pub fn active_vars(&self) -> Vec<(String, String)> {
+        let mut vars: Vec<(String, String)> = Vec::new();
+        let active = self.active_env().map(|env| env.vars.iter()).into_iter().flatten();
+        for item in self.globals.iter().chain(active) {
+            if !item.selected || item.key.is_empty() {
+                continue;
+            }
+            match vars.iter_mut().find(|(key, _)| key == &item.key) {
+                Some(var) => var.1 = item.value.clone(),
+                None => vars.push((item.key.clone(), item.value.clone())),
+            }
+        }
+        vars
+    }
+
+    ///合并后的加密变量,(名字,密文)
+    pub fn active_secrets(&self) -> Vec<(String, String)> {
+        let mut secrets: Vec<(String, String)> = Vec::new();
+        let active = self.active_env().map(|env| env.secrets.iter()).into_iter().flatten();
+        for item in self.secrets.iter().chain(active) {
+            if !item.selected || item.key.is_empty() || item.cipher.is_empty() {
+                continue;
+            }
+            match secrets.iter_mut().find(|(key, _)| key == &item.key) {
+                Some(secret) => secret.1 = item.cipher.clone(),
+                None => secrets.push((item.key.clone(), item.cipher.clone())),
+            }
+        }
+        secrets
+    }
+
+    ///切换或修改后调用,模板和脚本才能用到新的变量
+    pub fn apply(&self) {
+        if let Ok(mut vars) = ACTIVE_VARS.write() {
+            *vars = self.active_vars();
+        }
+        if let Ok(mut secrets) = ACTIVE_SECRETS.write() {
+            *secrets = self.active_secrets();
+        }
+    }
+
+    ///导出时去掉加密变量和主密码
+    pub fn without_secrets(&self) -> Environments {
+        let mut envs = self.clone();
+        envs.secrets.clear();
+        envs.vault = Vault::default();
+        for env in envs.envs.iter_mut() {
+            env.secrets.clear();
+        }
+        envs
+    }
+
+    ///新建环境,名字重复时加序号
+    pub fn add(&mut self, name: &str) -> String {
+        let mut unique = name.to_owned();
+        let mut index = 1;
+        while self.envs.iter().any(|env| env.name == unique) {
+            index += 1;
+            unique = format!("{}{}", name, index);
+        }
+        self.envs.push(Environment {
+            name: unique.clone(),
+            ..Default::default()
+        });
+        unique
+    }
+
+    ///提取器写入的变量,有当前环境时写进环境,否则写进全局变量
+    pub fn set_var(&mut self, key: &str, value: &str) {
+        let active = self.active.clone();
+        let vars = match self.envs.iter_mut().find(|env| env.name == active) {
+            Some(env) => &mut env.vars,
+            None => &mut self.globals,
+        };
+        match vars.iter_mut().find(|item| item.key == key) {
+            Some(item) => {
+                item.value = value.to_owned();
+                item.selected = true;
+            }
+            None => vars.push(SelectKeyValueItem::new(key, value)),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.envs.retain(|env| env.name != name);
+        if self.active == name {
+            self.active.clear();
+        }
+    }
+}
+
+pub fn active_vars() -> Vec<(String, String)> {
+    ACTIVE_VARS.read().map(|vars| vars.clone()).unwrap_or_default()
+}
+
+///解密当前的加密变量,锁定时没有
+fn open_secrets() -> Vec<(String, String)> {
+    let secrets = ACTIVE_SECRETS.read().map(|s| s.clone()).unwrap_or_default();
+    secrets
+        .into_iter()
+        .filter_map(|(key, cipher)| Some((key, secret::open(&cipher)?)))
+        .collect()
+}
+
+///预览里把加密变量的值替换成`******`
+pub fn mask_secrets(text: &str) -> String {
+    let mut text = text.to_owned();
+    for (_, value) in open_secrets() {
+        if !value.is_empty() {
+            text = text.replace(&value, MASK);
+        }
+    }
+    text
+}
+
+///环境变量放进rhai作用域,脚本里的同名变量会覆盖它
+pub fn push_env_vars(scope: &mut Scope) {
+    for (key, value) in env_values() {
+        scope.push(key, value);
+    }
+}
+
+///push_env_vars放进作用域的变量,加密变量是明文
+pub fn env_values() -> Vec<(String, String)> {
+    active_vars().into_iter().chain(open_secrets()).collect()
+}
+
+///环境变量合并到模板上下文,脚本变量优先
+pub fn with_env_vars(ctx: Value) -> Value {
+    let vars = env_values();
+    if vars.is_empty() {
+        return ctx;
+    }
+    let mut merged: HashMap<String, Value> = vars
+        .into_iter()
+        .map(|(key, value)| (key, Value::from(value)))
+        .collect();
+    if !ctx.is_undefined() {
+        if let Ok(keys) = ctx.try_iter() {
+            for key in keys {
+                if let (Some(name), Ok(value)) = (key.as_str(), ctx.get_item(&key)) {
+                    merged.insert(name.to_owned(), value);
+                }
+            }
+        }
+    }
+    Value::from(merged)
+}
+
+///变量的来源,按模板里的优先级从高到低
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarSource {
+    ///run_script得到的TMP_SCOPE_CTX
+    Script,
+    Secret,
+    Environment,
+    ///add_global_var添加的
+    Global,
+}
+
+impl VarSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            VarSource::Script => "脚本",
+            VarSource::Secret => "加密",
+            VarSource::Environment => "环境",
+            VarSource::Global => "全局",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VarEntry {
+    pub name: String,
+    pub source: VarSource,
+    pub value: serde_json::Value,
+    ///被优先级更高的同名变量覆盖了
+    pub shadowed: bool,
+}
+
+///按优先级排好的各来源变量,标出被覆盖的
+pub fn merge_sources(sources: Vec<(VarSource, Vec<(String, serde_json::Value)>)>) -> Vec<VarEntry> {
+    let mut entries: Vec<VarEntry> = Vec::new();
+    for (source, vars) in sources {
+        for (name, value) in vars {
+            let shadowed = entries.iter().any(|e| e.name == name);
+            entries.push(VarEntry {
+                name,
+                source,
+                value,
+                shadowed,
+            });
+        }
+    }
+    entries
+}
+
+///模板里能用到的所有变量,加密变量只显示掩码
+pub fn inspect_vars() -> Vec<VarEntry> {
+    let to_json = |vars: Vec<(String, Value)>| -> Vec<(String, serde_json::Value)> {
+        vars.into_iter()
+            .map(|(name, value)| (name, serde_json::to_value(&value).unwrap_or_default()))
+            .collect()
+    };
+    //脚本里用加密变量拼出来的值也不能显示明文
+    let script_vars = to_json(template::scope_vars())
+        .into_iter()
+        .map(|(name, value)| {
+            let text = match &value {
+                serde_json::Value::String(text) => text.clone(),
+                other => other.to_string(),
+            };
+            let masked = mask_secrets(&text);
+            if masked == text {
+                (name, value)
+            } else {
+                (name, masked.into())
+            }
+        })
+        .collect();
+    let secrets = ACTIVE_SECRETS.read().map(|s| s.clone()).unwrap_or_default();
+    merge_sources(vec![
+        (VarSource::Script, script_vars),
+        (
+            VarSource::Secret,
+            secrets
+                .into_iter()
+                .map(|(name, _)| (name, MASK.into()))
+                .collect(),
+        ),
+        (
+            VarSource::Environment,
+            active_vars()
+                .into_iter()
+                .map(|(name, value)| (name, value.into()))
+                .collect(),
+        ),
+        (VarSource::Global, to_json(template::global_vars())),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str, value: &str, selected: bool) -> SelectKeyValueItem {
+        SelectKeyValueItem {
+            selected,
+            key: key.to_owned(),
+            value: value.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_merge_sources() {
+        let entries = merge_sources(vec![
+            (VarSource::Script, vec![("host".to_owned(), "a".into())]),
+            (
+                VarSource::Environment,
+                vec![("host".to_owned(), "b".into()), ("port".to_owned(), "80".into())],
+            ),
+        ]);
+        let shadowed: Vec<(&str, VarSource, bool)> = entries
+            .iter()
+            .map(|e| (e.name.as_str(), e.source, e.shadowed))
+            .collect();
+        assert_eq!(
+            shadowed,
+            vec![
+                ("host", VarSource::Script, false),
+                ("host", VarSource::Environment, true),
+                ("port", VarSource::Environment, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_active_vars() {
+        let mut envs = Environments {
+            globals: vec![item("host", "localhost", true), item("debug", "1", false)],
+            ..Default::default()
+        };
+        let dev = envs.add("dev");
+        assert_eq!(envs.add("dev"), "dev2");
+        envs.envs[0].vars = vec![item("host", "dev.example.com", true), item("token", "t", true)];
+        assert_eq!(envs.active_vars(), vec![("host".to_owned(), "localhost".to_owned())]);
+        envs.active = dev;
+        assert_eq!(
+            envs.active_vars(),
+            vec![
+                ("host".to_owned(), "dev.example.com".to_owned()),
+                ("token".to_owned(), "t".to_owned()),
+            ]
+        );
+        envs.envs[0].secrets = vec![SecretVar {
+            key: "password".to_owned(),
+            cipher: "iv:data".to_owned(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            envs.active_secrets(),
+            vec![("password".to_owned(), "iv:data".to_owned())]
+        );
+        assert!(envs.without_secrets().envs[0].secrets.is_empty());
+        envs.set_var("token", "t2");
+        envs.set_var("user", "u");
+        assert_eq!(envs.envs[0].vars.len(), 3);
+        assert!(envs.globals.iter().all(|item| item.key != "user"));
+        envs.remove("dev");
+        assert!(envs.active.is_empty());
+        assert_eq!(envs.envs.len(), 1);
+    }
+}
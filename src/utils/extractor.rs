@@ -0,0 +1,297 @@
+use regex::Regex;
+use serde_json::Value;
+
+use crate::request_data::ResponseData;
+
+///提取的值从响应的哪里来
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ExtractSource {
+    ///JSON响应体,表达式是`$.data.items[0].id`这样的JSONPath
+    #[default]
+    JsonPath,
+    ///表达式是响应头的名字,不区分大小写
+    Header,
+    ///表达式是Set-Cookie里的名字
+    Cookie,
+    ///不需要表达式
+    Status,
+    ///匹配响应体,有分组时取第一个分组
+    Regex,
+}
+
+impl ExtractSource {
+    pub const ALL: [ExtractSource; 5] = [
+        ExtractSource::JsonPath,
+        ExtractSource::Header,
+        ExtractSource::Cookie,
+        ExtractSource::Status,
+        ExtractSource::Regex,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExtractSource::JsonPath => "JSONPath",
+            ExtractSource::Header => "响应头",
+            ExtractSource::Cookie => "Cookie",
+            ExtractSource::Status => "状态码",
+            ExtractSource::Regex => "正则",
+        }
+    }
+}
+
+///提取的变量保存在哪里
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ExtractScope {
+    ///只有这个请求的模板和脚本能用
+    #[default]
+    Request,
+    ///所在集合和子集合里的请求都能用
+    Collection,
+    ///写进当前环境,没有选环境时写进全局变量
+    Environment,
+}
+
+impl ExtractScope {
+    pub const ALL: [ExtractScope; 3] = [
+        ExtractScope::Request,
+        ExtractScope::Collection,
+        ExtractScope::Environment,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExtractScope::Request => "请求",
+            ExtractScope::Collection => "集合",
+            ExtractScope::Environment => "环境",
+        }
+    }
+}
+
+///提取器表格的一行
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Extractor {
+    pub selected: bool,
+    ///变量名
+    pub name: String,
+    pub source: ExtractSource,
+    pub expr: String,
+    pub scope: ExtractScope,
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        Self {
+            selected: true,
+            name: String::new(),
+            source: ExtractSource::default(),
+            expr: String::new(),
+            scope: ExtractScope::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    ///负数从后往前数
+    Index(i64),
+}
+
+///支持`$.a.b`、`$.a[0]`、`$['a b']`,开头的`$`可以省略
+fn path_segments(path: &str) -> Result<Vec<Segment>, String> {
+    let path = path.trim();
+    let mut rest = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    while !rest.is_empty() {
+        if let Some(inner) = rest.strip_prefix('[') {
+            let end = inner.find(']').ok_or(format!("缺少]:{}", path))?;
+            let index = inner[..end].trim();
+            let quoted = index
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+                .or_else(|| index.strip_prefix('"').and_then(|s| s.strip_suffix('"')));
+            match quoted {
+                Some(key) => segments.push(Segment::Key(key.to_owned())),
+                None => segments.push(Segment::Index(
+                    index
+                        .parse()
+                        .map_err(|_| format!("下标不是数字:{}", index))?,
+                )),
+            }
+            rest = &inner[end + 1..];
+        } else {
+            let key = rest.strip_prefix('.').unwrap_or(rest);
+            let end = key.find(['.', '[']).unwrap_or(key.len());
+            if end == 0 {
+                return Err(format!("JSONPath格式错误:{}", path));
+            }
+            segments.push(Segment::Key(key[..end].to_owned()));
+            rest = &key[end..];
+        }
+    }
+    Ok(segments)
+}
+
+fn json_path<'a>(value: &'a Value, path: &str) -> Result<Option<&'a Value>, String> {
+    let mut current = value;
+    for segment in path_segments(path)? {
+        let next = match (&segment, current) {
+            (Segment::Key(key), Value::Object(map)) => map.get(key),
+            (Segment::Index(index), Value::Array(items)) => {
+                let index = if *index < 0 {
+                    items.len() as i64 + index
+                } else {
+                    *index
+                };
+                usize::try_from(index).ok().and_then(|i| items.get(i))
+            }
+            _ => None,
+        };
+        match next {
+            Some(next) => current = next,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(current))
+}
+
+///字符串取原文,其他的转成JSON文本
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+pub fn extract(resp: &ResponseData, extractor: &Extractor) -> Result<String, String> {
+    let expr = extractor.expr.trim();
+    match extractor.source {
+        ExtractSource::JsonPath => {
+            let body: Value =
+                serde_json::from_str(&resp.body).map_err(|e| format!("响应不是JSON:{}", e))?;
+            json_path(&body, expr)?
+                .map(value_text)
+                .ok_or(format!("JSONPath没有匹配:{}", expr))
+        }
+        ExtractSource::Header => resp
+            .headers
+            .iter()
+            .find(|item| item.key.eq_ignore_ascii_case(expr))
+            .map(|item| item.value.clone())
+            .ok_or(format!("没有响应头:{}", expr)),
+        ExtractSource::Cookie => resp
+            .headers
+            .iter()
+            .filter(|item| item.key.eq_ignore_ascii_case("set-cookie"))
+            .filter_map(|item| {
+                let pair = item.value.split(';').next()?;
+                let (name, value) = pair.split_once('=')?;
+                (name.trim() == expr).then(|| value.trim().to_owned())
+            })
+            .next()
+            .ok_or(format!("没有Cookie:{}", expr)),
+        ExtractSource::Status => resp
+            .code
+            .split_whitespace()
+            .next()
+            .map(str::to_owned)
+            .ok_or("没有状态码".to_owned()),
+        ExtractSource::Regex => {
+            let re = Regex::new(expr).map_err(|e| format!("正则错误:{}", e))?;
+            let caps = re
+                .captures(&resp.body)
+                .ok_or(format!("正则没有匹配:{}", expr))?;
+            let found = caps.get(1).or_else(|| caps.get(0));
+            Ok(found.map(|m| m.as_str().to_owned()).unwrap_or_default())
+        }
+    }
+}
+
+///执行勾选的提取器,返回提取到的(作用域,变量名,值)和失败的原因
+pub fn run_extractors(
+    resp: &ResponseData,
+    extractors: &[Extractor],
+) -> (Vec<(ExtractScope, String, String)>, Vec<String>) {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for extractor in extractors
+        .iter()
+        .filter(|e| e.selected && !e.name.is_empty())
+    {
+        match extract(resp, extractor) {
+            Ok(value) => values.push((extractor.scope, extractor.name.clone(), value)),
+            Err(e) => errors.push(format!("{}:{}", extractor.name, e)),
+        }
+    }
+    (values, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::header_ui::SelectKeyValueItem;
+
+    fn extractor(source: ExtractSource, expr: &str) -> Extractor {
+        Extractor {
+            name: "v".to_owned(),
+            source,
+            expr: expr.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_json_path() {
+        let value: Value =
+            serde_json::from_str(r#"{"data":{"items":[{"id":1},{"id":2}],"a b":"x"}}"#).unwrap();
+        let find = |path: &str| json_path(&value, path).unwrap().map(value_text);
+        assert_eq!(find("$.data.items[0].id").as_deref(), Some("1"));
+        assert_eq!(find("data.items[-1].id").as_deref(), Some("2"));
+        assert_eq!(find("$.data['a b']").as_deref(), Some("x"));
+        assert_eq!(find("$.data.items[0]").as_deref(), Some(r#"{"id":1}"#));
+        assert_eq!(find("$.data.missing"), None);
+        assert!(json_path(&value, "$.data[x]").is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let resp = ResponseData {
+            code: "201 Created".to_owned(),
+            headers: vec![
+                SelectKeyValueItem::new("x-request-id", "abc"),
+                SelectKeyValueItem::new("set-cookie", "lang=zh; Path=/"),
+                SelectKeyValueItem::new("set-cookie", "sid=s1; HttpOnly"),
+            ],
+            body: r#"{"token":"t-123"}"#.to_owned(),
+            ..Default::default()
+        };
+        let run = |source, expr| extract(&resp, &extractor(source, expr));
+        assert_eq!(
+            run(ExtractSource::JsonPath, "$.token"),
+            Ok("t-123".to_owned())
+        );
+        assert_eq!(
+            run(ExtractSource::Header, "X-Request-Id"),
+            Ok("abc".to_owned())
+        );
+        assert_eq!(run(ExtractSource::Cookie, "sid"), Ok("s1".to_owned()));
+        assert_eq!(run(ExtractSource::Status, ""), Ok("201".to_owned()));
+        assert_eq!(
+            run(ExtractSource::Regex, r#""token":"([^"]+)""#),
+            Ok("t-123".to_owned())
+        );
+        assert!(run(ExtractSource::Header, "etag").is_err());
+
+        let extractors = vec![
+            extractor(ExtractSource::JsonPath, "$.token"),
+            extractor(ExtractSource::JsonPath, "$.id"),
+        ];
+        let (values, errors) = run_extractors(&resp, &extractors);
+        assert_eq!(
+            values,
+            vec![(ExtractScope::Request, "v".to_owned(), "t-123".to_owned())]
+        );
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::BTreeSet;
+
+use reqwest::{Client, Request};
+use serde_json::{json, Value};
+
+use crate::request_data::{BodyType, GraphQLBody, Method, RequestData};
+use crate::utils::auth;
+use crate::utils::template::rander_template;
+
+///只取自动补全需要的名称
+pub const INTROSPECTION_QUERY: &str = r#"query IntrospectionQuery {
+  __schema {
+    types {
+      kind
+      name
+      fields(includeDeprecated: true) { name args { name } }
+      inputFields { name }
+      enumValues(includeDeprecated: true) { name }
+    }
+  }
+}"#;
+
+///内省得到的Schema,按集合缓存
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct GraphQLSchema {
+    pub types: Vec<String>,
+    ///字段、输入字段和枚举值
+    pub fields: Vec<String>,
+    pub arguments: Vec<String>,
+    ///获取时间,毫秒时间戳
+    pub fetched_at: i64,
+}
+
+impl GraphQLSchema {
+    pub fn from_introspection(resp: &Value) -> Result<Self, String> {
+        let Some(types) = resp["data"]["__schema"]["types"].as_array() else {
+            let errors = resp.get("errors").map(|e| e.to_string());
+            return Err(errors.unwrap_or_else(|| "响应里没有__schema".to_owned()));
+        };
+        let mut type_names = BTreeSet::new();
+        let mut fields = BTreeSet::new();
+        let mut arguments = BTreeSet::new();
+        let names = |value: &Value| -> Vec<String> {
+            value
+                .as_array()
+                .map(|items| {
+                    items
+                        .iter()
+                        .filter_map(|item| item["name"].as_str())
+                        .map(str::to_owned)
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        for t in types {
+            let Some(name) = t["name"].as_str() else {
+                continue;
+            };
+            //内置的__Type等不需要补全
+            if name.starts_with("__") {
+                continue;
+            }
+            type_names.insert(name.to_owned());
+            fields.extend(names(&t["fields"]));
+            fields.extend(names(&t["inputFields"]));
+            fields.extend(names(&t["enumValues"]));
+            if let Some(type_fields) = t["fields"].as_array() {
+                for field in type_fields {
+                    arguments.extend(names(&field["args"]));
+                }
+            }
+        }
+        Ok(Self {
+            types: type_names.into_iter().collect(),
+            fields: fields.into_iter().collect(),
+            arguments: arguments.into_iter().collect(),
+            fetched_at: chrono::Local::now().timestamp_millis(),
+        })
+    }
+}
+
+impl GraphQLBody {
+    ///渲染模板,组装成{"query","variables"}
+    pub fn render(&self) -> Result<Value, String> {
+        let query = rander_template(&self.query).map_err(|e| e.to_string())?;
+        if self.variables.trim().is_empty() {
+            return Ok(json!({ "query": query }));
+        }
+        let variables = rander_template(&self.variables).map_err(|e| e.to_string())?;
+        let variables = json5::from_str::<Value>(&variables)
+            .map_err(|e| format!("variables格式错误:{}", e))?;
+        Ok(json!({ "query": query, "variables": variables }))
+    }
+}
+
+///用请求的地址、请求头和认证发内省查询,req的auth需要是已经解析过继承的
+pub async fn introspect(client: &Client, mut req: RequestData) -> Result<GraphQLSchema, String> {
+    req.method = Method::POST;
+    req.body_type = BodyType::GraphQL;
+    req.graphql = GraphQLBody {
+        query: INTROSPECTION_QUERY.to_owned(),
+        variables: String::new(),
+    };
+    let auth = req.auth.clone();
+    let request: Request = req.try_into()?;
+    let resp = auth::execute(client, request, &auth).await?;
+    let body = resp.json::<Value>().await.map_err(|e| e.to_string())?;
+    GraphQLSchema::from_introspection(&body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_introspection() {
+        let resp = json!({
+            "data": {"__schema": {"types": [
+                {"kind": "OBJECT", "name": "Query", "fields": [
+                    {"name": "user", "args": [{"name": "id"}]},
+                    {"name": "users", "args": []}
+                ]},
+                {"kind": "INPUT_OBJECT", "name": "UserFilter", "inputFields": [{"name": "age"}]},
+                {"kind": "ENUM", "name": "Role", "enumValues": [{"name": "ADMIN"}]},
+                {"kind": "OBJECT", "name": "__Type", "fields": [{"name": "kind", "args": []}]}
+            ]}}
+        });
+        let schema = GraphQLSchema::from_introspection(&resp).unwrap();
+        assert_eq!(schema.types, vec!["Query", "Role", "UserFilter"]);
+        assert_eq!(schema.fields, vec!["ADMIN", "age", "user", "users"]);
+        assert_eq!(schema.arguments, vec!["id"]);
+
+        let err = GraphQLSchema::from_introspection(&json!({"errors": [{"message": "denied"}]}));
+        assert!(err.unwrap_err().contains("denied"));
+    }
+
+    #[test]
+    fn test_render_body() {
+        let body = GraphQLBody {
+            query: "query($id: ID!) { user(id: $id) { name } }".to_owned(),
+            variables: "{id: '1',}".to_owned(),
+        };
+        let value = body.render().unwrap();
+        assert_eq!(value["variables"]["id"], "1");
+        assert!(value["query"].as_str().unwrap().starts_with("query"));
+        let empty = GraphQLBody::default().render().unwrap();
+        assert!(empty.get("variables").is_none());
+    }
+}
@@ -0,0 +1,580 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Request;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{
+    request_size, BodyType, MultipartItem, RequestData, ResponseData, Timing,
+};
+use crate::utils::auth::AuthType;
+use crate::utils::secret::MASK;
+
+///HAR 1.2,只包含导入导出用到的字段
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HarLog {
+    pub version: String,
+    pub creator: HarCreator,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarEntry {
+    pub started_date_time: String,
+    ///总耗时,毫秒
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: serde_json::Value,
+    pub timings: HarTimings,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HarPair {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarPair>,
+    pub headers: Vec<HarPair>,
+    pub query_string: Vec<HarPair>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub params: Vec<HarParam>,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarParam {
+    pub name: String,
+    pub value: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: u16,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarPair>,
+    pub headers: Vec<HarPair>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct HarContent {
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+///各阶段耗时,毫秒,-1表示不适用
+#[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct HarTimings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+impl Default for HarTimings {
+    fn default() -> Self {
+        Self {
+            blocked: -1.,
+            dns: -1.,
+            connect: -1.,
+            send: 0.,
+            wait: 0.,
+            receive: 0.,
+            ssl: -1.,
+        }
+    }
+}
+
+fn non_negative(v: f64) -> i64 {
+    if v > 0. {
+        v.round() as i64
+    } else {
+        0
+    }
+}
+
+///解析HAR文件,每个条目对应一个请求和录制的响应
+pub fn parse_har(text: &str) -> Result<Vec<(RequestData, ResponseData)>, String> {
+    let har: Har = serde_json::from_str(text).map_err(|e| format!("HAR格式错误:{}", e))?;
+    har.log
+        .entries
+        .into_iter()
+        .map(|entry| Ok((import_request(&entry.request)?, import_response(&entry))))
+        .collect()
+}
+
+fn import_request(har_req: &HarRequest) -> Result<RequestData, String> {
+    let mut req = RequestData {
+        url: har_req.url.clone(),
+        method: har_req.method.to_uppercase().parse()?,
+        body_type: BodyType::None,
+        ..Default::default()
+    };
+    let mime = har_req
+        .post_data
+        .as_ref()
+        .map(|p| p.mime_type.to_ascii_lowercase())
+        .unwrap_or_default();
+    let auto_content_type =
+        mime.starts_with("application/x-www-form-urlencoded") || mime.starts_with("multipart/");
+    req.headers = har_req
+        .headers
+        .iter()
+        //HTTP/2的伪头和自动计算的头不导入
+        .filter(|h| !h.name.starts_with(':'))
+        .filter(|h| !h.name.eq_ignore_ascii_case("content-length"))
+        .filter(|h| !(auto_content_type && h.name.eq_ignore_ascii_case("content-type")))
+        .map(|h| SelectKeyValueItem::new(&h.name, &h.value))
+        .collect();
+    if let Some(post_data) = &har_req.post_data {
+        if mime.starts_with("application/x-www-form-urlencoded") {
+            req.body_type = BodyType::FormUrlencoded;
+            req.form = if post_data.params.is_empty() {
+                url::form_urlencoded::parse(post_data.text.as_bytes())
+                    .map(|(k, v)| SelectKeyValueItem::new(&k, &v))
+                    .collect()
+            } else {
+                post_data
+                    .params
+                    .iter()
+                    .map(|p| SelectKeyValueItem::new(&p.name, &p.value))
+                    .collect()
+            };
+        } else if mime.starts_with("multipart/") {
+            req.body_type = BodyType::Multipart;
+            //录制里没有文件内容,文件项只保留文件名
+            req.multipart = post_data
+                .params
+                .iter()
+                .map(|p| match &p.file_name {
+                    Some(file_name) => MultipartItem::new(&p.name, file_name, true),
+                    None => MultipartItem::new(&p.name, &p.value, false),
+                })
+                .collect();
+        } else if !post_data.text.is_empty() {
+            req.body = post_data.text.clone();
+            req.body_type = if mime.contains("json") {
+                BodyType::Json
+            } else {
+                BodyType::Raw
+            };
+        }
+    }
+    req.sync_params_from_url();
+    Ok(req)
+}
+
+fn import_response(entry: &HarEntry) -> ResponseData {
+    let har_resp = &entry.response;
+    let text = har_resp.content.text.clone().unwrap_or_default();
+    let body = if har_resp.content.encoding.as_deref() == Some("base64") {
+        STANDARD
+            .decode(text.trim())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+            .unwrap_or(text)
+    } else {
+        text
+    };
+    let size = if har_resp.content.size >= 0 {
+        har_resp.content.size as u64
+    } else {
+        body.len() as u64
+    };
+    let req = &entry.request;
+    ResponseData {
+        headers: har_resp
+            .headers
+            .iter()
+            .map(|h| SelectKeyValueItem::new(&h.name, &h.value))
+            .collect(),
+        body,
+        size,
+        code: format!("{} {}", har_resp.status, har_resp.status_text)
+            .trim()
+            .to_owned(),
+        time: non_negative(entry.time),
+        header_size: har_resp.headers_size.max(0) as u64,
+        send_size: (req.headers_size.max(0) + req.body_size.max(0)) as u64,
+        timing: Timing {
+            dns: non_negative(entry.timings.dns),
+            //HAR的connect包含ssl
+            connect: (non_negative(entry.timings.connect) - non_negative(entry.timings.ssl)).max(0),
+            tls: non_negative(entry.timings.ssl),
+            ttfb: non_negative(entry.timings.send) + non_negative(entry.timings.wait),
+            download: non_negative(entry.timings.receive),
+        },
+        events: Vec::new(),
+        extract_errors: Vec::new(),
+        tests: Vec::new(),
+    }
+}
+
+///不导出密钥时这些头只保留名字
+pub const SENSITIVE_HEADERS: [&str; 4] = [
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+];
+
+///导出请求和最后一次的响应,请求按发送时的样子渲染模板,redact时认证信息和Cookie换成掩码
+pub fn export_har(entries: &[(&RequestData, &ResponseData)], redact: bool) -> Har {
+    Har {
+        log: HarLog {
+            version: "1.2".to_owned(),
+            creator: HarCreator {
+                name: env!("CARGO_PKG_NAME").to_owned(),
+                version: env!("CARGO_PKG_VERSION").to_owned(),
+            },
+            entries: entries
+                .iter()
+                .map(|(req, resp)| {
+                    let mut entry = HarEntry {
+                        started_date_time: chrono::Local::now().to_rfc3339(),
+                        time: resp.time as f64,
+                        request: export_request(req),
+                        response: export_response(resp),
+                        cache: serde_json::json!({}),
+                        timings: HarTimings {
+                            dns: resp.timing.dns as f64,
+                            connect: (resp.timing.connect + resp.timing.tls) as f64,
+                            ssl: resp.timing.tls as f64,
+                            wait: resp.timing.ttfb as f64,
+                            receive: resp.timing.download as f64,
+                            ..Default::default()
+                        },
+                    };
+                    if redact {
+                        redact_entry(&mut entry, req);
+                    }
+                    entry
+                })
+                .collect(),
+        },
+    }
+}
+
+///认证头、Cookie和API Key的值换成掩码,请求的名字保留
+fn redact_entry(entry: &mut HarEntry, req: &RequestData) {
+    let api_key = (req.auth.auth_type == AuthType::ApiKey && !req.auth.api_key.is_empty())
+        .then_some(req.auth.api_key.as_str());
+    let in_query = req.auth.api_key_in_query;
+    let sensitive = |name: &str| {
+        SENSITIVE_HEADERS
+            .iter()
+            .any(|h| h.eq_ignore_ascii_case(name))
+            || (!in_query && api_key.map_or(false, |key| key.eq_ignore_ascii_case(name)))
+    };
+    let request = &mut entry.request;
+    let response = &mut entry.response;
+    for pair in request
+        .headers
+        .iter_mut()
+        .chain(response.headers.iter_mut())
+        .filter(|pair| sensitive(&pair.name))
+    {
+        pair.value = MASK.to_owned();
+    }
+    for pair in request
+        .cookies
+        .iter_mut()
+        .chain(response.cookies.iter_mut())
+    {
+        pair.value = MASK.to_owned();
+    }
+    let Some(key) = api_key.filter(|_| in_query) else {
+        return;
+    };
+    for pair in request.query_string.iter_mut().filter(|p| p.name == key) {
+        pair.value = MASK.to_owned();
+    }
+    if let Ok(mut url) = reqwest::Url::parse(&request.url) {
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .map(|(k, v)| {
+                let v = if k == key {
+                    MASK.to_owned()
+                } else {
+                    v.into_owned()
+                };
+                (k.into_owned(), v)
+            })
+            .collect();
+        url.query_pairs_mut().clear().extend_pairs(pairs);
+        request.url = url.to_string();
+    }
+}
+
+fn export_request(req: &RequestData) -> HarRequest {
+    let mut har_req = HarRequest {
+        method: req.method.to_string(),
+        url: req.url.clone(),
+        http_version: "HTTP/1.1".to_owned(),
+        headers_size: -1,
+        body_size: -1,
+        ..Default::default()
+    };
+    //渲染失败(比如文件读不到)时导出模板原文
+    let rendered: Result<Request, String> = req.clone().try_into();
+    match rendered {
+        Ok(request) => {
+            har_req.url = request.url().to_string();
+            har_req.headers = request
+                .headers()
+                .iter()
+                .map(|(k, v)| HarPair {
+                    name: k.to_string(),
+                    value: String::from_utf8_lossy(v.as_bytes()).into_owned(),
+                })
+                .collect();
+            let body = request
+                .body()
+                .and_then(|b| b.as_bytes())
+                .unwrap_or_default();
+            har_req.body_size = body.len() as i64;
+            har_req.headers_size = request_size(&request) as i64 - har_req.body_size;
+            if !body.is_empty() {
+                let mime_type = request
+                    .headers()
+                    .get(CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .unwrap_or_default()
+                    .to_owned();
+                har_req.post_data = Some(HarPostData {
+                    mime_type,
+                    text: String::from_utf8_lossy(body).into_owned(),
+                    params: export_params(req),
+                });
+            }
+        }
+        Err(_) => {
+            har_req.headers = req
+                .headers
+                .iter()
+                .filter(|h| h.selected)
+                .map(|h| HarPair {
+                    name: h.key.clone(),
+                    value: h.value.clone(),
+                })
+                .collect();
+        }
+    }
+    har_req.query_string = reqwest::Url::parse(&har_req.url)
+        .map(|url| {
+            url.query_pairs()
+                .map(|(k, v)| HarPair {
+                    name: k.into_owned(),
+                    value: v.into_owned(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    har_req
+}
+
+fn export_params(req: &RequestData) -> Vec<HarParam> {
+    match req.body_type {
+        BodyType::FormUrlencoded => req
+            .form
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| HarParam {
+                name: item.key.clone(),
+                value: item.value.clone(),
+                ..Default::default()
+            })
+            .collect(),
+        BodyType::Multipart => req
+            .multipart
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| HarParam {
+                name: item.key.clone(),
+                value: if item.is_file {
+                    String::new()
+                } else {
+                    item.value.clone()
+                },
+                file_name: item.is_file.then(|| item.value.clone()),
+                ..Default::default()
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn export_response(resp: &ResponseData) -> HarResponse {
+    let (status, status_text) = resp
+        .code
+        .split_once(' ')
+        .unwrap_or((resp.code.as_str(), ""));
+    let mime_type = resp
+        .headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.clone())
+        .unwrap_or_default();
+    HarResponse {
+        status: status.parse().unwrap_or_default(),
+        status_text: status_text.to_owned(),
+        http_version: "HTTP/1.1".to_owned(),
+        headers: resp
+            .headers
+            .iter()
+            .map(|h| HarPair {
+                name: h.key.clone(),
+                value: h.value.clone(),
+            })
+            .collect(),
+        content: HarContent {
+            size: resp.size as i64,
+            mime_type,
+            text: Some(resp.body.clone()),
+            encoding: None,
+        },
+        headers_size: resp.header_size as i64,
+        body_size: resp.size as i64,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_data::Method;
+
+    const HAR: &str = r#"{"log":{"version":"1.2","creator":{"name":"WebInspector","version":"537.36"},
+    "entries":[{"startedDateTime":"2024-05-01T10:00:00.000Z","time":120.5,
+    "request":{"method":"POST","url":"https://api.example.com/login?from=web","httpVersion":"http/2.0",
+        "headers":[{"name":":authority","value":"api.example.com"},{"name":"content-type","value":"application/x-www-form-urlencoded"},{"name":"accept","value":"*/*"}],
+        "queryString":[{"name":"from","value":"web"}],"cookies":[],"headersSize":-1,"bodySize":21,
+        "postData":{"mimeType":"application/x-www-form-urlencoded","text":"user=admin&pass=a%20b"}},
+    "response":{"status":200,"statusText":"OK","httpVersion":"http/2.0",
+        "headers":[{"name":"content-type","value":"application/json"}],"cookies":[],
+        "content":{"size":11,"mimeType":"application/json","text":"eyJvayI6dHJ1ZX0=","encoding":"base64"},
+        "redirectURL":"","headersSize":-1,"bodySize":-1},
+    "cache":{},"timings":{"blocked":1.2,"dns":-1,"ssl":-1,"connect":-1,"send":0.3,"wait":100.2,"receive":18.8}}]}}"#;
+
+    #[test]
+    fn test_parse_har() {
+        let entries = parse_har(HAR).unwrap();
+        assert_eq!(entries.len(), 1);
+        let (req, resp) = &entries[0];
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.params[0].key, "from");
+        assert_eq!(req.headers.len(), 1);
+        assert_eq!(req.headers[0].key, "accept");
+        assert_eq!(req.body_type, BodyType::FormUrlencoded);
+        assert_eq!(req.form[1].value, "a b");
+        assert_eq!(resp.code, "200 OK");
+        assert_eq!(resp.body, r#"{"ok":true}"#);
+        assert_eq!(resp.time, 121);
+        assert_eq!(resp.timing.ttfb, 100);
+        assert_eq!(resp.timing.dns, 0);
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let mut entries = parse_har(HAR).unwrap();
+        entries[0].1.timing.connect = 10;
+        entries[0].1.timing.tls = 20;
+        let pairs: Vec<(&RequestData, &ResponseData)> =
+            entries.iter().map(|e| (&e.0, &e.1)).collect();
+        let har = export_har(&pairs, false);
+        let text = serde_json::to_string(&har).unwrap();
+        assert!(text.contains("\"redirectURL\""));
+        let entry = &har.log.entries[0];
+        assert_eq!(entry.request.url, "https://api.example.com/login?from=web");
+        assert_eq!(entry.request.query_string[0].value, "web");
+        let post_data = entry.request.post_data.as_ref().unwrap();
+        assert_eq!(post_data.mime_type, "application/x-www-form-urlencoded");
+        assert_eq!(post_data.text, "user=admin&pass=a+b");
+        assert_eq!(entry.response.status, 200);
+        assert_eq!(entry.timings.wait, 100.);
+        assert_eq!(entry.timings.connect, 30.);
+        assert_eq!(entry.timings.ssl, 20.);
+
+        let again = parse_har(&text).unwrap();
+        assert_eq!(again[0].1.timing.connect, 10);
+        assert_eq!(again[0].1.timing.tls, 20);
+        assert_eq!(again[0].0.form[0].value, "admin");
+        assert_eq!(again[0].1.body, r#"{"ok":true}"#);
+    }
+
+    #[test]
+    fn test_export_redact() {
+        let mut req = RequestData {
+            url: "https://api.example.com/a?key=k1&page=2".to_owned(),
+            headers: vec![SelectKeyValueItem::new("Authorization", "Bearer t")],
+            ..Default::default()
+        };
+        req.auth.auth_type = AuthType::ApiKey;
+        req.auth.api_key = "key".to_owned();
+        req.auth.api_key_in_query = true;
+        let resp = ResponseData {
+            headers: vec![SelectKeyValueItem::new("Set-Cookie", "sid=s1")],
+            ..Default::default()
+        };
+        let har = export_har(&[(&req, &resp)], true);
+        let entry = &har.log.entries[0];
+        let text = serde_json::to_string(&har).unwrap();
+        assert!(!text.contains("Bearer t"));
+        assert!(!text.contains("k1"));
+        assert!(!text.contains("sid=s1"));
+        assert!(entry.request.url.contains("page=2"));
+        assert!(entry.request.query_string.iter().any(|p| p.value == MASK));
+    }
+}
@@ -0,0 +1,621 @@
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{BodyType, MultipartItem, RequestData, ScriptData};
+use crate::utils::auth::{is_literal, AuthData, AuthType};
+use crate::utils::curl::set_data_body;
+use crate::utils::har::SENSITIVE_HEADERS;
+use crate::utils::import::{convert_vars, script_stub, vars_script, ImportNode, ImportResult};
+use crate::utils::secret::MASK;
+
+const METHODS: [&str; 9] = [
+    "GET", "POST", "PUT", "DELETE", "PATCH", "HEAD", "OPTIONS", "TRACE", "CONNECT",
+];
+
+///JetBrains和VS Code REST Client的`.http`文件,一个文件导入成一个集合
+pub fn import_http(name: &str, text: &str) -> ImportResult {
+    let mut warnings = Vec::new();
+    let mut vars = Vec::new();
+    let mut children = Vec::new();
+    for (title, lines) in split_blocks(text) {
+        if let Some((name, req, script)) = parse_block(title, &lines, &mut vars, &mut warnings) {
+            children.push(ImportNode::Request {
+                key: String::new(),
+                name,
+                req,
+                script,
+            });
+        }
+    }
+    let root = ImportNode::Collection {
+        key: String::new(),
+        name: name.to_owned(),
+        doc: String::new(),
+        script: vars_script(&vars, &mut warnings),
+        auth: AuthData::default(),
+        children,
+    };
+    ImportResult { root, warnings }
+}
+
+///按`###`拆分,`###`后面的文字是请求名
+fn split_blocks(text: &str) -> Vec<(Option<String>, Vec<&str>)> {
+    let mut blocks = vec![(None, Vec::new())];
+    for line in text.lines() {
+        match line.strip_prefix("###") {
+            Some(title) => {
+                let title = title.trim();
+                blocks.push(((!title.is_empty()).then(|| title.to_owned()), Vec::new()));
+            }
+            None => blocks.last_mut().unwrap().1.push(line),
+        }
+    }
+    blocks
+}
+
+fn parse_block(
+    title: Option<String>,
+    lines: &[&str],
+    vars: &mut Vec<(String, String)>,
+    warnings: &mut Vec<String>,
+) -> Option<(String, RequestData, ScriptData)> {
+    let mut name = title;
+    let mut remark = Vec::new();
+    let mut rest = lines.iter();
+    let request_line = loop {
+        let line = rest.next()?.trim();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#').or_else(|| line.strip_prefix("//")) {
+            let comment = comment.trim();
+            match comment.strip_prefix("@name") {
+                Some(n) => name = Some(n.trim_start_matches([' ', '=']).trim().to_owned()),
+                //其他的@指令不支持
+                None if comment.starts_with('@') => {}
+                None => remark.push(comment),
+            }
+            continue;
+        }
+        if let Some(var) = line.strip_prefix('@') {
+            if let Some((key, value)) = var.split_once('=') {
+                vars.push((key.trim().to_owned(), convert_vars(value.trim(), warnings)));
+            }
+            continue;
+        }
+        break line;
+    };
+
+    let mut req = RequestData {
+        remark: remark.join("\n"),
+        body_type: BodyType::None,
+        ..Default::default()
+    };
+    let mut url = match request_line.split_once(' ') {
+        Some((method, url)) if METHODS.contains(&method) => {
+            if let Ok(m) = method.parse() {
+                req.method = m;
+            }
+            url.trim()
+        }
+        _ => request_line,
+    };
+    //结尾的HTTP/1.1
+    if let Some((u, version)) = url.rsplit_once(' ') {
+        if version.starts_with("HTTP/") {
+            url = u.trim_end();
+        }
+    }
+    let mut url = url.to_owned();
+    let mut rest = rest.peekable();
+    //多行的查询参数以?或&开头
+    while let Some(line) = rest.next_if(|l| l.trim_start().starts_with(['?', '&'])) {
+        url.push_str(line.trim());
+    }
+    req.url = convert_vars(&url, warnings);
+    req.sync_params_from_url();
+
+    for line in rest.by_ref() {
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            req.headers.push(SelectKeyValueItem::new(
+                key.trim(),
+                &convert_vars(value.trim(), warnings),
+            ));
+        }
+    }
+    basic_auth(&mut req);
+
+    let mut body = Vec::new();
+    let mut script = ScriptData::default();
+    let mut handler = Vec::new();
+    let mut in_handler = false;
+    for line in rest {
+        if in_handler {
+            handler.push(*line);
+            in_handler = !line.contains("%}");
+        } else if let Some(h) = line.strip_prefix('>') {
+            handler.push(h.trim());
+            in_handler = h.contains("{%") && !h.contains("%}");
+        } else if !line.starts_with("<>") {
+            body.push(*line);
+        }
+    }
+    if !handler.is_empty() {
+        let label = format!("响应处理脚本({})", name.as_deref().unwrap_or(request_line));
+        warnings.push(format!("{}需要改写成rhai", label));
+        script.after = script_stub(&handler.join("\n"), &label);
+    }
+    while body.last().is_some_and(|l| l.trim().is_empty()) {
+        body.pop();
+    }
+    if !body.is_empty() {
+        set_body(&mut req, &body, warnings);
+    }
+    let name = name.unwrap_or_else(|| format!("{} {}", req.method, url));
+    Some((name, req, script))
+}
+
+///`Authorization: Basic user pass`是用户名和密码,转成认证
+fn basic_auth(req: &mut RequestData) {
+    let Some(pos) = req
+        .headers
+        .iter()
+        .position(|h| h.key.eq_ignore_ascii_case("authorization"))
+    else {
+        return;
+    };
+    let value = req.headers[pos].value.clone();
+    let Some(credential) = value.strip_prefix("Basic ") else {
+        return;
+    };
+    let Some((username, password)) = credential.trim().split_once([' ', ':']) else {
+        return;
+    };
+    req.auth = AuthData {
+        auth_type: AuthType::Basic,
+        username: username.to_owned(),
+        password: password.trim().to_owned(),
+        ..Default::default()
+    };
+    req.headers.remove(pos);
+}
+
+fn content_type(req: &RequestData) -> String {
+    req.headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| h.value.to_ascii_lowercase())
+        .unwrap_or_default()
+}
+
+fn set_body(req: &mut RequestData, body: &[&str], warnings: &mut Vec<String>) {
+    let ct = content_type(req);
+    if let Some(pos) = ct.find("boundary=") {
+        //boundary区分大小写,从原始的头里取
+        let boundary = req
+            .headers
+            .iter()
+            .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+            .map(|h| h.value[pos + 9..].trim_matches('"').to_owned())
+            .unwrap_or_default();
+        req.body_type = BodyType::Multipart;
+        req.multipart = parse_multipart(body, &boundary, warnings);
+        req.headers
+            .retain(|h| !h.key.eq_ignore_ascii_case("content-type"));
+        return;
+    }
+    //`< ./file.json`从文件读取
+    if let [line] = body {
+        if let Some(path) = line.trim().strip_prefix('<') {
+            req.body_type = BodyType::Binary;
+            req.binary = path.trim().to_owned();
+            return;
+        }
+    }
+    let data = convert_vars(&body.join("\n"), warnings);
+    if ct.is_empty() {
+        req.body_type = BodyType::Raw;
+        req.body = data;
+    } else {
+        set_data_body(req, data);
+    }
+}
+
+fn parse_multipart(
+    body: &[&str],
+    boundary: &str,
+    warnings: &mut Vec<String>,
+) -> Vec<MultipartItem> {
+    let delimiter = format!("--{}", boundary);
+    let mut items = Vec::new();
+    let mut part: Option<(Vec<&str>, Vec<&str>, bool)> = None;
+    for &line in body {
+        if line.trim().starts_with(&delimiter) {
+            if let Some((headers, content, _)) = part.take() {
+                items.extend(multipart_item(&headers, &content, warnings));
+            }
+            part = Some((Vec::new(), Vec::new(), false));
+            continue;
+        }
+        let Some((headers, content, in_body)) = part.as_mut() else {
+            continue;
+        };
+        if *in_body {
+            content.push(line);
+        } else if line.trim().is_empty() {
+            *in_body = true;
+        } else {
+            headers.push(line);
+        }
+    }
+    if let Some((headers, content, _)) = part {
+        items.extend(multipart_item(&headers, &content, warnings));
+    }
+    items
+}
+
+fn multipart_item(
+    headers: &[&str],
+    content: &[&str],
+    warnings: &mut Vec<String>,
+) -> Option<MultipartItem> {
+    let disposition = headers
+        .iter()
+        .find(|h| h.to_ascii_lowercase().starts_with("content-disposition"))?;
+    let name = disposition_param(disposition, "name")?;
+    let mut content = content.to_vec();
+    while content.last().is_some_and(|l| l.trim().is_empty()) {
+        content.pop();
+    }
+    match content.as_slice() {
+        [line] if line.trim().starts_with('<') => {
+            Some(MultipartItem::new(&name, line.trim()[1..].trim(), true))
+        }
+        lines => Some(MultipartItem::new(
+            &name,
+            &convert_vars(&lines.join("\n"), warnings),
+            false,
+        )),
+    }
+}
+
+fn disposition_param(header: &str, param: &str) -> Option<String> {
+    header.split(';').skip(1).find_map(|p| {
+        let (key, value) = p.trim().split_once('=')?;
+        (key == param).then(|| value.trim_matches('"').to_owned())
+    })
+}
+
+///`${var}`转回`{{var}}`,模板函数保持原样
+fn to_http_vars(text: &str) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${") {
+        let Some(len) = rest[start + 2..].find('}') else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let inner = rest[start + 2..start + 2 + len].trim();
+        if !inner.is_empty() && inner.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            result.push_str("{{");
+            result.push_str(inner);
+            result.push_str("}}");
+        } else {
+            match inner {
+                "UUID()" => result.push_str("{{$uuid}}"),
+                "NOW('%s')" => result.push_str("{{$timestamp}}"),
+                _ => result.push_str(&rest[start..start + 3 + len]),
+            }
+        }
+        rest = &rest[start + 3 + len..];
+    }
+    result.push_str(rest);
+    result
+}
+
+///填上路径参数,`.http`没有路径参数
+fn export_url(req: &RequestData) -> String {
+    let (path, query) = match req.url.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (req.url.as_str(), None),
+    };
+    let mut url = path
+        .split('/')
+        .map(|segment| {
+            let name = segment
+                .strip_prefix(':')
+                .or_else(|| segment.strip_prefix('{').and_then(|s| s.strip_suffix('}')));
+            match name.and_then(|n| req.path_params.iter().find(|p| p.key == n)) {
+                Some(p) if !p.value.is_empty() => p.value.clone(),
+                Some(p) => format!("${{{}}}", p.key),
+                None => segment.to_owned(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("/");
+    if let Some(query) = query {
+        url.push('?');
+        url.push_str(query);
+    }
+    to_http_vars(&url)
+}
+
+///集合导出成`.http`文件,requests的auth需要是已经解析过继承的,
+///redact时认证里直接写的凭证和Authorization、Cookie头换成掩码
+pub fn export_http(
+    vars: &[(String, String)],
+    requests: &[(String, RequestData)],
+    redact: bool,
+) -> String {
+    let mut text = String::new();
+    for (name, value) in vars {
+        text.push_str(&format!("@{} = {}\n", name, to_http_vars(value)));
+    }
+    for (title, req) in requests {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(&format!("### {}\n", title));
+        if req.remark != "备注" {
+            for line in req.remark.lines().filter(|l| !l.trim().is_empty()) {
+                text.push_str(&format!("# {}\n", line));
+            }
+        }
+        text.push_str(&format!("{} {}\n", req.method, export_url(req)));
+        for header in req.headers.iter().filter(|h| h.selected) {
+            let sensitive = SENSITIVE_HEADERS
+                .iter()
+                .any(|h| h.eq_ignore_ascii_case(&header.key));
+            let value = if redact && sensitive && is_literal(&header.value) {
+                MASK
+            } else {
+                header.value.as_str()
+            };
+            text.push_str(&format!("{}: {}\n", header.key, to_http_vars(value)));
+        }
+        let auth = if redact {
+            req.auth.without_credentials(MASK)
+        } else {
+            req.auth.clone()
+        };
+        match auth.auth_type {
+            AuthType::Basic => text.push_str(&format!(
+                "Authorization: Basic {} {}\n",
+                to_http_vars(&auth.username),
+                to_http_vars(&auth.password)
+            )),
+            AuthType::Bearer => text.push_str(&format!(
+                "Authorization: Bearer {}\n",
+                to_http_vars(&auth.token)
+            )),
+            AuthType::ApiKey if !auth.api_key_in_query => text.push_str(&format!(
+                "{}: {}\n",
+                auth.api_key,
+                to_http_vars(&auth.api_value)
+            )),
+            _ => {}
+        }
+        let has_content_type = req
+            .headers
+            .iter()
+            .any(|h| h.selected && h.key.eq_ignore_ascii_case("content-type"));
+        if !has_content_type {
+            if let Some(ct) = req.body_type.content_type() {
+                text.push_str(&format!("Content-Type: {}\n", ct));
+            }
+        }
+        export_body(req, &mut text);
+    }
+    text
+}
+
+fn export_body(req: &RequestData, text: &mut String) {
+    let body = match req.body_type {
+        BodyType::None => return,
+        BodyType::Raw | BodyType::Json => req.body.clone(),
+        BodyType::Json5 => json5::from_str::<serde_json::Value>(&req.body)
+            .ok()
+            .and_then(|v| serde_json::to_string_pretty(&v).ok())
+            .unwrap_or_else(|| req.body.clone()),
+        BodyType::FormUrlencoded => req
+            .form
+            .iter()
+            .filter(|f| f.selected)
+            .map(|f| format!("{}={}", f.key, f.value))
+            .collect::<Vec<_>>()
+            .join("&"),
+        BodyType::Binary => format!("< {}", req.binary),
+        BodyType::GraphQL => {
+            let variables = json5::from_str::<serde_json::Value>(&req.graphql.variables)
+                .unwrap_or(serde_json::Value::Null);
+            let body = serde_json::json!({ "query": req.graphql.query, "variables": variables });
+            serde_json::to_string_pretty(&body).unwrap_or_default()
+        }
+        BodyType::Multipart => {
+            let boundary = "boundary";
+            text.push_str(&format!(
+                "Content-Type: multipart/form-data; boundary={}\n\n",
+                boundary
+            ));
+            for item in req.multipart.iter().filter(|m| m.selected) {
+                text.push_str(&format!("--{}\n", boundary));
+                if item.is_file {
+                    let file_name = std::path::Path::new(&item.value)
+                        .file_name()
+                        .map(|f| f.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    text.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\n\n< {}\n",
+                        item.key, file_name, item.value
+                    ));
+                } else {
+                    text.push_str(&format!(
+                        "Content-Disposition: form-data; name=\"{}\"\n\n{}\n",
+                        item.key,
+                        to_http_vars(&item.value)
+                    ));
+                }
+            }
+            text.push_str(&format!("--{}--\n", boundary));
+            return;
+        }
+    };
+    text.push('\n');
+    text.push_str(&to_http_vars(&body));
+    text.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_data::Method;
+
+    const HTTP: &str = r#"@host = https://api.example.com
+@token = abc
+
+### 查询用户
+# 按id查询
+GET {{host}}/users/1
+    ?page=1
+    &size={{size}}
+Accept: application/json
+
+### 登录
+POST {{host}}/login HTTP/1.1
+Content-Type: application/json
+Authorization: Basic admin {{password}}
+
+{"name": "{{$uuid}}"}
+
+> {% client.global.set("token", response.body.token); %}
+
+###
+# @name 上传
+POST {{host}}/upload
+Content-Type: multipart/form-data; boundary=WebBoundary
+
+--WebBoundary
+Content-Disposition: form-data; name="note"
+
+hello
+--WebBoundary
+Content-Disposition: form-data; name="file"; filename="a.png"
+
+< ./a.png
+--WebBoundary--
+"#;
+
+    #[test]
+    fn test_import_http() {
+        let result = import_http("api", HTTP);
+        let ImportNode::Collection {
+            name,
+            script,
+            children,
+            ..
+        } = &result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        assert_eq!(name, "api");
+        assert_eq!(
+            script,
+            "let host = \"https://api.example.com\";\nlet token = \"abc\";\n"
+        );
+        assert_eq!(children.len(), 3);
+        let ImportNode::Request { name, req, .. } = &children[0] else {
+            panic!("应该是请求");
+        };
+        assert_eq!(name, "查询用户");
+        assert_eq!(req.remark, "按id查询");
+        assert_eq!(req.url, "${host}/users/1?page=1&size=${size}");
+        assert_eq!(req.params.len(), 2);
+        assert_eq!(req.headers.len(), 1);
+
+        let ImportNode::Request { req, script, .. } = &children[1] else {
+            panic!("应该是请求");
+        };
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.url, "${host}/login");
+        assert_eq!(req.body_type, BodyType::Json);
+        assert_eq!(req.body, "{\"name\": \"${UUID()}\"}");
+        assert_eq!(req.auth.auth_type, AuthType::Basic);
+        assert_eq!(req.auth.password, "${password}");
+        assert!(script.after.contains("client.global.set"));
+        assert_eq!(result.warnings.len(), 1);
+
+        let ImportNode::Request { name, req, .. } = &children[2] else {
+            panic!("应该是请求");
+        };
+        assert_eq!(name, "上传");
+        assert_eq!(req.body_type, BodyType::Multipart);
+        let parts: Vec<_> = req
+            .multipart
+            .iter()
+            .map(|p| (p.key.as_str(), p.value.as_str(), p.is_file))
+            .collect();
+        assert_eq!(
+            parts,
+            vec![("note", "hello", false), ("file", "./a.png", true)]
+        );
+    }
+
+    #[test]
+    fn test_export_round_trip() {
+        let result = import_http("api", HTTP);
+        let ImportNode::Collection {
+            script, children, ..
+        } = result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        let requests: Vec<(String, RequestData)> = children
+            .into_iter()
+            .filter_map(|node| match node {
+                ImportNode::Request { name, req, .. } => Some((name, req)),
+                _ => None,
+            })
+            .collect();
+        let vars = crate::utils::import::script_vars(&script);
+        let text = export_http(&vars, &requests, false);
+        assert!(text.starts_with("@host = https://api.example.com\n@token = abc\n"));
+        assert!(
+            text.contains("### 查询用户\n# 按id查询\nGET {{host}}/users/1?page=1&size={{size}}\n")
+        );
+        assert!(text.contains("Authorization: Basic admin {{password}}\n"));
+        assert!(text.contains("{\"name\": \"{{$uuid}}\"}"));
+
+        let again = import_http("api", &text);
+        let ImportNode::Collection { children, .. } = again.root else {
+            panic!("根节点应该是集合");
+        };
+        let ImportNode::Request { req, .. } = &children[2] else {
+            panic!("应该是请求");
+        };
+        assert_eq!(req.multipart.len(), 2);
+        assert!(req.multipart[1].is_file);
+    }
+
+    #[test]
+    fn test_export_redact() {
+        let mut req = RequestData {
+            url: "https://api.example.com/me".to_owned(),
+            headers: vec![
+                SelectKeyValueItem::new("Cookie", "sid=literal-cookie"),
+                SelectKeyValueItem::new("Proxy-Authorization", "${proxy_auth}"),
+            ],
+            ..Default::default()
+        };
+        req.auth.auth_type = AuthType::Bearer;
+        req.auth.token = "literal-token".to_owned();
+        let requests = vec![("me".to_owned(), req)];
+        let text = export_http(&[], &requests, true);
+        assert!(!text.contains("literal-token"));
+        assert!(!text.contains("literal-cookie"));
+        assert!(text.contains(&format!("Authorization: Bearer {}\n", MASK)));
+        assert!(text.contains("Proxy-Authorization: {{proxy_auth}}\n"));
+        assert!(export_http(&[], &requests, false).contains("literal-token"));
+    }
+}
@@ -0,0 +1,337 @@
+use serde_json::Value;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{RequestData, ScriptData};
+use crate::utils::auth::AuthData;
+
+///导入得到的节点,集合对应CollectionsData,请求对应ApiTester
+#[derive(Debug, Clone)]
+pub enum ImportNode {
+    Collection {
+        ///重新导入时按它找到已有的节点,为空时总是新建
+        key: String,
+        name: String,
+        doc: String,
+        script: String,
+        auth: AuthData,
+        children: Vec<ImportNode>,
+    },
+    Request {
+        key: String,
+        name: String,
+        req: RequestData,
+        script: ScriptData,
+    },
+}
+
+impl ImportNode {
+    ///请求的个数
+    pub fn request_count(&self) -> usize {
+        match self {
+            ImportNode::Collection { children, .. } => {
+                children.iter().map(ImportNode::request_count).sum()
+            }
+            ImportNode::Request { .. } => 1,
+        }
+    }
+
+    ///自己和所有子孙节点的key,空key不算
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys = Vec::new();
+        self.collect_keys(&mut keys);
+        keys
+    }
+
+    fn collect_keys<'a>(&'a self, keys: &mut Vec<&'a str>) {
+        let key = match self {
+            ImportNode::Collection { key, children, .. } => {
+                for child in children {
+                    child.collect_keys(keys);
+                }
+                key
+            }
+            ImportNode::Request { key, .. } => key,
+        };
+        if !key.is_empty() {
+            keys.push(key);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportResult {
+    ///导入成一个新集合,key相同的已有集合会被更新
+    pub root: ImportNode,
+    ///没能转换的内容
+    pub warnings: Vec<String>,
+}
+
+///按内容识别格式,支持Postman v2.1、Insomnia v4和OpenAPI 3/Swagger 2,JSON或YAML
+pub fn import_collection(text: &str) -> Result<ImportResult, String> {
+    let doc = parse_document(text)?;
+    if doc["info"]["schema"]
+        .as_str()
+        .is_some_and(|s| s.contains("getpostman.com"))
+    {
+        return crate::utils::postman::import_postman(&doc);
+    }
+    if doc["_type"] == "export" && doc.get("resources").is_some() {
+        return crate::utils::insomnia::import_insomnia(&doc);
+    }
+    if doc.get("openapi").is_some() || doc.get("swagger").is_some() {
+        return crate::utils::openapi::import_openapi(&doc);
+    }
+    Err("无法识别的格式,只支持Postman v2.1、Insomnia v4导出文件和OpenAPI文档".to_owned())
+}
+
+pub fn parse_document(text: &str) -> Result<Value, String> {
+    match serde_json::from_str::<Value>(text) {
+        Ok(value) => Ok(value),
+        Err(json_err) => serde_yaml::from_str::<Value>(text)
+            .map_err(|yaml_err| format!("既不是JSON也不是YAML:{};{}", json_err, yaml_err)),
+    }
+}
+
+///`{{var}}`和Insomnia的`{{ _.var }}`转成`${var}`
+pub fn convert_vars(text: &str, warnings: &mut Vec<String>) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        result.push_str(&rest[..start]);
+        let inner = rest[start + 2..start + 2 + len].trim();
+        let name = inner.strip_prefix("_.").unwrap_or(inner);
+        match name.strip_prefix('$') {
+            Some(dynamic) => match dynamic_var(dynamic) {
+                Some(expr) => {
+                    result.push_str("${");
+                    result.push_str(expr);
+                    result.push('}');
+                }
+                None => {
+                    warnings.push(format!("动态变量{{{{{}}}}}没有对应的模板函数", name));
+                    result.push_str(&rest[start..start + 4 + len]);
+                }
+            },
+            None => {
+                let ident = to_ident(name);
+                if ident != name {
+                    warnings.push(format!("变量{}改名为{}", name, ident));
+                }
+                result.push_str("${");
+                result.push_str(&ident);
+                result.push('}');
+            }
+        }
+        rest = &rest[start + 4 + len..];
+    }
+    result.push_str(rest);
+    if result.contains("{%") {
+        warnings.push(format!("模板标签无法转换:{}", text));
+    }
+    result
+}
+
+///Postman的动态变量对应的模板函数
+fn dynamic_var(name: &str) -> Option<&'static str> {
+    match name {
+        "guid" | "randomUUID" | "uuid" | "random.uuid" => Some("UUID()"),
+        "timestamp" => Some("NOW('%s')"),
+        "isoTimestamp" => Some("NOW('%Y-%m-%dT%H:%M:%S%.3fZ')"),
+        "randomInt" => Some("NUM(0,1000)"),
+        "randomEmail" | "randomExampleEmail" => Some("EMAIL()"),
+        "randomUserName" => Some("USERNAME()"),
+        "randomIP" => Some("IPV4()"),
+        "randomIPV6" => Some("IPV6()"),
+        "randomMACAddress" => Some("MAC()"),
+        "randomUserAgent" => Some("USERAGENT()"),
+        "randomFullName" => Some("NAME_EN()"),
+        _ => None,
+    }
+}
+
+///模板和rhai的变量名只能是字母、数字和下划线
+pub fn to_ident(name: &str) -> String {
+    let mut ident: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if ident.is_empty() || ident.starts_with(|c: char| c.is_ascii_digit()) {
+        ident.insert(0, '_');
+    }
+    ident
+}
+
+///集合变量写成集合脚本里的let,执行后会进入模板上下文
+pub fn vars_script(vars: &[(String, String)], warnings: &mut Vec<String>) -> String {
+    vars.iter()
+        .map(|(name, value)| {
+            let ident = to_ident(name);
+            if &ident != name {
+                warnings.push(format!("变量{}改名为{}", name, ident));
+            }
+            format!("let {} = {};\n", ident, Value::String(value.clone()))
+        })
+        .collect()
+}
+
+///从集合脚本里取出`let name = "value";`形式的变量,和vars_script相反
+pub fn script_vars(script: &str) -> Vec<(String, String)> {
+    script
+        .lines()
+        .filter_map(|line| {
+            let (name, value) = line.trim().strip_prefix("let ")?.split_once('=')?;
+            let value = value.trim().trim_end_matches(';').trim_end();
+            let value = serde_json::from_str::<String>(value).ok()?;
+            Some((name.trim().to_owned(), value))
+        })
+        .collect()
+}
+
+///不能转换的脚本注释掉保留,方便手动改写成rhai
+pub fn script_stub(source: &str, title: &str) -> String {
+    if source.trim().is_empty() {
+        return String::new();
+    }
+    let mut stub = format!("// {},需要改写成rhai\n", title);
+    for line in source.lines() {
+        stub.push_str("// ");
+        stub.push_str(line);
+        stub.push('\n');
+    }
+    stub
+}
+
+///重新导入时合并请求:结构以新的为准,已经填过的参数值、请求体、认证和设置保留
+pub fn merge_request(old: &RequestData, new: RequestData) -> RequestData {
+    let keep = |old: &[SelectKeyValueItem], mut new: Vec<SelectKeyValueItem>| {
+        for item in new.iter_mut() {
+            if let Some(prev) = old.iter().find(|p| p.key == item.key) {
+                if !prev.value.is_empty() {
+                    item.value = prev.value.clone();
+                }
+                item.selected = prev.selected;
+            }
+        }
+        new
+    };
+    let mut req = RequestData {
+        params: keep(&old.params, new.params),
+        path_params: keep(&old.path_params, new.path_params),
+        headers: keep(&old.headers, new.headers),
+        form: keep(&old.form, new.form),
+        auth: old.auth.clone(),
+        settings: old.settings.clone(),
+        ..new
+    };
+    if req.body_type == old.body_type && !old.body.is_empty() {
+        req.body = old.body.clone();
+    }
+    //保留用户加的头
+    for header in old.headers.iter() {
+        if !req.headers.iter().any(|h| h.key == header.key) {
+            req.headers.push(header.clone());
+        }
+    }
+    req.sync_url_from_params();
+    req
+}
+
+///拼接脚本,中间空一行
+pub fn join_script(a: &str, b: &str) -> String {
+    match (a.is_empty(), b.is_empty()) {
+        (true, _) => b.to_owned(),
+        (_, true) => a.to_owned(),
+        _ => format!("{}\n{}", a, b),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_vars() {
+        let mut warnings = Vec::new();
+        assert_eq!(
+            convert_vars(
+                "{{baseUrl}}/users/{{ _.user-id }}?t={{$timestamp}}",
+                &mut warnings
+            ),
+            "${baseUrl}/users/${user_id}?t=${NOW('%s')}"
+        );
+        assert_eq!(warnings, vec!["变量user-id改名为user_id"]);
+        assert_eq!(
+            convert_vars("{{$randomColor}}", &mut warnings),
+            "{{$randomColor}}"
+        );
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(convert_vars("{{unclosed", &mut warnings), "{{unclosed");
+    }
+
+    #[test]
+    fn test_keys() {
+        let request = |key: &str| ImportNode::Request {
+            key: key.to_owned(),
+            name: String::new(),
+            req: RequestData::default(),
+            script: ScriptData::default(),
+        };
+        let root = ImportNode::Collection {
+            key: "root".to_owned(),
+            name: String::new(),
+            doc: String::new(),
+            script: String::new(),
+            auth: AuthData::default(),
+            children: vec![request("GET /a"), request("")],
+        };
+        assert_eq!(root.keys(), vec!["GET /a", "root"]);
+    }
+
+    #[test]
+    fn test_merge_request() {
+        let old = RequestData {
+            url: "${baseUrl}/pets?limit=50".to_owned(),
+            params: vec![SelectKeyValueItem::new("limit", "50")],
+            headers: vec![SelectKeyValueItem::new("X-Debug", "1")],
+            body: "{\"name\":\"tom\"}".to_owned(),
+            ..Default::default()
+        };
+        let new = RequestData {
+            url: "${baseUrl}/animals?limit=0&type=cat".to_owned(),
+            params: vec![
+                SelectKeyValueItem::new("limit", "0"),
+                SelectKeyValueItem::new("type", "cat"),
+            ],
+            body: "{}".to_owned(),
+            ..Default::default()
+        };
+        let req = merge_request(&old, new);
+        assert_eq!(req.url, "${baseUrl}/animals?limit=50&type=cat");
+        assert_eq!(req.body, "{\"name\":\"tom\"}");
+        assert_eq!(req.headers, old.headers);
+    }
+
+    #[test]
+    fn test_vars_script() {
+        let mut warnings = Vec::new();
+        let vars = vec![("host".to_owned(), "a\"b".to_owned())];
+        assert_eq!(
+            vars_script(&vars, &mut warnings),
+            "let host = \"a\\\"b\";\n"
+        );
+        assert_eq!(script_vars(&vars_script(&vars, &mut warnings)), vars);
+        assert_eq!(
+            script_stub("pm.test()\n", "测试脚本"),
+            "// 测试脚本,需要改写成rhai\n// pm.test()\n"
+        );
+    }
+}
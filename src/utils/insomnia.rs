@@ -0,0 +1,378 @@
+use serde_json::Value;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{BodyType, GraphQLBody, MultipartItem, RequestData, ScriptData};
+use crate::utils::auth::{AuthData, AuthType};
+use crate::utils::import::{
+    convert_vars, join_script, script_stub, vars_script, ImportNode, ImportResult,
+};
+
+///Insomnia v4导出,request_group转成集合,request转成ApiTester
+pub fn import_insomnia(doc: &Value) -> Result<ImportResult, String> {
+    let resources = doc["resources"]
+        .as_array()
+        .ok_or_else(|| "没有resources".to_owned())?;
+    let mut warnings = Vec::new();
+    let workspace = resources.iter().find(|r| r["_type"] == "workspace");
+    let root_id = workspace.map(|w| str_of(&w["_id"])).unwrap_or_default();
+    let name = workspace
+        .map(|w| str_of(&w["name"]))
+        .filter(|n| !n.is_empty())
+        .unwrap_or("Insomnia");
+    for resource in resources {
+        let kind = str_of(&resource["_type"]);
+        if !matches!(
+            kind,
+            "workspace" | "request_group" | "request" | "environment" | "cookie_jar"
+        ) {
+            warnings.push(format!(
+                "{}:不支持的资源类型{}",
+                str_of(&resource["name"]),
+                kind
+            ));
+        }
+    }
+    //工作区下的基础环境作为根集合的变量
+    let base_env = resources
+        .iter()
+        .find(|r| r["_type"] == "environment" && r["parentId"] == root_id)
+        .map(|env| env_vars(&env["data"]))
+        .unwrap_or_default();
+    let mut root = import_group(resources, root_id, name, &Value::Null, &mut warnings);
+    if let ImportNode::Collection { script, .. } = &mut root {
+        *script = join_script(&vars_script(&base_env, &mut warnings), script);
+    }
+    Ok(ImportResult { root, warnings })
+}
+
+fn str_of(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+///环境里嵌套的对象展开成a_b这样的变量
+fn env_vars(data: &Value) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+    if let Some(map) = data.as_object() {
+        for (k, v) in map {
+            match v {
+                Value::Object(_) => {
+                    for (sub_k, sub_v) in env_vars(v) {
+                        vars.push((format!("{}_{}", k, sub_k), sub_v));
+                    }
+                }
+                other => vars.push((k.clone(), value_string(other))),
+            }
+        }
+    }
+    vars
+}
+
+///请求按metaSortKey排序,和Insomnia里显示的顺序一致
+fn children_of<'a>(resources: &'a [Value], parent: &str) -> Vec<&'a Value> {
+    let mut children: Vec<&Value> = resources
+        .iter()
+        .filter(|r| r["parentId"] == parent)
+        .filter(|r| r["_type"] == "request_group" || r["_type"] == "request")
+        .collect();
+    children.sort_by(|a, b| {
+        let key = |v: &Value| v["metaSortKey"].as_f64().unwrap_or_default();
+        key(a).total_cmp(&key(b))
+    });
+    children
+}
+
+fn import_group(
+    resources: &[Value],
+    id: &str,
+    name: &str,
+    group: &Value,
+    warnings: &mut Vec<String>,
+) -> ImportNode {
+    let vars = env_vars(&group["environment"]);
+    let pre = script_stub(
+        str_of(&group["preRequestScript"]),
+        &format!("Insomnia前置脚本({})", name),
+    );
+    let after = script_stub(
+        str_of(&group["afterResponseScript"]),
+        &format!("Insomnia响应后脚本({})", name),
+    );
+    let children = children_of(resources, id)
+        .into_iter()
+        .map(|child| {
+            let child_name = str_of(&child["name"]);
+            if child["_type"] == "request_group" {
+                import_group(
+                    resources,
+                    str_of(&child["_id"]),
+                    child_name,
+                    child,
+                    warnings,
+                )
+            } else {
+                import_request(child_name, child, warnings)
+            }
+        })
+        .collect();
+    ImportNode::Collection {
+        key: String::new(),
+        name: name.to_owned(),
+        doc: str_of(&group["description"]).to_owned(),
+        script: join_script(&vars_script(&vars, warnings), &join_script(&pre, &after)),
+        auth: import_auth(&group["authentication"], name, warnings),
+        children,
+    }
+}
+
+fn key_values(items: &Value, warnings: &mut Vec<String>) -> Vec<SelectKeyValueItem> {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|item| !str_of(&item["name"]).is_empty())
+        .map(|item| {
+            let mut kv = SelectKeyValueItem::new(
+                str_of(&item["name"]),
+                &convert_vars(&value_string(&item["value"]), warnings),
+            );
+            kv.selected = item["disabled"] != true;
+            kv
+        })
+        .collect()
+}
+
+fn import_request(name: &str, request: &Value, warnings: &mut Vec<String>) -> ImportNode {
+    let mut req = RequestData {
+        remark: str_of(&request["description"]).to_owned(),
+        body_type: BodyType::None,
+        ..Default::default()
+    };
+    match str_of(&request["method"]).to_uppercase().parse() {
+        Ok(m) => req.method = m,
+        Err(e) => warnings.push(format!("{}:{}", name, e)),
+    }
+    req.url = convert_vars(str_of(&request["url"]), warnings);
+    req.sync_params_from_url();
+    let params = key_values(&request["parameters"], warnings);
+    if !params.is_empty() {
+        req.params.extend(params);
+        req.sync_url_from_params();
+    }
+    req.headers = key_values(&request["headers"], warnings);
+    import_body(&mut req, &request["body"], name, warnings);
+    req.auth = import_auth(&request["authentication"], name, warnings);
+    ImportNode::Request {
+        key: String::new(),
+        name: name.to_owned(),
+        req,
+        script: ScriptData {
+            pre: script_stub(
+                str_of(&request["preRequestScript"]),
+                &format!("Insomnia前置脚本({})", name),
+            ),
+            after: script_stub(
+                str_of(&request["afterResponseScript"]),
+                &format!("Insomnia响应后脚本({})", name),
+            ),
+        },
+    }
+}
+
+fn import_body(req: &mut RequestData, body: &Value, name: &str, warnings: &mut Vec<String>) {
+    let mime = str_of(&body["mimeType"]).to_ascii_lowercase();
+    let text = convert_vars(str_of(&body["text"]), warnings);
+    if mime.starts_with("application/x-www-form-urlencoded") {
+        req.body_type = BodyType::FormUrlencoded;
+        req.form = key_values(&body["params"], warnings);
+    } else if mime.starts_with("multipart/form-data") {
+        req.body_type = BodyType::Multipart;
+        req.multipart = body["params"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|item| {
+                let key = str_of(&item["name"]);
+                let mut part = if item["type"] == "file" {
+                    MultipartItem::new(key, str_of(&item["fileName"]), true)
+                } else {
+                    MultipartItem::new(
+                        key,
+                        &convert_vars(&value_string(&item["value"]), warnings),
+                        false,
+                    )
+                };
+                part.selected = item["disabled"] != true;
+                part
+            })
+            .collect();
+    } else if mime == "application/graphql" {
+        //text是{"query","variables"}
+        let graphql: Value = serde_json::from_str(&text).unwrap_or_default();
+        req.body_type = BodyType::GraphQL;
+        req.graphql = GraphQLBody {
+            query: str_of(&graphql["query"]).to_owned(),
+            variables: match &graphql["variables"] {
+                Value::Null => String::new(),
+                variables => serde_json::to_string_pretty(variables).unwrap_or_default(),
+            },
+        };
+    } else if let Some(file_name) = body["fileName"].as_str() {
+        req.body_type = BodyType::Binary;
+        req.binary = file_name.to_owned();
+    } else if !text.is_empty() {
+        req.body = text;
+        req.body_type = if mime.contains("json") {
+            BodyType::Json
+        } else {
+            BodyType::Raw
+        };
+    } else if !mime.is_empty() {
+        warnings.push(format!("{}:请求体{}没有内容", name, mime));
+    }
+}
+
+fn import_auth(auth: &Value, name: &str, warnings: &mut Vec<String>) -> AuthData {
+    let mut data = AuthData::default();
+    if auth["disabled"] == true {
+        data.auth_type = AuthType::None;
+        return data;
+    }
+    let field = |key: &str| value_string(&auth[key]);
+    match str_of(&auth["type"]) {
+        "" => return data,
+        "none" => data.auth_type = AuthType::None,
+        "basic" => {
+            data.auth_type = AuthType::Basic;
+            data.username = field("username");
+            data.password = field("password");
+        }
+        "digest" => {
+            data.auth_type = AuthType::Digest;
+            data.username = field("username");
+            data.password = field("password");
+        }
+        "bearer" => {
+            data.auth_type = AuthType::Bearer;
+            data.token = field("token");
+            let prefix = field("prefix");
+            if !prefix.is_empty() && prefix != "Bearer" {
+                warnings.push(format!("{}:Bearer前缀{}被替换为Bearer", name, prefix));
+            }
+        }
+        "apikey" => {
+            data.auth_type = AuthType::ApiKey;
+            data.api_key = field("key");
+            data.api_value = field("value");
+            data.api_key_in_query = field("addTo") == "queryParams";
+        }
+        "oauth2" => {
+            match field("grantType").as_str() {
+                "client_credentials" => data.auth_type = AuthType::OAuth2ClientCredentials,
+                "password" => data.auth_type = AuthType::OAuth2Password,
+                grant => {
+                    warnings.push(format!("{}:不支持的OAuth2授权方式{}", name, grant));
+                    return data;
+                }
+            }
+            data.token_url = field("accessTokenUrl");
+            data.client_id = field("clientId");
+            data.client_secret = field("clientSecret");
+            data.scope = field("scope");
+            data.username = field("username");
+            data.password = field("password");
+        }
+        other => {
+            warnings.push(format!("{}:不支持的认证方式{}", name, other));
+            return data;
+        }
+    }
+    for field in [
+        &mut data.username,
+        &mut data.password,
+        &mut data.token,
+        &mut data.api_key,
+        &mut data.api_value,
+        &mut data.token_url,
+        &mut data.client_id,
+        &mut data.client_secret,
+        &mut data.scope,
+    ] {
+        *field = convert_vars(field, warnings);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::import::import_collection;
+
+    const EXPORT: &str = r#"{"_type": "export", "__export_format": 4, "resources": [
+        {"_id": "wrk_1", "_type": "workspace", "name": "Shop"},
+        {"_id": "env_1", "_type": "environment", "parentId": "wrk_1", "data": {"base": "http://localhost", "auth": {"user": "admin"}}},
+        {"_id": "fld_1", "_type": "request_group", "parentId": "wrk_1", "name": "Orders", "metaSortKey": 2,
+         "environment": {"page_size": 20}},
+        {"_id": "req_2", "_type": "request", "parentId": "fld_1", "name": "List", "method": "GET",
+         "url": "{{ _.base }}/orders", "parameters": [{"name": "size", "value": "{{ _.page_size }}"}],
+         "headers": [], "body": {}, "authentication": {"type": "basic", "username": "{{ _.auth_user }}", "password": "p"}},
+        {"_id": "req_1", "_type": "request", "parentId": "wrk_1", "name": "Login", "method": "POST", "metaSortKey": 1,
+         "url": "{{ _.base }}/login", "headers": [{"name": "Content-Type", "value": "application/json"}],
+         "body": {"mimeType": "application/json", "text": "{\"token\": \"{% uuid 'v4' %}\"}"},
+         "authentication": {"type": "hawk"}},
+        {"_id": "ut_1", "_type": "unit_test", "parentId": "wrk_1", "name": "check"}
+    ]}"#;
+
+    #[test]
+    fn test_import_insomnia() {
+        let result = import_collection(EXPORT).unwrap();
+        let ImportNode::Collection {
+            name,
+            script,
+            children,
+            ..
+        } = &result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        assert_eq!(name, "Shop");
+        assert!(script.contains("let base = \"http://localhost\";"));
+        assert!(script.contains("let auth_user = \"admin\";"));
+        assert_eq!(children.len(), 2);
+
+        let ImportNode::Request { name, req, .. } = &children[0] else {
+            panic!("Login应该是请求");
+        };
+        assert_eq!(name, "Login");
+        assert_eq!(req.body_type, BodyType::Json);
+        assert!(req.auth.is_inherit());
+
+        let ImportNode::Collection {
+            script,
+            children: orders,
+            ..
+        } = &children[1]
+        else {
+            panic!("Orders应该是集合");
+        };
+        assert_eq!(script, "let page_size = \"20\";\n");
+        let ImportNode::Request { req, .. } = &orders[0] else {
+            panic!("List应该是请求");
+        };
+        assert_eq!(req.url, "${base}/orders?size=${page_size}");
+        assert_eq!(req.params[0].value, "${page_size}");
+        assert_eq!(req.auth.auth_type, AuthType::Basic);
+        assert_eq!(req.auth.username, "${auth_user}");
+
+        assert!(result.warnings.iter().any(|w| w.contains("hawk")));
+        assert!(result.warnings.iter().any(|w| w.contains("unit_test")));
+        assert!(result.warnings.iter().any(|w| w.contains("{%")));
+    }
+}
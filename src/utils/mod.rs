@@ -1,4 +1,30 @@
 pub mod aes_tool;
+pub mod assertion;
+pub mod auth;
+pub mod client;
+pub mod codegen;
+pub mod debugger;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod curl;
+pub mod environment;
+pub mod extractor;
+pub mod graphql;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod har;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod http_file;
+pub mod import;
+pub mod insomnia;
+pub mod openapi;
+pub mod postman;
 pub mod rhai_script;
+pub mod script_data;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod script_http;
+pub mod secret;
 pub mod template;
-pub mod regen;
\ No newline at end of file
+#[cfg(not(target_arch = "wasm32"))]
+pub mod timing;
+pub mod websocket;
+pub mod regen;
+pub mod stream;
\ No newline at end of file
@@ -0,0 +1,940 @@
+use serde_json::{Map, Value};
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{
+    path_param_names, BodyType, MultipartItem, RequestData, ResponseData, ScriptData,
+};
+use crate::utils::auth::AuthData;
+use crate::utils::import::{vars_script, ImportNode, ImportResult};
+
+///示例生成的最大嵌套层数,防止循环引用
+const MAX_DEPTH: usize = 5;
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+///导入OpenAPI 3和Swagger 2,每个tag一个集合,每个操作一个请求
+pub fn import_openapi(doc: &Value) -> Result<ImportResult, String> {
+    let swagger2 = doc["swagger"].as_str().is_some_and(|v| v.starts_with('2'));
+    if !swagger2 && !doc["openapi"].as_str().is_some_and(|v| v.starts_with('3')) {
+        return Err("只支持OpenAPI 3和Swagger 2".to_owned());
+    }
+    let spec = Spec { doc, swagger2 };
+    let mut warnings = Vec::new();
+    let title = doc["info"]["title"].as_str().unwrap_or("OpenAPI");
+
+    //(tag,说明,操作)
+    let mut tags: Vec<(String, String, Vec<ImportNode>)> = doc["tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tag| {
+            let name = tag["name"].as_str()?;
+            Some((
+                name.to_owned(),
+                str_of(&tag["description"]).to_owned(),
+                Vec::new(),
+            ))
+        })
+        .collect();
+    let mut untagged = Vec::new();
+    for (path, item) in doc["paths"].as_object().into_iter().flatten() {
+        for method in METHODS {
+            let op = &item[method];
+            if !op.is_object() {
+                continue;
+            }
+            let node = spec.operation(path, method, item, op, &mut warnings);
+            match op["tags"][0].as_str() {
+                Some(tag) => match tags.iter_mut().find(|t| t.0 == tag) {
+                    Some(t) => t.2.push(node),
+                    None => tags.push((tag.to_owned(), String::new(), vec![node])),
+                },
+                None => untagged.push(node),
+            }
+        }
+    }
+
+    let mut children: Vec<ImportNode> = tags
+        .into_iter()
+        .filter(|t| !t.2.is_empty())
+        .map(|(name, doc, children)| ImportNode::Collection {
+            key: format!("tag:{}", name),
+            name,
+            doc,
+            script: String::new(),
+            auth: AuthData::default(),
+            children,
+        })
+        .collect();
+    children.extend(untagged);
+    let vars = vec![("baseUrl".to_owned(), spec.base_url())];
+    let root = ImportNode::Collection {
+        key: format!("openapi:{}", title),
+        name: title.to_owned(),
+        doc: markdown(title, &doc["info"]["description"]),
+        script: vars_script(&vars, &mut warnings),
+        auth: AuthData::default(),
+        children,
+    };
+    Ok(ImportResult { root, warnings })
+}
+
+fn str_of(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+fn markdown(title: &str, description: &Value) -> String {
+    match description.as_str() {
+        Some(d) if !d.is_empty() => format!("# {}\n\n{}", title, d),
+        _ => format!("# {}", title),
+    }
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+struct Spec<'a> {
+    doc: &'a Value,
+    swagger2: bool,
+}
+
+impl<'a> Spec<'a> {
+    ///只支持文档内的`#/...`引用
+    fn resolve(&self, value: &'a Value) -> &'a Value {
+        let mut value = value;
+        for _ in 0..MAX_DEPTH {
+            let Some(reference) = value["$ref"].as_str() else {
+                break;
+            };
+            let Some(pointer) = reference.strip_prefix('#') else {
+                break;
+            };
+            match self.doc.pointer(pointer) {
+                Some(target) => value = target,
+                None => break,
+            }
+        }
+        value
+    }
+
+    fn base_url(&self) -> String {
+        if self.swagger2 {
+            let scheme = self.doc["schemes"][0].as_str().unwrap_or("https");
+            let host = self.doc["host"].as_str().unwrap_or("localhost");
+            let base_path = str_of(&self.doc["basePath"]);
+            return format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/'));
+        }
+        let server = &self.doc["servers"][0];
+        let mut url = str_of(&server["url"]).to_owned();
+        for (name, var) in server["variables"].as_object().into_iter().flatten() {
+            url = url.replace(&format!("{{{}}}", name), &value_string(&var["default"]));
+        }
+        url.trim_end_matches('/').to_owned()
+    }
+
+    fn operation(
+        &self,
+        path: &str,
+        method: &str,
+        item: &'a Value,
+        op: &'a Value,
+        warnings: &mut Vec<String>,
+    ) -> ImportNode {
+        let method_upper = method.to_uppercase();
+        let name = op["summary"]
+            .as_str()
+            .or(op["operationId"].as_str())
+            .filter(|n| !n.is_empty())
+            .map(str::to_owned)
+            .unwrap_or_else(|| format!("{} {}", method_upper, path));
+        let mut req = RequestData {
+            remark: markdown(&name, &op["description"]),
+            url: format!("${{baseUrl}}{}", path),
+            body_type: BodyType::None,
+            ..Default::default()
+        };
+        if let Ok(m) = method_upper.parse() {
+            req.method = m;
+        }
+
+        //操作上的参数覆盖路径上同名同位置的参数
+        let mut params: Vec<&Value> = Vec::new();
+        for param in item["parameters"]
+            .as_array()
+            .into_iter()
+            .chain(op["parameters"].as_array())
+            .flatten()
+        {
+            let param = self.resolve(param);
+            params.retain(|p| p["name"] != param["name"] || p["in"] != param["in"]);
+            params.push(param);
+        }
+        let mut form_params = Vec::new();
+        let mut path_values = Vec::new();
+        for param in params {
+            let key = str_of(&param["name"]);
+            let required = param["required"] == true;
+            match str_of(&param["in"]) {
+                "query" => {
+                    let mut kv = SelectKeyValueItem::new(key, &self.param_example(param));
+                    kv.selected = required;
+                    req.params.push(kv);
+                }
+                "header" if required => {
+                    req.headers
+                        .push(SelectKeyValueItem::new(key, &self.param_example(param)));
+                }
+                "path" => path_values.push((key.to_owned(), self.param_example(param))),
+                "body" => {
+                    req.body_type = BodyType::Json;
+                    req.body = pretty(&self.example(&param["schema"], 0));
+                }
+                "formData" => form_params.push(param),
+                _ => {}
+            }
+        }
+        req.sync_url_from_params();
+        req.path_params = path_param_names(&req.url)
+            .into_iter()
+            .map(|name| {
+                let value = path_values
+                    .iter()
+                    .find(|(key, _)| key == &name)
+                    .map(|(_, value)| value.clone())
+                    .unwrap_or_default();
+                SelectKeyValueItem::new(&name, &value)
+            })
+            .collect();
+        if !form_params.is_empty() {
+            self.swagger2_form(&mut req, op, &form_params);
+        }
+        if !op["requestBody"].is_null() {
+            self.request_body(&mut req, &op["requestBody"], &name, warnings);
+        }
+        ImportNode::Request {
+            key: format!("op:{} {}", method_upper, path),
+            name,
+            req,
+            script: ScriptData::default(),
+        }
+    }
+
+    fn param_example(&self, param: &'a Value) -> String {
+        for value in [&param["example"], &param["schema"]["example"]] {
+            if !value.is_null() {
+                return value_string(value);
+            }
+        }
+        //Swagger 2的非body参数直接带type
+        let schema = if param["schema"].is_null() {
+            param
+        } else {
+            &param["schema"]
+        };
+        match self.example(schema, 0) {
+            Value::Array(items) => items.iter().map(value_string).collect::<Vec<_>>().join(","),
+            other => value_string(&other),
+        }
+    }
+
+    fn swagger2_form(&self, req: &mut RequestData, op: &Value, params: &[&'a Value]) {
+        let multipart = params.iter().any(|p| p["type"] == "file")
+            || op["consumes"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .any(|c| c == "multipart/form-data");
+        if multipart {
+            req.body_type = BodyType::Multipart;
+            req.multipart = params
+                .iter()
+                .map(|p| {
+                    let is_file = p["type"] == "file";
+                    let value = if is_file {
+                        String::new()
+                    } else {
+                        self.param_example(p)
+                    };
+                    MultipartItem::new(str_of(&p["name"]), &value, is_file)
+                })
+                .collect();
+        } else {
+            req.body_type = BodyType::FormUrlencoded;
+            req.form = params
+                .iter()
+                .map(|p| SelectKeyValueItem::new(str_of(&p["name"]), &self.param_example(p)))
+                .collect();
+        }
+    }
+
+    fn request_body(
+        &self,
+        req: &mut RequestData,
+        body: &'a Value,
+        name: &str,
+        warnings: &mut Vec<String>,
+    ) {
+        let body = self.resolve(body);
+        let Some(content) = body["content"].as_object() else {
+            return;
+        };
+        let json = content.iter().find(|(mime, _)| mime.contains("json"));
+        if let Some((_, media)) = json {
+            req.body_type = BodyType::Json;
+            req.body = pretty(&self.media_example(media));
+            return;
+        }
+        if let Some(media) = content.get("application/x-www-form-urlencoded") {
+            req.body_type = BodyType::FormUrlencoded;
+            req.form = self
+                .properties(&media["schema"])
+                .into_iter()
+                .map(|(key, schema)| {
+                    SelectKeyValueItem::new(&key, &value_string(&self.example(schema, 0)))
+                })
+                .collect();
+            return;
+        }
+        if let Some(media) = content.get("multipart/form-data") {
+            req.body_type = BodyType::Multipart;
+            req.multipart = self
+                .properties(&media["schema"])
+                .into_iter()
+                .map(|(key, schema)| {
+                    let schema = self.resolve(schema);
+                    if schema["format"] == "binary" || schema["format"] == "base64" {
+                        MultipartItem::new(&key, "", true)
+                    } else {
+                        MultipartItem::new(&key, &value_string(&self.example(schema, 0)), false)
+                    }
+                })
+                .collect();
+            return;
+        }
+        match content.iter().next() {
+            Some((mime, _)) if mime == "application/octet-stream" => {
+                req.body_type = BodyType::Binary;
+            }
+            Some((mime, media)) if mime.starts_with("text/") => {
+                req.body_type = BodyType::Raw;
+                req.body = value_string(&self.media_example(media));
+            }
+            Some((mime, _)) => warnings.push(format!("{}:不支持的请求体类型{}", name, mime)),
+            None => {}
+        }
+        if let Some((mime, _)) = content.iter().next() {
+            req.headers
+                .push(SelectKeyValueItem::new("Content-Type", mime));
+        }
+    }
+
+    fn media_example(&self, media: &'a Value) -> Value {
+        if !media["example"].is_null() {
+            return media["example"].clone();
+        }
+        if let Some(first) = media["examples"]
+            .as_object()
+            .and_then(|e| e.values().next())
+        {
+            let first = self.resolve(first);
+            if !first["value"].is_null() {
+                return first["value"].clone();
+            }
+        }
+        self.example(&media["schema"], 0)
+    }
+
+    ///对象的属性,合并allOf
+    fn properties(&self, schema: &'a Value) -> Vec<(String, &'a Value)> {
+        let schema = self.resolve(schema);
+        let mut props: Vec<(String, &Value)> = schema["properties"]
+            .as_object()
+            .into_iter()
+            .flatten()
+            .map(|(k, v)| (k.clone(), v))
+            .collect();
+        for sub in schema["allOf"].as_array().into_iter().flatten() {
+            props.extend(self.properties(sub));
+        }
+        props
+    }
+
+    ///按schema生成示例值,优先用example、default和enum
+    fn example(&self, schema: &'a Value, depth: usize) -> Value {
+        let schema = self.resolve(schema);
+        for key in ["example", "default"] {
+            if !schema[key].is_null() {
+                return schema[key].clone();
+            }
+        }
+        if let Some(first) = schema["enum"].as_array().and_then(|e| e.first()) {
+            return first.clone();
+        }
+        if depth > MAX_DEPTH {
+            return Value::Null;
+        }
+        for key in ["oneOf", "anyOf"] {
+            if let Some(first) = schema[key].as_array().and_then(|s| s.first()) {
+                return self.example(first, depth + 1);
+            }
+        }
+        let type_name = match &schema["type"] {
+            Value::Array(types) => types
+                .iter()
+                .map(str_of)
+                .find(|t| *t != "null")
+                .unwrap_or(""),
+            other => str_of(other),
+        };
+        let is_object = type_name == "object"
+            || (type_name.is_empty()
+                && (schema.get("properties").is_some() || schema.get("allOf").is_some()));
+        if is_object {
+            let mut map = Map::new();
+            for (key, prop) in self.properties(schema) {
+                map.insert(key, self.example(prop, depth + 1));
+            }
+            return Value::Object(map);
+        }
+        match type_name {
+            "array" => Value::Array(vec![self.example(&schema["items"], depth + 1)]),
+            "integer" => 0.into(),
+            "number" => 0.0.into(),
+            "boolean" => true.into(),
+            "string" => match str_of(&schema["format"]) {
+                "date" => "2024-01-01".into(),
+                "date-time" => "2024-01-01T00:00:00Z".into(),
+                "email" => "user@example.com".into(),
+                "uuid" => "00000000-0000-0000-0000-000000000000".into(),
+                "uri" | "url" => "https://example.com".into(),
+                "ipv4" => "127.0.0.1".into(),
+                _ => "string".into(),
+            },
+            _ => Value::Null,
+        }
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+///导出时集合的信息,vars是集合脚本里的变量,用来还原服务器地址
+#[derive(Debug, Default, Clone)]
+pub struct SpecInfo {
+    pub title: String,
+    pub doc: String,
+    ///(名称,说明)
+    pub tags: Vec<(String, String)>,
+    pub vars: Vec<(String, String)>,
+}
+
+///导出的一个请求,summary是树上的标题
+pub struct SpecOperation<'a> {
+    pub summary: String,
+    pub tag: Option<String>,
+    pub req: &'a RequestData,
+    pub resp: &'a ResponseData,
+}
+
+///集合导出成OpenAPI 3,相同路径和方法只取第一个请求,后面重复的以`方法 路径(标题)`返回
+pub fn export_openapi(info: &SpecInfo, ops: &[SpecOperation]) -> (Value, Vec<String>) {
+    let mut servers: Vec<String> = Vec::new();
+    let mut paths = Map::new();
+    let mut skipped = Vec::new();
+    for op in ops {
+        let (prefix, path) = split_url(&op.req.url);
+        let server = server_url(&prefix, &info.vars);
+        if !server.is_empty() && !servers.contains(&server) {
+            servers.push(server);
+        }
+        let method = op.req.method.to_string().to_lowercase();
+        let item = paths
+            .entry(path.clone())
+            .or_insert_with(|| Value::Object(Map::new()));
+        if item.get(&method).is_some() {
+            skipped.push(format!("{} {}({})", op.req.method, path, op.summary));
+            continue;
+        }
+        item[&method] = export_operation(op);
+    }
+    let mut doc = serde_json::json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": info.title,
+            "version": "1.0.0",
+        },
+        "paths": paths,
+    });
+    let description = strip_heading(&info.doc, &info.title);
+    if !description.is_empty() {
+        doc["info"]["description"] = description.into();
+    }
+    if !servers.is_empty() {
+        doc["servers"] = servers
+            .into_iter()
+            .map(|url| serde_json::json!({ "url": url }))
+            .collect();
+    }
+    let used: Vec<&str> = ops.iter().filter_map(|op| op.tag.as_deref()).collect();
+    let tags: Vec<Value> = info
+        .tags
+        .iter()
+        .filter(|(name, _)| used.contains(&name.as_str()))
+        .map(|(name, doc)| {
+            let mut tag = serde_json::json!({ "name": name });
+            let description = strip_heading(doc, name);
+            if !description.is_empty() {
+                tag["description"] = description.into();
+            }
+            tag
+        })
+        .collect();
+    if !tags.is_empty() {
+        doc["tags"] = tags.into();
+    }
+    (doc, skipped)
+}
+
+///导入时文档写成`# 标题\n\n说明`,导出时去掉标题
+fn strip_heading(doc: &str, title: &str) -> String {
+    let doc = doc.trim();
+    let heading = format!("# {}", title);
+    doc.strip_prefix(&heading)
+        .map(str::trim_start)
+        .unwrap_or(doc)
+        .to_owned()
+}
+
+///拆成服务器部分和路径,`:id`转成`{id}`
+fn split_url(url: &str) -> (String, String) {
+    let url = url.split('?').next().unwrap_or_default();
+    let split = if url.starts_with("${") {
+        url.find('}').map(|end| end + 1)
+    } else {
+        url.find("://")
+            .map(|p| url[p + 3..].find('/').map_or(url.len(), |s| p + 3 + s))
+    };
+    let (prefix, path) = url.split_at(split.unwrap_or(0));
+    let path: Vec<String> = path
+        .trim_start_matches('/')
+        .split('/')
+        .map(|segment| match segment.strip_prefix(':') {
+            Some(name) if !name.is_empty() => format!("{{{}}}", name),
+            _ => segment.to_owned(),
+        })
+        .collect();
+    (prefix.to_owned(), format!("/{}", path.join("/")))
+}
+
+///`${baseUrl}`用集合变量的值替换,找不到时写成服务器变量
+fn server_url(prefix: &str, vars: &[(String, String)]) -> String {
+    match prefix.strip_prefix("${").and_then(|p| p.strip_suffix('}')) {
+        Some(name) => vars
+            .iter()
+            .find(|(key, _)| key == name.trim())
+            .map(|(_, value)| value.trim_end_matches('/').to_owned())
+            .unwrap_or_else(|| format!("{{{}}}", name.trim())),
+        None => prefix.to_owned(),
+    }
+}
+
+fn export_operation(op: &SpecOperation) -> Value {
+    let req = op.req;
+    let mut operation = serde_json::json!({ "summary": op.summary });
+    if let Some(tag) = &op.tag {
+        operation["tags"] = serde_json::json!([tag]);
+    }
+    let description = strip_heading(&req.remark, &op.summary);
+    if !description.is_empty() && description != "备注" {
+        operation["description"] = description.into();
+    }
+
+    let mut params = Vec::new();
+    let (_, path) = split_url(&req.url);
+    for name in path_param_names(&path) {
+        let example = req
+            .path_params
+            .iter()
+            .find(|p| p.key == name)
+            .map(|p| p.value.as_str())
+            .unwrap_or_default();
+        params.push(export_param(&name, "path", true, example));
+    }
+    for param in req.params.iter() {
+        params.push(export_param(
+            &param.key,
+            "query",
+            param.selected,
+            &param.value,
+        ));
+    }
+    //这几个头在OpenAPI里由requestBody和security描述
+    for header in req.headers.iter().filter(|h| h.selected) {
+        let name = header.key.to_lowercase();
+        if !matches!(name.as_str(), "content-type" | "accept" | "authorization") {
+            params.push(export_param(&header.key, "header", true, &header.value));
+        }
+    }
+    if !params.is_empty() {
+        operation["parameters"] = params.into();
+    }
+    if let Some(body) = export_body(req) {
+        operation["requestBody"] = body;
+    }
+    operation["responses"] = export_responses(op.resp);
+    operation
+}
+
+fn export_param(name: &str, location: &str, required: bool, example: &str) -> Value {
+    let mut param = serde_json::json!({
+        "name": name,
+        "in": location,
+        "required": required,
+        "schema": { "type": "string" },
+    });
+    if !example.is_empty() {
+        param["example"] = example.into();
+    }
+    param
+}
+
+fn export_body(req: &RequestData) -> Option<Value> {
+    let (mime, media) = match req.body_type {
+        BodyType::None => return None,
+        BodyType::Json | BodyType::Json5 => {
+            if req.body.trim().is_empty() {
+                return None;
+            }
+            let media = match json5::from_str::<Value>(&req.body) {
+                Ok(example) => serde_json::json!({
+                    "schema": infer_schema(&example),
+                    "example": example,
+                }),
+                //带模板的请求体不是合法JSON,只保留原文
+                Err(_) => serde_json::json!({
+                    "schema": { "type": "object" },
+                    "example": req.body,
+                }),
+            };
+            ("application/json", media)
+        }
+        BodyType::FormUrlencoded => (
+            "application/x-www-form-urlencoded",
+            form_media(req.form.iter().map(|f| (&f.key, &f.value, false))),
+        ),
+        BodyType::Multipart => (
+            "multipart/form-data",
+            form_media(req.multipart.iter().map(|m| (&m.key, &m.value, m.is_file))),
+        ),
+        BodyType::Binary => (
+            "application/octet-stream",
+            serde_json::json!({ "schema": { "type": "string", "format": "binary" } }),
+        ),
+        BodyType::Raw => (
+            "text/plain",
+            serde_json::json!({ "schema": { "type": "string" }, "example": req.body }),
+        ),
+        BodyType::GraphQL => (
+            "application/json",
+            serde_json::json!({ "schema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "example": req.graphql.query },
+                    "variables": { "type": "object" },
+                },
+            }}),
+        ),
+    };
+    let mut content = Map::new();
+    content.insert(mime.to_owned(), media);
+    Some(serde_json::json!({ "content": content }))
+}
+
+fn form_media<'a>(fields: impl Iterator<Item = (&'a String, &'a String, bool)>) -> Value {
+    let mut properties = Map::new();
+    for (key, value, is_file) in fields {
+        let schema = if is_file {
+            serde_json::json!({ "type": "string", "format": "binary" })
+        } else {
+            serde_json::json!({ "type": "string", "example": value })
+        };
+        properties.insert(key.clone(), schema);
+    }
+    serde_json::json!({ "schema": { "type": "object", "properties": properties } })
+}
+
+///没有发送过的请求只写一个默认响应
+fn export_responses(resp: &ResponseData) -> Value {
+    let status = resp.code.split(' ').next().unwrap_or_default();
+    if status.len() != 3 || !status.chars().all(|c| c.is_ascii_digit()) {
+        return serde_json::json!({ "default": { "description": "" } });
+    }
+    let reason = resp.code[3..].trim();
+    let mut response = serde_json::json!({ "description": reason });
+    let mime = resp
+        .headers
+        .iter()
+        .find(|h| h.key.eq_ignore_ascii_case("content-type"))
+        .map(|h| {
+            h.value
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_owned()
+        })
+        .unwrap_or_default();
+    if !resp.body.is_empty() {
+        let media = match serde_json::from_str::<Value>(&resp.body) {
+            Ok(example) if mime.is_empty() || mime.contains("json") => {
+                serde_json::json!({ "schema": infer_schema(&example), "example": example })
+            }
+            _ => serde_json::json!({ "schema": { "type": "string" } }),
+        };
+        let mime = if mime.is_empty() {
+            "application/json".to_owned()
+        } else {
+            mime
+        };
+        let mut content = Map::new();
+        content.insert(mime, media);
+        response["content"] = content.into();
+    }
+    let mut responses = Map::new();
+    responses.insert(status.to_owned(), response);
+    responses.into()
+}
+
+///从示例值推断schema,数组按第一个元素推断
+fn infer_schema(value: &Value) -> Value {
+    match value {
+        Value::Null => serde_json::json!({ "nullable": true }),
+        Value::Bool(_) => serde_json::json!({ "type": "boolean" }),
+        Value::Number(n) if n.is_f64() => serde_json::json!({ "type": "number" }),
+        Value::Number(_) => serde_json::json!({ "type": "integer" }),
+        Value::String(_) => serde_json::json!({ "type": "string" }),
+        Value::Array(items) => serde_json::json!({
+            "type": "array",
+            "items": items.first().map(infer_schema).unwrap_or_else(|| serde_json::json!({})),
+        }),
+        Value::Object(map) => {
+            let properties: Map<String, Value> = map
+                .iter()
+                .map(|(key, value)| (key.clone(), infer_schema(value)))
+                .collect();
+            serde_json::json!({ "type": "object", "properties": properties })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi3() {
+        let doc: Value = serde_json::json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Pets", "description": "宠物接口"},
+            "servers": [{"url": "https://{env}.example.com/v1/", "variables": {"env": {"default": "api"}}}],
+            "tags": [{"name": "pet", "description": "宠物"}],
+            "paths": {
+                "/pets/{petId}": {
+                    "parameters": [{"$ref": "#/components/parameters/PetId"}],
+                    "put": {
+                        "tags": ["pet"],
+                        "summary": "修改宠物",
+                        "description": "按id修改",
+                        "parameters": [
+                            {"name": "dry", "in": "query", "schema": {"type": "boolean"}},
+                            {"name": "X-Trace", "in": "header", "required": true, "schema": {"type": "string", "example": "abc"}},
+                            {"name": "X-Skip", "in": "header", "schema": {"type": "string"}}
+                        ],
+                        "requestBody": {"content": {"application/json": {"schema": {"$ref": "#/components/schemas/Pet"}}}}
+                    }
+                },
+                "/health": {"get": {}}
+            },
+            "components": {
+                "parameters": {"PetId": {"name": "petId", "in": "path", "required": true, "schema": {"type": "integer", "example": 7}}},
+                "schemas": {"Pet": {"type": "object", "properties": {
+                    "name": {"type": "string"},
+                    "tags": {"type": "array", "items": {"type": "string", "enum": ["cat", "dog"]}},
+                    "parent": {"$ref": "#/components/schemas/Pet"}
+                }}}
+            }
+        });
+        let result = import_openapi(&doc).unwrap();
+        let ImportNode::Collection {
+            key,
+            script,
+            children,
+            ..
+        } = result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        assert_eq!(key, "openapi:Pets");
+        assert_eq!(script, "let baseUrl = \"https://api.example.com/v1\";\n");
+        assert_eq!(children.len(), 2);
+        let ImportNode::Collection {
+            key,
+            doc,
+            children: ops,
+            ..
+        } = &children[0]
+        else {
+            panic!("tag应该是集合");
+        };
+        assert_eq!((key.as_str(), doc.as_str()), ("tag:pet", "宠物"));
+        let ImportNode::Request { key, name, req, .. } = &ops[0] else {
+            panic!("操作应该是请求");
+        };
+        assert_eq!(key, "op:PUT /pets/{petId}");
+        assert_eq!(name, "修改宠物");
+        assert_eq!(req.remark, "# 修改宠物\n\n按id修改");
+        assert_eq!(req.url, "${baseUrl}/pets/{petId}");
+        assert_eq!(req.path_params, vec![SelectKeyValueItem::new("petId", "7")]);
+        assert_eq!(req.params.len(), 1);
+        assert!(!req.params[0].selected);
+        assert_eq!(req.headers, vec![SelectKeyValueItem::new("X-Trace", "abc")]);
+        assert_eq!(req.body_type, BodyType::Json);
+        let body: Value = serde_json::from_str(&req.body).unwrap();
+        assert_eq!(body["name"], "string");
+        assert_eq!(body["tags"][0], "cat");
+        assert!(body["parent"]["parent"].is_object());
+        let ImportNode::Request { key, name, .. } = &children[1] else {
+            panic!("没有tag的操作放在根下面");
+        };
+        assert_eq!(
+            (key.as_str(), name.as_str()),
+            ("op:GET /health", "GET /health")
+        );
+    }
+
+    #[test]
+    fn test_swagger2() {
+        let doc: Value = serde_json::json!({
+            "swagger": "2.0",
+            "info": {"title": "Files"},
+            "host": "files.example.com",
+            "basePath": "/api",
+            "schemes": ["http"],
+            "paths": {"/upload": {"post": {
+                "operationId": "upload",
+                "tags": ["file"],
+                "parameters": [
+                    {"name": "file", "in": "formData", "type": "file", "required": true},
+                    {"name": "note", "in": "formData", "type": "string", "default": "hi"},
+                    {"name": "page", "in": "query", "type": "integer", "required": true}
+                ]
+            }}}
+        });
+        let result = import_openapi(&doc).unwrap();
+        let ImportNode::Collection {
+            script, children, ..
+        } = result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        assert_eq!(script, "let baseUrl = \"http://files.example.com/api\";\n");
+        let ImportNode::Collection { children: ops, .. } = &children[0] else {
+            panic!("tag应该是集合");
+        };
+        let ImportNode::Request { name, req, .. } = &ops[0] else {
+            panic!("操作应该是请求");
+        };
+        assert_eq!(name, "upload");
+        assert_eq!(req.url, "${baseUrl}/upload?page=0");
+        assert_eq!(req.body_type, BodyType::Multipart);
+        let parts: Vec<_> = req
+            .multipart
+            .iter()
+            .map(|p| (p.key.as_str(), p.value.as_str(), p.is_file))
+            .collect();
+        assert_eq!(parts, vec![("file", "", true), ("note", "hi", false)]);
+    }
+
+    #[test]
+    fn test_export_openapi() {
+        let req = RequestData {
+            remark: "# 查询宠物\n\n按id查询".to_owned(),
+            url: "${baseUrl}/pets/:id?verbose=1".to_owned(),
+            params: vec![SelectKeyValueItem::new("verbose", "1")],
+            path_params: vec![SelectKeyValueItem::new("id", "7")],
+            headers: vec![
+                SelectKeyValueItem::new("X-Trace", "abc"),
+                SelectKeyValueItem::new("Content-Type", "application/json"),
+            ],
+            body: "{name: 'tom', age: 3, tags: ['a']}".to_owned(),
+            body_type: BodyType::Json5,
+            ..Default::default()
+        };
+        let resp = ResponseData {
+            code: "200 OK".to_owned(),
+            headers: vec![SelectKeyValueItem::new(
+                "content-type",
+                "application/json; charset=utf-8",
+            )],
+            body: r#"{"id":7,"score":1.5,"owner":null}"#.to_owned(),
+            ..Default::default()
+        };
+        let info = SpecInfo {
+            title: "Pets".to_owned(),
+            doc: "# Pets\n\n宠物接口".to_owned(),
+            tags: vec![
+                ("pet".to_owned(), "宠物".to_owned()),
+                ("user".to_owned(), String::new()),
+            ],
+            vars: vec![("baseUrl".to_owned(), "https://api.example.com/".to_owned())],
+        };
+        let ops = [
+            SpecOperation {
+                summary: "查询宠物".to_owned(),
+                tag: Some("pet".to_owned()),
+                req: &req,
+                resp: &resp,
+            },
+            SpecOperation {
+                summary: "重复".to_owned(),
+                tag: None,
+                req: &req,
+                resp: &resp,
+            },
+        ];
+        let (doc, skipped) = export_openapi(&info, &ops);
+        assert_eq!(skipped, vec!["GET /pets/{id}(重复)"]);
+        assert_eq!(doc["info"]["description"], "宠物接口");
+        assert_eq!(doc["servers"][0]["url"], "https://api.example.com");
+        assert_eq!(doc["tags"].as_array().unwrap().len(), 1);
+        let op = &doc["paths"]["/pets/{id}"]["put"];
+        assert!(op.is_null());
+        let op = &doc["paths"]["/pets/{id}"]["get"];
+        assert_eq!(op["summary"], "查询宠物");
+        assert_eq!(op["description"], "按id查询");
+        let params = op["parameters"].as_array().unwrap();
+        assert_eq!(params.len(), 3);
+        assert_eq!(params[0]["in"], "path");
+        assert_eq!(params[0]["example"], "7");
+        assert_eq!(params[2]["name"], "X-Trace");
+        let body = &op["requestBody"]["content"]["application/json"];
+        assert_eq!(body["schema"]["properties"]["age"]["type"], "integer");
+        assert_eq!(
+            body["schema"]["properties"]["tags"]["items"]["type"],
+            "string"
+        );
+        let response = &op["responses"]["200"];
+        assert_eq!(response["description"], "OK");
+        let schema = &response["content"]["application/json"]["schema"];
+        assert_eq!(schema["properties"]["score"]["type"], "number");
+        assert_eq!(schema["properties"]["owner"]["nullable"], true);
+    }
+}
@@ -0,0 +1,415 @@
+use serde_json::Value;
+
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::request_data::{BodyType, GraphQLBody, MultipartItem, RequestData, ScriptData};
+use crate::utils::auth::{AuthData, AuthType};
+use crate::utils::import::{
+    convert_vars, join_script, script_stub, vars_script, ImportNode, ImportResult,
+};
+
+///Postman Collection v2.1,文件夹转成集合,请求转成ApiTester
+pub fn import_postman(doc: &Value) -> Result<ImportResult, String> {
+    let mut warnings = Vec::new();
+    let name = doc["info"]["name"].as_str().unwrap_or("Postman");
+    let root = import_folder(name, doc, &mut warnings);
+    Ok(ImportResult { root, warnings })
+}
+
+fn str_of(value: &Value) -> &str {
+    value.as_str().unwrap_or_default()
+}
+
+///描述可以是字符串,也可以是{content}
+fn description(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Object(_) => str_of(&value["content"]).to_owned(),
+        _ => String::new(),
+    }
+}
+
+fn import_folder(name: &str, folder: &Value, warnings: &mut Vec<String>) -> ImportNode {
+    let vars: Vec<(String, String)> = folder["variable"]
+        .as_array()
+        .map(|vars| {
+            vars.iter()
+                .filter(|v| v["disabled"] != true)
+                .map(|v| (str_of(&v["key"]).to_owned(), value_string(&v["value"])))
+                .collect()
+        })
+        .unwrap_or_default();
+    let (pre, test) = scripts(&folder["event"], name);
+    let script = join_script(&vars_script(&vars, warnings), &join_script(&pre, &test));
+    let children = folder["item"]
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .map(|item| {
+                    let item_name = str_of(&item["name"]);
+                    if item.get("item").is_some() {
+                        import_folder(item_name, item, warnings)
+                    } else {
+                        import_item(item_name, item, warnings)
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    ImportNode::Collection {
+        key: String::new(),
+        name: name.to_owned(),
+        doc: description(&folder["description"])
+            + description(&folder["info"]["description"]).as_str(),
+        script,
+        auth: import_auth(&folder["auth"], name, warnings),
+        children,
+    }
+}
+
+fn value_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+///返回(前置脚本,测试脚本)的注释
+fn scripts(events: &Value, name: &str) -> (String, String) {
+    let mut pre = String::new();
+    let mut test = String::new();
+    for event in events.as_array().into_iter().flatten() {
+        let exec = match &event["script"]["exec"] {
+            Value::Array(lines) => lines.iter().map(str_of).collect::<Vec<_>>().join("\n"),
+            other => str_of(other).to_owned(),
+        };
+        match str_of(&event["listen"]) {
+            "prerequest" => {
+                pre.push_str(&script_stub(&exec, &format!("Postman前置脚本({})", name)))
+            }
+            "test" => test.push_str(&script_stub(&exec, &format!("Postman测试脚本({})", name))),
+            _ => {}
+        }
+    }
+    (pre, test)
+}
+
+fn import_item(name: &str, item: &Value, warnings: &mut Vec<String>) -> ImportNode {
+    let request = &item["request"];
+    let mut req = RequestData {
+        remark: description(&request["description"]),
+        body_type: BodyType::None,
+        ..Default::default()
+    };
+    let method = match request {
+        //简写的请求只有url
+        Value::String(_) => "GET",
+        _ => request["method"].as_str().unwrap_or("GET"),
+    };
+    match method.to_uppercase().parse() {
+        Ok(m) => req.method = m,
+        Err(e) => warnings.push(format!("{}:{}", name, e)),
+    }
+    let url = match request {
+        Value::String(s) => s.clone(),
+        _ => url_raw(&request["url"]),
+    };
+    req.url = convert_vars(&url, warnings);
+    req.sync_params_from_url();
+    //:id这样的路径参数
+    for var in request["url"]["variable"].as_array().into_iter().flatten() {
+        let key = str_of(&var["key"]);
+        if let Some(param) = req.path_params.iter_mut().find(|p| p.key == key) {
+            param.value = convert_vars(&value_string(&var["value"]), warnings);
+        }
+    }
+    req.headers = key_values(&request["header"], warnings);
+    import_body(&mut req, &request["body"], name, warnings);
+    req.auth = import_auth(&request["auth"], name, warnings);
+
+    let (pre, after) = scripts(&item["event"], name);
+    ImportNode::Request {
+        key: String::new(),
+        name: name.to_owned(),
+        req,
+        script: ScriptData { pre, after },
+    }
+}
+
+fn url_raw(url: &Value) -> String {
+    match url {
+        Value::String(s) => s.clone(),
+        Value::Object(_) => {
+            if let Some(raw) = url["raw"].as_str() {
+                return raw.to_owned();
+            }
+            let join = |v: &Value, sep: &str| match v {
+                Value::Array(parts) => parts.iter().map(value_string).collect::<Vec<_>>().join(sep),
+                other => value_string(other),
+            };
+            let mut raw = String::new();
+            if let Some(protocol) = url["protocol"].as_str() {
+                raw.push_str(protocol);
+                raw.push_str("://");
+            }
+            raw.push_str(&join(&url["host"], "."));
+            if let Some(port) = url["port"].as_str() {
+                raw.push(':');
+                raw.push_str(port);
+            }
+            let path = join(&url["path"], "/");
+            if !path.is_empty() {
+                raw.push('/');
+                raw.push_str(&path);
+            }
+            let query: Vec<String> = url["query"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|q| q["disabled"] != true)
+                .map(|q| format!("{}={}", str_of(&q["key"]), value_string(&q["value"])))
+                .collect();
+            if !query.is_empty() {
+                raw.push('?');
+                raw.push_str(&query.join("&"));
+            }
+            raw
+        }
+        _ => String::new(),
+    }
+}
+
+fn key_values(items: &Value, warnings: &mut Vec<String>) -> Vec<SelectKeyValueItem> {
+    items
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|item| {
+            let mut kv = SelectKeyValueItem::new(
+                str_of(&item["key"]),
+                &convert_vars(&value_string(&item["value"]), warnings),
+            );
+            kv.selected = item["disabled"] != true;
+            kv
+        })
+        .collect()
+}
+
+fn import_body(req: &mut RequestData, body: &Value, name: &str, warnings: &mut Vec<String>) {
+    if body.is_null() || body["disabled"] == true {
+        return;
+    }
+    match str_of(&body["mode"]) {
+        "raw" => {
+            req.body = convert_vars(str_of(&body["raw"]), warnings);
+            let is_json = body["options"]["raw"]["language"] == "json";
+            req.body_type = if is_json {
+                BodyType::Json
+            } else {
+                BodyType::Raw
+            };
+        }
+        "urlencoded" => {
+            req.body_type = BodyType::FormUrlencoded;
+            req.form = key_values(&body["urlencoded"], warnings);
+        }
+        "formdata" => {
+            req.body_type = BodyType::Multipart;
+            req.multipart = body["formdata"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(|item| {
+                    let key = str_of(&item["key"]);
+                    let mut part = if item["type"] == "file" {
+                        MultipartItem::new(key, &file_src(&item["src"]), true)
+                    } else {
+                        MultipartItem::new(
+                            key,
+                            &convert_vars(&value_string(&item["value"]), warnings),
+                            false,
+                        )
+                    };
+                    part.selected = item["disabled"] != true;
+                    part
+                })
+                .collect();
+        }
+        "file" => {
+            req.body_type = BodyType::Binary;
+            req.binary = file_src(&body["file"]["src"]);
+        }
+        "graphql" => {
+            req.body_type = BodyType::GraphQL;
+            req.graphql = GraphQLBody {
+                query: convert_vars(str_of(&body["graphql"]["query"]), warnings),
+                variables: convert_vars(str_of(&body["graphql"]["variables"]), warnings),
+            };
+        }
+        "" => {}
+        mode => warnings.push(format!("{}:不支持的请求体类型{}", name, mode)),
+    }
+}
+
+///文件可以是路径或路径数组,只取第一个
+fn file_src(src: &Value) -> String {
+    match src {
+        Value::Array(items) => items.first().map(value_string).unwrap_or_default(),
+        other => value_string(other),
+    }
+}
+
+fn import_auth(auth: &Value, name: &str, warnings: &mut Vec<String>) -> AuthData {
+    let auth_type = str_of(&auth["type"]);
+    //v2.1里每种认证的参数是[{key,value}]
+    let param = |key: &str| -> String {
+        auth[auth_type]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|p| p["key"] == key)
+            .map(|p| value_string(&p["value"]))
+            .unwrap_or_default()
+    };
+    let mut data = AuthData::default();
+    match auth_type {
+        "" | "inherit" => return data,
+        "noauth" => data.auth_type = AuthType::None,
+        "basic" | "digest" => {
+            data.auth_type = if auth_type == "basic" {
+                AuthType::Basic
+            } else {
+                AuthType::Digest
+            };
+            data.username = param("username");
+            data.password = param("password");
+        }
+        "bearer" => {
+            data.auth_type = AuthType::Bearer;
+            data.token = param("token");
+        }
+        "apikey" => {
+            data.auth_type = AuthType::ApiKey;
+            data.api_key = param("key");
+            data.api_value = param("value");
+            data.api_key_in_query = param("in") == "query";
+        }
+        "oauth2" => {
+            match param("grant_type").as_str() {
+                "client_credentials" => data.auth_type = AuthType::OAuth2ClientCredentials,
+                "password_credentials" => data.auth_type = AuthType::OAuth2Password,
+                grant => {
+                    warnings.push(format!("{}:不支持的OAuth2授权方式{}", name, grant));
+                    return data;
+                }
+            }
+            data.token_url = param("accessTokenUrl");
+            data.client_id = param("clientId");
+            data.client_secret = param("clientSecret");
+            data.scope = param("scope");
+            data.username = param("username");
+            data.password = param("password");
+        }
+        other => {
+            warnings.push(format!("{}:不支持的认证方式{}", name, other));
+            return data;
+        }
+    }
+    for field in [
+        &mut data.username,
+        &mut data.password,
+        &mut data.token,
+        &mut data.api_key,
+        &mut data.api_value,
+        &mut data.token_url,
+        &mut data.client_id,
+        &mut data.client_secret,
+        &mut data.scope,
+    ] {
+        *field = convert_vars(field, warnings);
+    }
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::request_data::Method;
+    use crate::utils::import::import_collection;
+
+    const COLLECTION: &str = r#"{
+        "info": {"name": "Demo", "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json"},
+        "variable": [{"key": "baseUrl", "value": "https://api.example.com"}],
+        "auth": {"type": "bearer", "bearer": [{"key": "token", "value": "{{token}}"}]},
+        "item": [
+            {"name": "Users", "item": [
+                {"name": "Get user",
+                 "event": [{"listen": "test", "script": {"exec": ["pm.test('ok', () => {});"]}}],
+                 "request": {"method": "GET",
+                    "header": [{"key": "X-Trace", "value": "{{$guid}}"}, {"key": "X-Off", "value": "1", "disabled": true}],
+                    "url": {"raw": "{{baseUrl}}/users/:id?expand=true", "variable": [{"key": "id", "value": "42"}]}}},
+                {"name": "Upload",
+                 "request": {"method": "POST", "url": "{{baseUrl}}/upload",
+                    "auth": {"type": "hawk"},
+                    "body": {"mode": "formdata", "formdata": [
+                        {"key": "name", "value": "a", "type": "text"},
+                        {"key": "file", "src": ["/tmp/a.png"], "type": "file"}]}}}
+            ]},
+            {"name": "Create", "request": {"method": "POST", "url": "{{baseUrl}}/items",
+                "body": {"mode": "raw", "raw": "{\"name\": \"{{name}}\"}", "options": {"raw": {"language": "json"}}}}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_import_postman() {
+        let result = import_collection(COLLECTION).unwrap();
+        assert_eq!(result.root.request_count(), 3);
+        let ImportNode::Collection {
+            name,
+            script,
+            auth,
+            children,
+            ..
+        } = &result.root
+        else {
+            panic!("根节点应该是集合");
+        };
+        assert_eq!(name, "Demo");
+        assert_eq!(script, "let baseUrl = \"https://api.example.com\";\n");
+        assert_eq!(auth.auth_type, AuthType::Bearer);
+        assert_eq!(auth.token, "${token}");
+
+        let ImportNode::Collection {
+            children: users, ..
+        } = &children[0]
+        else {
+            panic!("Users应该是集合");
+        };
+        let ImportNode::Request { req, script, .. } = &users[0] else {
+            panic!("Get user应该是请求");
+        };
+        assert_eq!(req.url, "${baseUrl}/users/:id?expand=true");
+        assert_eq!(req.path_params[0].value, "42");
+        assert_eq!(req.params[0].key, "expand");
+        assert_eq!(req.headers[0].value, "${UUID()}");
+        assert!(!req.headers[1].selected);
+        assert!(req.auth.is_inherit());
+        assert!(script.after.starts_with("// Postman测试脚本(Get user)"));
+
+        let ImportNode::Request { req, .. } = &users[1] else {
+            panic!("Upload应该是请求");
+        };
+        assert_eq!(req.body_type, BodyType::Multipart);
+        assert!(req.multipart[1].is_file);
+        assert_eq!(req.multipart[1].value, "/tmp/a.png");
+        assert!(result.warnings.iter().any(|w| w.contains("hawk")));
+
+        let ImportNode::Request { req, .. } = &children[1] else {
+            panic!("Create应该是请求");
+        };
+        assert_eq!(req.method, Method::POST);
+        assert_eq!(req.body_type, BodyType::Json);
+        assert_eq!(req.body, "{\"name\": \"${name}\"}");
+    }
+}
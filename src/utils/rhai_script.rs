@@ -1,17 +1,91 @@
+use std::cell::Cell;
 use std::f64::consts::E;
+use std::time::{Duration, Instant};
 
 use once_cell::sync::Lazy;
 use rhai::export_module;
 use rhai::module_resolvers::FileModuleResolver;
 use rhai::module_resolvers::StaticModuleResolver;
 use rhai::plugin::*;
-use rhai::Engine;
+use rhai::{Dynamic, Engine, EvalAltResult};
 // use rhai_rand::RandomPackage;
 // use rhai_sci::SciPackage;
 
 use crate::app::{TASK_CHANNEL, TOKIO_RT};
+use crate::utils::assertion::register_assertions;
+use crate::utils::script_data::register_data_fns;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::utils::script_http::register_http;
+
+pub const SCRIPT_ENGINE: Lazy<Engine> = Lazy::new(new_engine);
+
+///界面线程上一次执行脚本(包括集合脚本)的总时间,超时界面会卡住,只给几秒
+pub const SCRIPT_BUDGET: Duration = Duration::from_secs(5);
+
+///超时的脚本rhai只报Script terminated,换成超时提示
+pub fn script_error(e: &EvalAltResult) -> String {
+    match e {
+        EvalAltResult::ErrorTerminated(..) => format!(
+            "脚本执行超时(超过{}秒),检查脚本里的请求和循环",
+            SCRIPT_BUDGET.as_secs()
+        ),
+        other => other.to_string(),
+    }
+}
+
+thread_local! {
+    ///当前线程上执行脚本的截止时间和是否离线
+    static SCRIPT_LIMIT: Cell<Option<(Option<Instant>, bool)>> = const { Cell::new(None) };
+}
+
+///执行脚本期间有效,超过截止时间后脚本在下一条语句前结束,离线时脚本不发送请求
+pub struct ScriptLimit {
+    previous: Option<(Option<Instant>, bool)>,
+}
+
+impl ScriptLimit {
+    pub fn start(offline: bool) -> Self {
+        Self::with_budget(SCRIPT_BUDGET, offline)
+    }
+
+    pub fn with_budget(budget: Duration, offline: bool) -> Self {
+        Self::set(Some(Instant::now() + budget), offline)
+    }
+
+    ///不限时间也不发送请求,调试时脚本会停下来等操作
+    pub fn offline() -> Self {
+        Self::set(None, true)
+    }
+
+    fn set(deadline: Option<Instant>, offline: bool) -> Self {
+        let previous = SCRIPT_LIMIT.with(|limit| limit.replace(Some((deadline, offline))));
+        Self { previous }
+    }
+}
 
-pub const SCRIPT_ENGINE: Lazy<Engine> = Lazy::new(|| {
+impl Drop for ScriptLimit {
+    fn drop(&mut self) {
+        SCRIPT_LIMIT.with(|limit| limit.set(self.previous));
+    }
+}
+
+///脚本剩下的时间,没有限制时为None
+pub fn script_remaining() -> Option<Duration> {
+    SCRIPT_LIMIT
+        .with(|limit| limit.get())
+        .and_then(|(deadline, _)| deadline)
+        .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+}
+
+///复制curl、生成代码时执行的脚本不能发送请求
+pub fn script_offline() -> bool {
+    SCRIPT_LIMIT
+        .with(|limit| limit.get())
+        .map_or(false, |(_, offline)| offline)
+}
+
+///注册好所有模块的引擎,调试时在这个基础上再挂调试器
+pub fn new_engine() -> Engine {
     let mut engine = Engine::new();
     // let mut smr = StaticModuleResolver::new();
 
@@ -49,6 +123,10 @@ pub const SCRIPT_ENGINE: Lazy<Engine> = Lazy::new(|| {
     engine.register_static_module("crypto", crypto.into());
 
     engine.register_fn("call_req", call_req);
+    register_assertions(&mut engine);
+    register_data_fns(&mut engine);
+    #[cfg(not(target_arch = "wasm32"))]
+    register_http(&mut engine);
     // engine.register_fn("log_info", log_info);
     // engine.register_fn("log_error", log_error);
     // engine.register_fn("log_debug", log_debug);
@@ -56,15 +134,23 @@ pub const SCRIPT_ENGINE: Lazy<Engine> = Lazy::new(|| {
     // engine.register_fn("log_trace", log_trace);
     // engine.set_module_resolver(smr);
     engine.set_max_call_levels(255);
+    engine.on_progress(|_| {
+        script_remaining()
+            .filter(|left| left.is_zero())
+            .map(|_| Dynamic::from("脚本执行超时"))
+    });
 
     engine
-});
+}
 
 // pub struct ScriptEngine {
 //     pub engine: Engine,
 // }
 
 fn call_req(id: u64) {
+    if script_offline() {
+        return;
+    }
     let task_sender = unsafe { TASK_CHANNEL.0.clone() };
     TOKIO_RT.spawn(async move {
         if (task_sender.send((id, 1, 1)).await).is_err() {
@@ -343,6 +429,25 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_script_limit() {
+        assert!(script_remaining().is_none());
+        {
+            let _limit = ScriptLimit::with_budget(Duration::ZERO, true);
+            assert!(script_offline());
+            let e = new_engine().run("loop { }").unwrap_err();
+            assert!(script_error(&e).starts_with("脚本执行超时"));
+        }
+        assert!(script_remaining().is_none());
+        assert!(!script_offline());
+        {
+            let _limit = ScriptLimit::offline();
+            assert!(script_offline());
+            assert!(script_remaining().is_none());
+        }
+        assert!(!script_offline());
+    }
+
     #[test]
     fn test_script() {
         let mut engine = Engine::new();
@@ -0,0 +1,167 @@
+use rhai::{Dynamic, Engine, EvalAltResult, Map, INT};
+
+use crate::request_data::{RequestData, ResponseData};
+
+///给_resp和_req用的辅助函数,如`_resp.header("content-type")`、`_resp.json()`
+pub fn register_data_fns(engine: &mut Engine) {
+    engine.register_fn("header", header);
+    engine.register_fn("json", json_of_map);
+    engine.register_fn("json", json_of_str);
+}
+
+///同名的头用`, `连接,名字都转成小写
+fn headers_map<'a>(items: impl Iterator<Item = (&'a str, &'a str)>) -> Map {
+    let mut headers = Map::new();
+    for (key, value) in items {
+        let key = key.to_lowercase();
+        let value = match headers.get(key.as_str()) {
+            Some(old) => format!("{}, {}", old, value),
+            None => value.to_owned(),
+        };
+        headers.insert(key.into(), value.into());
+    }
+    headers
+}
+
+///能解析成JSON的转成rhai的map或数组,否则保留字符串
+fn parse_body(body: &str) -> Dynamic {
+    serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|json| rhai::serde::to_dynamic(json).ok())
+        .unwrap_or_else(|| body.into())
+}
+
+///状态码、响应头和响应体,JSON响应体已经解析好,原文在text里
+pub fn response_map(resp: &ResponseData) -> Map {
+    let headers = headers_map(
+        resp.headers
+            .iter()
+            .map(|item| (item.key.as_str(), item.value.as_str())),
+    );
+    let status: INT = resp
+        .code
+        .split_whitespace()
+        .next()
+        .and_then(|code| code.parse().ok())
+        .unwrap_or_default();
+    let mut map = Map::new();
+    map.insert("status".into(), status.into());
+    map.insert("code".into(), resp.code.clone().into());
+    map.insert("headers".into(), headers.into());
+    map.insert("body".into(), parse_body(&resp.body));
+    map.insert("text".into(), resp.body.clone().into());
+    map.insert("time".into(), (resp.time as INT).into());
+    map.insert("size".into(), (resp.size as INT).into());
+    map
+}
+
+///发出的请求,只包含勾选的请求头和参数,模板没有渲染
+pub fn request_map(req: &RequestData) -> Map {
+    let headers = headers_map(
+        req.headers
+            .iter()
+            .filter(|item| item.selected)
+            .map(|item| (item.key.as_str(), item.value.as_str())),
+    );
+    let mut params = Map::new();
+    for item in req.params.iter().filter(|item| item.selected) {
+        params.insert(item.key.as_str().into(), item.value.clone().into());
+    }
+    let mut map = Map::new();
+    map.insert("method".into(), req.method.to_string().into());
+    map.insert("url".into(), req.url.clone().into());
+    map.insert("headers".into(), headers.into());
+    map.insert("params".into(), params.into());
+    map.insert("body_type".into(), req.body_type.label().into());
+    map.insert("body".into(), parse_body(&req.body));
+    map.insert("text".into(), req.body.clone().into());
+    map
+}
+
+///不区分大小写,没有时返回()
+fn header(data: &mut Map, name: &str) -> Dynamic {
+    data.get("headers")
+        .and_then(|headers| {
+            let headers = headers.read_lock::<Map>()?;
+            let found = headers
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                .map(|(_, value)| value.clone());
+            found
+        })
+        .unwrap_or(Dynamic::UNIT)
+}
+
+///body已经解析过就直接返回,否则解析text
+fn json_of_map(data: &mut Map) -> Result<Dynamic, Box<EvalAltResult>> {
+    if let Some(body) = data
+        .get("body")
+        .filter(|body| body.is_map() || body.is_array())
+    {
+        return Ok(body.clone());
+    }
+    let text = data
+        .get("text")
+        .map(|text| text.to_string())
+        .unwrap_or_default();
+    json_of_str(&text)
+}
+
+fn json_of_str(text: &str) -> Result<Dynamic, Box<EvalAltResult>> {
+    let json: serde_json::Value =
+        serde_json::from_str(text).map_err(|e| format!("不是JSON:{}", e))?;
+    rhai::serde::to_dynamic(json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::header_ui::SelectKeyValueItem;
+
+    #[test]
+    fn test_response_map() {
+        let resp = ResponseData {
+            code: "201 Created".to_owned(),
+            headers: vec![
+                SelectKeyValueItem::new("Set-Cookie", "a=1"),
+                SelectKeyValueItem::new("set-cookie", "b=2"),
+            ],
+            body: r#"{"token":"t"}"#.to_owned(),
+            time: 12,
+            ..Default::default()
+        };
+        let map = response_map(&resp);
+        assert_eq!(map["status"].as_int().unwrap(), 201);
+        let headers = map["headers"].read_lock::<Map>().unwrap().clone();
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers["set-cookie"].to_string(), "a=1, b=2");
+        let body = map["body"].read_lock::<Map>().unwrap().clone();
+        assert_eq!(body["token"].to_string(), "t");
+        assert_eq!(map["text"].to_string(), resp.body);
+    }
+
+    #[test]
+    fn test_script_helpers() {
+        let mut engine = Engine::new();
+        register_data_fns(&mut engine);
+        let mut scope = rhai::Scope::new();
+        scope.push(
+            "_resp",
+            response_map(&ResponseData {
+                code: "200 OK".to_owned(),
+                headers: vec![SelectKeyValueItem::new("Content-Type", "application/json")],
+                body: r#"{"data":{"items":[1,2]}}"#.to_owned(),
+                ..Default::default()
+            }),
+        );
+        let script = r#"
+let n = _resp.json().data.items.len();
+let t = _resp.header("content-type");
+let m = _resp.header("x-missing");
+let s = `{"a":1}`.json().a;
+`${_resp.status}:${n}:${t}:${type_of(m)}:${s}`
+"#;
+        let result: String = engine.eval_with_scope(&mut scope, script).unwrap();
+        assert_eq!(result, "200:2:application/json:():1");
+    }
+}
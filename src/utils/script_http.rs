@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, Request};
+use rhai::plugin::*;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, INT};
+
+use crate::app::TOKIO_RT;
+use crate::request_data::{covert_to_ui, RequestData, ResponseData};
+use crate::utils::auth::{self, AuthData};
+use crate::utils::client::{client_for, ResolvedClientSettings};
+use crate::utils::rhai_script::{script_offline, script_remaining};
+use crate::utils::script_data::response_map;
+
+///脚本里没有指定超时时用的
+const DEFAULT_TIMEOUT: u64 = 5_000;
+///脚本在界面线程上同步等待,超时不能太长
+const MAX_TIMEOUT: u64 = 5_000;
+
+///send(id)用的请求快照,认证和客户端设置已经按树合并好
+#[derive(Debug, Clone)]
+pub struct SavedRequest {
+    pub req: RequestData,
+    pub settings: ResolvedClientSettings,
+}
+
+static SAVED_REQUESTS: Lazy<RwLock<HashMap<u64, SavedRequest>>> =
+    Lazy::new(|| RwLock::new(HashMap::new()));
+///http模块用全局的客户端设置
+static GLOBAL_SETTINGS: Lazy<RwLock<ResolvedClientSettings>> =
+    Lazy::new(|| RwLock::new(ResolvedClientSettings::default()));
+
+///执行脚本前由ApiContext更新
+pub fn publish(global: ResolvedClientSettings, requests: HashMap<u64, SavedRequest>) {
+    if let Ok(mut settings) = GLOBAL_SETTINGS.write() {
+        *settings = global;
+    }
+    if let Ok(mut saved) = SAVED_REQUESTS.write() {
+        *saved = requests;
+    }
+}
+
+pub fn register_http(engine: &mut Engine) {
+    let http = exported_module!(shttp);
+    engine.register_static_module("http", http.into());
+    engine.register_fn("send", send);
+}
+
+///单个请求不超过MAX_TIMEOUT,也不超过脚本剩下的时间
+fn clamp_timeout(ms: u64) -> Result<u64, String> {
+    let ms = match ms {
+        0 => MAX_TIMEOUT,
+        ms => ms.min(MAX_TIMEOUT),
+    };
+    match script_remaining() {
+        Some(left) if left.is_zero() => Err("脚本执行超时".to_owned()),
+        Some(left) => Ok(ms.min(left.as_millis() as u64).max(1)),
+        None => Ok(ms),
+    }
+}
+
+///离线时不发请求,返回空的响应
+fn offline_response() -> Map {
+    log::info!("复制curl或生成代码时脚本不发送请求");
+    response_map(&ResponseData::default())
+}
+
+///在TOKIO_RT上执行,当前线程等结果,超时返回错误
+fn block_on<F>(timeout: u64, fut: F) -> Result<ResponseData, String>
+where
+    F: Future<Output = Result<ResponseData, String>> + Send + 'static,
+{
+    let (tx, rx) = std::sync::mpsc::channel();
+    let limit = Duration::from_millis(timeout);
+    TOKIO_RT.spawn(async move {
+        let result = tokio::time::timeout(limit, fut)
+            .await
+            .unwrap_or_else(|_| Err(format!("请求超时({}毫秒)", timeout)));
+        let _ = tx.send(result);
+    });
+    rx.recv_timeout(limit + Duration::from_millis(500))
+        .unwrap_or_else(|_| Err(format!("请求超时({}毫秒)", timeout)))
+}
+
+fn execute(
+    client: Client,
+    req: Request,
+    auth: AuthData,
+    timeout: u64,
+) -> Result<ResponseData, String> {
+    block_on(timeout, async move {
+        let start = Instant::now();
+        let rep = auth::execute(&client, req, &auth).await?;
+        let mut resp = covert_to_ui(rep).await;
+        resp.time = start.elapsed().as_millis() as i64;
+        Ok(resp)
+    })
+}
+
+///字符串原样发送,其他的转成JSON,没有Content-Type时补上
+fn build_request(
+    client: &Client,
+    method: &str,
+    url: &str,
+    headers: &Map,
+    body: Option<&Dynamic>,
+) -> Result<Request, String> {
+    let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes())
+        .map_err(|e| format!("请求方法错误:{}", e))?;
+    let mut builder = client.request(method, url);
+    let mut has_type = false;
+    for (key, value) in headers {
+        has_type |= key.eq_ignore_ascii_case(CONTENT_TYPE.as_str());
+        builder = builder.header(key.as_str(), value.to_string());
+    }
+    match body {
+        Some(body) if body.is_unit() => {}
+        Some(body) if body.is_string() => builder = builder.body(body.to_string()),
+        Some(body) => {
+            let json: serde_json::Value =
+                rhai::serde::from_dynamic(body).map_err(|e| e.to_string())?;
+            if !has_type {
+                builder = builder.header(CONTENT_TYPE, "application/json");
+            }
+            builder = builder.body(json.to_string());
+        }
+        None => {}
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn request_with(
+    method: &str,
+    url: &str,
+    headers: &Map,
+    body: Option<&Dynamic>,
+    timeout: u64,
+) -> Result<Map, Box<EvalAltResult>> {
+    if script_offline() {
+        return Ok(offline_response());
+    }
+    let settings = GLOBAL_SETTINGS
+        .read()
+        .map(|s| s.clone())
+        .unwrap_or_default();
+    let client = client_for(&settings)?;
+    let req = build_request(&client, method, url, headers, body)?;
+    let resp = execute(client, req, AuthData::default(), clamp_timeout(timeout)?)?;
+    Ok(response_map(&resp))
+}
+
+///执行树里保存的请求,用它的认证和客户端设置
+fn send(id: INT) -> Result<Map, Box<EvalAltResult>> {
+    if script_offline() {
+        return Ok(offline_response());
+    }
+    let saved = SAVED_REQUESTS
+        .read()
+        .ok()
+        .and_then(|saved| saved.get(&(id as u64)).cloned())
+        .ok_or(format!("没有{}号请求", id))?;
+    let client = client_for(&saved.settings)?;
+    let auth = saved.req.auth.clone();
+    let req: Request = saved.req.try_into()?;
+    let timeout = clamp_timeout(saved.settings.read_timeout)?;
+    let resp = execute(client, req, auth, timeout)?;
+    Ok(response_map(&resp))
+}
+
+#[export_module]
+mod shttp {
+    use rhai::{Dynamic, EvalAltResult, Map, INT};
+
+    #[rhai_fn(return_raw)]
+    pub fn get(url: &str) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("GET", url, &Map::new(), None, super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(name = "get", return_raw)]
+    pub fn get_with_headers(url: &str, headers: Map) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("GET", url, &headers, None, super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn post(url: &str, body: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with(
+            "POST",
+            url,
+            &Map::new(),
+            Some(&body),
+            super::DEFAULT_TIMEOUT,
+        )
+    }
+
+    #[rhai_fn(name = "post", return_raw)]
+    pub fn post_with_headers(
+        url: &str,
+        body: Dynamic,
+        headers: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("POST", url, &headers, Some(&body), super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn put(url: &str, body: Dynamic) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("PUT", url, &Map::new(), Some(&body), super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(name = "put", return_raw)]
+    pub fn put_with_headers(
+        url: &str,
+        body: Dynamic,
+        headers: Map,
+    ) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("PUT", url, &headers, Some(&body), super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(return_raw)]
+    pub fn delete(url: &str) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("DELETE", url, &Map::new(), None, super::DEFAULT_TIMEOUT)
+    }
+
+    #[rhai_fn(name = "delete", return_raw)]
+    pub fn delete_with_headers(url: &str, headers: Map) -> Result<Map, Box<EvalAltResult>> {
+        super::request_with("DELETE", url, &headers, None, super::DEFAULT_TIMEOUT)
+    }
+
+    ///#{method: "POST", url: "...", headers: #{}, body: #{}, timeout: 5000}
+    #[rhai_fn(return_raw)]
+    pub fn request(options: Map) -> Result<Map, Box<EvalAltResult>> {
+        let text = |key: &str| options.get(key).map(|v| v.to_string()).unwrap_or_default();
+        let url = text("url");
+        if url.is_empty() {
+            return Err("缺少url".into());
+        }
+        let method = match text("method") {
+            method if method.is_empty() => "GET".to_owned(),
+            method => method,
+        };
+        let headers = options
+            .get("headers")
+            .and_then(|h| h.read_lock::<Map>().map(|h| h.clone()))
+            .unwrap_or_default();
+        let timeout = options
+            .get("timeout")
+            .and_then(|t| t.as_int().ok())
+            .map(|t: INT| t.max(0) as u64)
+            .unwrap_or(super::DEFAULT_TIMEOUT);
+        super::request_with(&method, &url, &headers, options.get("body"), timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_build_request() {
+        let client = Client::new();
+        let mut headers = Map::new();
+        headers.insert("X-Token".into(), "abc".into());
+        let mut body = Map::new();
+        body.insert("id".into(), (1 as INT).into());
+        let req = build_request(
+            &client,
+            "post",
+            "http://localhost/a",
+            &headers,
+            Some(&body.into()),
+        )
+        .unwrap();
+        assert_eq!(req.method(), reqwest::Method::POST);
+        assert_eq!(req.headers()["x-token"], "abc");
+        assert_eq!(req.headers()[CONTENT_TYPE], "application/json");
+        let sent = req.body().and_then(|b| b.as_bytes()).unwrap();
+        assert_eq!(sent, br#"{"id":1}"#);
+        assert!(build_request(&client, "bad method", "http://localhost", &headers, None).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::RwLock;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use crypto::hmac::Hmac;
+use crypto::pbkdf2::pbkdf2;
+use crypto::sha2::Sha256;
+use once_cell::sync::Lazy;
+
+use crate::utils::aes_tool::{aes_dec_cbc_bytes, aes_enc_cbc_bytes};
+
+const ITERATIONS: u32 = 100_000;
+///解锁时用来校验主密码
+const CHECK_TEXT: &str = "http-test-tool";
+pub const MASK: &str = "******";
+
+///解锁后的密钥只在内存里,不会保存
+static MASTER_KEY: Lazy<RwLock<Option<[u8; 32]>>> = Lazy::new(|| RwLock::new(None));
+
+///加密的变量,值只以密文保存
+#[derive(Debug, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct SecretVar {
+    pub selected: bool,
+    pub key: String,
+    ///iv和密文的base64,用`:`连接
+    pub cipher: String,
+}
+
+impl Default for SecretVar {
+    fn default() -> Self {
+        Self {
+            selected: true,
+            key: String::new(),
+            cipher: String::new(),
+        }
+    }
+}
+
+///工作区的主密码,只保存盐和校验用的密文
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct Vault {
+    pub salt: String,
+    pub check: String,
+}
+
+impl Vault {
+    pub fn is_initialized(&self) -> bool {
+        !self.check.is_empty()
+    }
+
+    ///设置主密码并解锁
+    pub fn init(&mut self, password: &str) -> Result<(), String> {
+        if password.is_empty() {
+            return Err("主密码不能为空".to_owned());
+        }
+        let salt: [u8; 16] = rand::random();
+        let key = derive_key(password, &salt);
+        self.salt = STANDARD.encode(salt);
+        self.check = encrypt(&key, CHECK_TEXT)?;
+        set_master_key(Some(key));
+        Ok(())
+    }
+
+    pub fn unlock(&self, password: &str) -> Result<(), String> {
+        let salt = STANDARD.decode(&self.salt).map_err(|e| e.to_string())?;
+        let key = derive_key(password, &salt);
+        match decrypt(&key, &self.check) {
+            Ok(text) if text == CHECK_TEXT => {
+                set_master_key(Some(key));
+                Ok(())
+            }
+            _ => Err("主密码错误".to_owned()),
+        }
+    }
+}
+
+fn set_master_key(key: Option<[u8; 32]>) {
+    if let Ok(mut master) = MASTER_KEY.write() {
+        *master = key;
+    }
+}
+
+pub fn lock() {
+    set_master_key(None);
+}
+
+pub fn is_unlocked() -> bool {
+    MASTER_KEY.read().is_ok_and(|key| key.is_some())
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> [u8; 32] {
+    let mut mac = Hmac::new(Sha256::new(), password.as_bytes());
+    let mut key = [0u8; 32];
+    pbkdf2(&mut mac, salt, ITERATIONS, &mut key);
+    key
+}
+
+fn encrypt(key: &[u8; 32], plain: &str) -> Result<String, String> {
+    let iv: [u8; 16] = rand::random();
+    let cipher = aes_enc_cbc_bytes(key, plain.as_bytes(), &iv)?;
+    Ok(format!("{}:{}", STANDARD.encode(iv), STANDARD.encode(cipher)))
+}
+
+fn decrypt(key: &[u8; 32], cipher: &str) -> Result<String, String> {
+    let (iv, data) = cipher.split_once(':').ok_or("密文格式错误")?;
+    let iv = STANDARD.decode(iv).map_err(|e| e.to_string())?;
+    let data = STANDARD.decode(data).map_err(|e| e.to_string())?;
+    let plain = aes_dec_cbc_bytes(key, &data, &iv)?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
+
+///用解锁的主密码加密,锁定时返回错误
+pub fn seal(plain: &str) -> Result<String, String> {
+    let master = MASTER_KEY.read().map_err(|e| e.to_string())?;
+    let key = master.as_ref().ok_or("请先解锁主密码")?;
+    encrypt(key, plain)
+}
+
+///锁定或者密文损坏时返回None
+pub fn open(cipher: &str) -> Option<String> {
+    let master = MASTER_KEY.read().ok()?;
+    decrypt(master.as_ref()?, cipher).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_round_trip() {
+        let key = derive_key("master", b"salt");
+        let cipher = encrypt(&key, "token-值").unwrap();
+        assert_ne!(encrypt(&key, "token-值").unwrap(), cipher);
+        assert_eq!(decrypt(&key, &cipher).unwrap(), "token-值");
+        let other = derive_key("wrong", b"salt");
+        assert_ne!(decrypt(&other, &cipher).ok().as_deref(), Some("token-值"));
+    }
+}
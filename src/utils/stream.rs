@@ -0,0 +1,242 @@
+use chrono::Local;
+
+use crate::request_data::StreamEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamKind {
+    ///text/event-stream
+    Sse,
+    ///每行一个JSON
+    Ndjson,
+}
+
+impl StreamKind {
+    pub fn detect(content_type: &str) -> Option<Self> {
+        let mime = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        match mime.as_str() {
+            "text/event-stream" => Some(StreamKind::Sse),
+            "application/x-ndjson" | "application/ndjson" | "application/jsonl"
+            | "application/x-jsonlines" => Some(StreamKind::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+///按行切分流式响应,块的边界可以落在任意字节上
+pub struct StreamParser {
+    kind: StreamKind,
+    buf: Vec<u8>,
+    ///SSE的id在事件之间保留
+    last_id: String,
+    event: String,
+    data: Vec<String>,
+    retry: Option<u64>,
+}
+
+impl StreamParser {
+    pub fn new(kind: StreamKind) -> Self {
+        Self {
+            kind,
+            buf: Vec::new(),
+            last_id: String::new(),
+            event: String::new(),
+            data: Vec::new(),
+            retry: None,
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<StreamEvent> {
+        self.buf.extend_from_slice(chunk);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line[..pos]);
+            let line = line.strip_suffix('\r').unwrap_or(&line);
+            if let Some(event) = self.parse_line(line) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    ///响应结束,NDJSON最后一行可以没有换行,SSE没有空行结尾的事件按规范丢弃
+    pub fn finish(&mut self) -> Vec<StreamEvent> {
+        let rest = std::mem::take(&mut self.buf);
+        let line = String::from_utf8_lossy(&rest);
+        match self.kind {
+            StreamKind::Ndjson => self.parse_line(line.trim_end_matches('\r')).into_iter().collect(),
+            StreamKind::Sse => Vec::new(),
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Option<StreamEvent> {
+        match self.kind {
+            StreamKind::Ndjson => {
+                let line = line.trim();
+                (!line.is_empty()).then(|| StreamEvent {
+                    time: now(),
+                    data: line.to_owned(),
+                    ..Default::default()
+                })
+            }
+            StreamKind::Sse => self.parse_sse_line(line),
+        }
+    }
+
+    fn parse_sse_line(&mut self, line: &str) -> Option<StreamEvent> {
+        if line.is_empty() {
+            return self.dispatch();
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event = value.to_owned(),
+            "data" => self.data.push(value.to_owned()),
+            "id" if !value.contains('\0') => self.last_id = value.to_owned(),
+            "retry" => {
+                if let Ok(retry) = value.parse() {
+                    self.retry = Some(retry);
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<StreamEvent> {
+        let event = std::mem::take(&mut self.event);
+        let retry = self.retry.take();
+        if self.data.is_empty() {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data).join("\n");
+        Some(StreamEvent {
+            time: now(),
+            id: self.last_id.clone(),
+            event: if event.is_empty() {
+                "message".to_owned()
+            } else {
+                event
+            },
+            data,
+            retry,
+        })
+    }
+}
+
+///把分块解码成文本,块尾不完整的UTF-8字符留到下一块
+#[derive(Default)]
+pub struct Utf8Decoder {
+    pending: Vec<u8>,
+}
+
+impl Utf8Decoder {
+    pub fn feed(&mut self, chunk: &[u8]) -> String {
+        self.pending.extend_from_slice(chunk);
+        let mut text = String::new();
+        loop {
+            match std::str::from_utf8(&self.pending) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    self.pending.clear();
+                    return text;
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    text.push_str(&String::from_utf8_lossy(&self.pending[..valid]));
+                    match e.error_len() {
+                        //真正的非法字节,替换后继续
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            self.pending.drain(..valid + len);
+                        }
+                        //字符被截断,等下一块
+                        None => {
+                            self.pending.drain(..valid);
+                            return text;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    ///响应结束,剩下的字节按非法字符处理
+    pub fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        String::from_utf8_lossy(&rest).into_owned()
+    }
+}
+
+fn now() -> String {
+    Local::now().format("%H:%M:%S%.3f").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            StreamKind::detect("text/event-stream; charset=utf-8"),
+            Some(StreamKind::Sse)
+        );
+        assert_eq!(StreamKind::detect("application/x-ndjson"), Some(StreamKind::Ndjson));
+        assert_eq!(StreamKind::detect("application/json"), None);
+    }
+
+    #[test]
+    fn test_sse_split_chunks() {
+        let mut parser = StreamParser::new(StreamKind::Sse);
+        let mut events = parser.feed(b": ping\r\nid: 1\r\nevent: delta\r\ndata: {\"t\":");
+        assert!(events.is_empty());
+        events.extend(parser.feed("\"你".as_bytes()));
+        events.extend(parser.feed("好\"}\r\ndata: second\r\nretry: 3000\r\n\r\ndata:x\n\n".as_bytes()));
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id, "1");
+        assert_eq!(events[0].event, "delta");
+        assert_eq!(events[0].data, "{\"t\":\"你好\"}\nsecond");
+        assert_eq!(events[0].retry, Some(3000));
+        //id保留,event重置
+        assert_eq!(events[1].id, "1");
+        assert_eq!(events[1].event, "message");
+        assert_eq!(events[1].data, "x");
+        assert!(parser.feed(b"data: unterminated").is_empty());
+        assert!(parser.finish().is_empty());
+    }
+
+    #[test]
+    fn test_utf8_decoder() {
+        let bytes = "a你好".as_bytes();
+        let mut decoder = Utf8Decoder::default();
+        let mut text = decoder.feed(&bytes[..2]);
+        assert_eq!(text, "a");
+        text.push_str(&decoder.feed(&bytes[2..5]));
+        text.push_str(&decoder.feed(&bytes[5..]));
+        assert_eq!(text, "a你好");
+        assert_eq!(decoder.feed(b"x\xffy"), "x\u{fffd}y");
+        assert_eq!(decoder.feed(&bytes[1..2]), "");
+        assert_eq!(decoder.finish(), "\u{fffd}");
+    }
+
+    #[test]
+    fn test_ndjson() {
+        let mut parser = StreamParser::new(StreamKind::Ndjson);
+        let mut rows = parser.feed(b"{\"a\":1}\n\n{\"a\"");
+        rows.extend(parser.feed(b":2}"));
+        rows.extend(parser.finish());
+        let data: Vec<&str> = rows.iter().map(|r| r.data.as_str()).collect();
+        assert_eq!(data, vec!["{\"a\":1}", "{\"a\":2}"]);
+    }
+}
@@ -4,8 +4,10 @@ use minijinja::value::Value;
 use minijinja::{context, Environment, Syntax};
 use minijinja::{Error, ErrorKind, State};
 use std::borrow::BorrowMut;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::{Arc, Mutex, RwLock};
 
+use crate::utils::environment::with_env_vars;
 use crate::utils::aes_tool::{
     aes_dec_cbc_string, aes_dec_ctr_string, aes_dec_ecb_string, aes_enc_cbc_string,
     aes_enc_ctr_string, aes_enc_ecb_string,
@@ -22,6 +24,9 @@ const ASCII_HEX: &str = "0123456789ABCDEF";
 const ASCII_NUM: &str = "0123456789";
 pub static TMP_SCOPE_CTX: Lazy<Arc<RwLock<Value>>> =
     Lazy::new(|| Arc::new(RwLock::new(Value::UNDEFINED)));
+///minijinja不能列出全局变量,另存一份给变量查看器用
+static GLOBAL_VARS: Lazy<RwLock<BTreeMap<String, Value>>> =
+    Lazy::new(|| RwLock::new(BTreeMap::new()));
 static TEMP_ENV: Lazy<Arc<Mutex<Environment<'static>>>> = Lazy::new(|| {
     let mut t_env = Environment::new();
 
@@ -145,7 +150,7 @@ fn aes_enc_ctr(
 pub fn rander_template(template: &str) -> Result<String, Error> {
     let mut lock = TEMP_ENV.lock().unwrap();
     let env = lock.borrow_mut();
-    let tmp = TMP_SCOPE_CTX.read().unwrap().clone();
+    let tmp = with_env_vars(TMP_SCOPE_CTX.read().unwrap().clone());
     let result = env
         .render_str(template, tmp)
         .unwrap_or_else(|s| s.to_string());
@@ -155,14 +160,56 @@ pub fn remove_global_value(key: &str) {
     if let Ok(mut env) = TEMP_ENV.lock() {
         env.remove_global(key);
     }
+    if let Ok(mut globals) = GLOBAL_VARS.write() {
+        globals.remove(key);
+    }
 }
 
 pub fn add_global_var(key: String, value: Value) {
+    if let Ok(mut globals) = GLOBAL_VARS.write() {
+        globals.insert(key.clone(), value.clone());
+    }
     if let Ok(mut env) = TEMP_ENV.lock() {
         env.add_global(key, value);
     }
 }
 
+///add_global_var添加的所有变量
+pub fn global_vars() -> Vec<(String, Value)> {
+    GLOBAL_VARS
+        .read()
+        .map(|globals| globals.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+        .unwrap_or_default()
+}
+
+///最近一次run_script得到的脚本变量
+pub fn scope_vars() -> Vec<(String, Value)> {
+    let ctx = TMP_SCOPE_CTX.read().map(|ctx| ctx.clone()).unwrap_or_default();
+    let Ok(keys) = ctx.try_iter() else {
+        return Vec::new();
+    };
+    let mut vars: Vec<(String, Value)> = keys
+        .filter_map(|key| {
+            let value = ctx.get_item(&key).ok()?;
+            Some((key.as_str()?.to_owned(), value))
+        })
+        .collect();
+    vars.sort_by(|a, b| a.0.cmp(&b.0));
+    vars
+}
+
+///修改脚本变量,value为None时删除
+pub fn set_scope_var(key: &str, value: Option<Value>) {
+    let mut vars: HashMap<String, Value> = scope_vars().into_iter().collect();
+    match value {
+        Some(value) => vars.insert(key.to_owned(), value),
+        None => vars.remove(key),
+    };
+    if let Ok(mut ctx) = TMP_SCOPE_CTX.write() {
+        *ctx = Value::from(vars);
+    }
+}
+
 fn fake_name_zh(_state: &State<'_, '_>) -> Result<String, Error> {
     let name = NameZh().fake();
     Ok(name)
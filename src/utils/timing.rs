@@ -0,0 +1,210 @@
+use std::cell::Cell;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use rustls::client::{
+    ClientSessionMemoryCache, ClientSessionStore, Tls12ClientSessionValue, Tls13ClientSessionValue,
+};
+use rustls::pki_types::ServerName;
+use rustls::NamedGroup;
+use tower_layer::Layer;
+use tower_service::Service;
+
+use crate::request_data::Timing;
+
+#[derive(Default)]
+struct Phases {
+    dns: Cell<i64>,
+    connect: Cell<i64>,
+    tls: Cell<i64>,
+    ///TCP连接建立、开始TLS握手的时间
+    tls_start: Cell<Option<Instant>>,
+}
+
+tokio::task_local! {
+    static PHASES: Phases;
+}
+
+fn elapsed_ms(start: Instant) -> i64 {
+    start.elapsed().as_millis() as i64
+}
+
+///执行请求并记录DNS、TCP连接和TLS握手的耗时,连接池里已有连接时不会重新解析
+pub async fn measure<F: Future>(f: F) -> (F::Output, Timing) {
+    PHASES
+        .scope(Phases::default(), async move {
+            let start = Instant::now();
+            let output = f.await;
+            let total = elapsed_ms(start);
+            let timing = PHASES.with(|p| {
+                let dns = p.dns.get();
+                let connect = (p.connect.get() - dns).max(0);
+                Timing {
+                    dns,
+                    connect,
+                    tls: p.tls.get(),
+                    ttfb: total,
+                    download: 0,
+                }
+            });
+            (output, timing)
+        })
+        .await
+}
+
+///记录解析耗时的DNS解析器
+pub struct TimingResolver;
+
+impl Resolve for TimingResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let start = Instant::now();
+            let addrs = tokio::net::lookup_host((name.as_str(), 0)).await?;
+            let addrs: Vec<SocketAddr> = addrs.collect();
+            let _ = PHASES.try_with(|p| p.dns.set(p.dns.get() + elapsed_ms(start)));
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+///包在连接器外面,记录新建连接的耗时(含DNS),有TLS时按握手开始的时间拆成两段
+#[derive(Clone)]
+pub struct TimingLayer;
+
+impl<S> Layer<S> for TimingLayer {
+    type Service = TimingService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TimingService { inner }
+    }
+}
+
+#[derive(Clone)]
+pub struct TimingService<S> {
+    inner: S,
+}
+
+impl<S, R> Service<R> for TimingService<S>
+where
+    S: Service<R>,
+    S::Future: Send + 'static,
+    S::Response: 'static,
+    S::Error: 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<S::Response, S::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        let fut = self.inner.call(req);
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = fut.await;
+            let _ = PHASES.try_with(|p| match p.tls_start.take() {
+                //TLS握手开始前是TCP连接,之后是握手
+                Some(tls_start) => {
+                    let connect = tls_start.duration_since(start).as_millis() as i64;
+                    p.connect.set(p.connect.get() + connect);
+                    p.tls.set(p.tls.get() + elapsed_ms(tls_start));
+                }
+                None => p.connect.set(p.connect.get() + elapsed_ms(start)),
+            });
+            result
+        })
+    }
+}
+
+///TLS会话缓存,构造ClientHello时rustls先取它,借此记下TCP连接完成、握手开始的时间
+#[derive(Debug)]
+pub struct TlsStartStore {
+    inner: ClientSessionMemoryCache,
+}
+
+impl TlsStartStore {
+    pub fn new(size: usize) -> Self {
+        Self {
+            inner: ClientSessionMemoryCache::new(size),
+        }
+    }
+}
+
+impl ClientSessionStore for TlsStartStore {
+    fn set_kx_hint(&self, server_name: ServerName<'static>, group: NamedGroup) {
+        self.inner.set_kx_hint(server_name, group)
+    }
+
+    fn kx_hint(&self, server_name: &ServerName<'_>) -> Option<NamedGroup> {
+        let _ = PHASES.try_with(|p| p.tls_start.set(Some(Instant::now())));
+        self.inner.kx_hint(server_name)
+    }
+
+    fn set_tls12_session(&self, server_name: ServerName<'static>, value: Tls12ClientSessionValue) {
+        self.inner.set_tls12_session(server_name, value)
+    }
+
+    fn tls12_session(&self, server_name: &ServerName<'_>) -> Option<Tls12ClientSessionValue> {
+        self.inner.tls12_session(server_name)
+    }
+
+    fn remove_tls12_session(&self, server_name: &ServerName<'static>) {
+        self.inner.remove_tls12_session(server_name)
+    }
+
+    fn insert_tls13_ticket(
+        &self,
+        server_name: ServerName<'static>,
+        value: Tls13ClientSessionValue,
+    ) {
+        self.inner.insert_tls13_ticket(server_name, value)
+    }
+
+    fn take_tls13_ticket(
+        &self,
+        server_name: &ServerName<'static>,
+    ) -> Option<Tls13ClientSessionValue> {
+        self.inner.take_tls13_ticket(server_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_measure_new_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            if let Ok((mut stream, _)) = listener.accept() {
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf);
+                let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok");
+            }
+        });
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .dns_resolver(Arc::new(TimingResolver))
+            .connector_layer(TimingLayer)
+            .build()
+            .unwrap();
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        let (resp, timing) = rt.block_on(measure(
+            client.get(format!("http://localhost:{}/", port)).send(),
+        ));
+        assert_eq!(resp.unwrap().status(), reqwest::StatusCode::OK);
+        assert!(timing.ttfb >= timing.dns + timing.connect);
+        //明文HTTP没有握手
+        assert_eq!(timing.tls, 0);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Local;
+use futures::{SinkExt, StreamExt};
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client;
+use reqwest_websocket::{Message, RequestBuilderExt};
+use tokio::sync::mpsc::{self, Sender, UnboundedReceiver, UnboundedSender};
+
+use crate::app::{TOKIO_RT, WS_CHANNEL};
+use crate::component::header_ui::SelectKeyValueItem;
+use crate::utils::template::rander_template;
+
+///已连接的WebSocket,删掉发送端就会关闭连接
+static WS_CONNECTIONS: Lazy<Mutex<HashMap<u64, UnboundedSender<Message>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum WsMessageKind {
+    #[default]
+    Text,
+    Json,
+    ///内容是base64编码的字节
+    Binary,
+}
+
+impl WsMessageKind {
+    pub const ALL: [WsMessageKind; 3] = [Self::Text, Self::Json, Self::Binary];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            WsMessageKind::Text => "文本",
+            WsMessageKind::Json => "JSON",
+            WsMessageKind::Binary => "二进制(base64)",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct WsMessage {
+    pub name: String,
+    pub kind: WsMessageKind,
+    pub content: String,
+}
+
+impl WsMessage {
+    ///渲染模板,转成要发送的消息
+    pub fn render(&self) -> Result<Message, String> {
+        let content = rander_template(&self.content).map_err(|e| e.to_string())?;
+        match self.kind {
+            WsMessageKind::Text => Ok(Message::Text(content)),
+            WsMessageKind::Json => {
+                let value = json5::from_str::<serde_json::Value>(&content)
+                    .map_err(|e| format!("JSON格式错误:{}", e))?;
+                Ok(Message::Text(value.to_string()))
+            }
+            WsMessageKind::Binary => STANDARD
+                .decode(content.trim())
+                .map(Message::Binary)
+                .map_err(|e| format!("base64格式错误:{}", e)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WsDirection {
+    Incoming,
+    Outgoing,
+    ///连接、断开和错误
+    Info,
+}
+
+#[derive(Debug, Clone)]
+pub struct WsLogItem {
+    pub time: String,
+    pub direction: WsDirection,
+    pub kind: WsMessageKind,
+    pub content: String,
+}
+
+impl WsLogItem {
+    pub fn new(direction: WsDirection, kind: WsMessageKind, content: String) -> Self {
+        Self {
+            time: Local::now().format("%H:%M:%S%.3f").to_string(),
+            direction,
+            kind,
+            content,
+        }
+    }
+
+    fn from_message(direction: WsDirection, msg: &Message) -> Option<Self> {
+        if let Message::Text(text) = msg {
+            Some(Self::new(direction, WsMessageKind::Text, text.to_string()))
+        } else if let Message::Binary(bytes) = msg {
+            Some(Self::new(
+                direction,
+                WsMessageKind::Binary,
+                STANDARD.encode(&bytes[..]),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WsEvent {
+    Connected,
+    Received(WsLogItem),
+    Error(String),
+    Closed,
+}
+
+///WebSocket节点,和RequestData一样保存在工作区里
+#[derive(Debug, Default, Clone, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct WebSocketData {
+    pub remark: String,
+    pub url: String,
+    pub headers: Vec<SelectKeyValueItem>,
+    ///正在编辑的消息
+    pub draft: WsMessage,
+    pub saved: Vec<WsMessage>,
+    ///收到消息时执行,消息在_msg里
+    pub script: String,
+    #[serde(skip)]
+    pub log: Vec<WsLogItem>,
+}
+
+impl WebSocketData {
+    pub fn render_headers(&self) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        for item in self
+            .headers
+            .iter()
+            .filter(|h| h.selected && !h.key.is_empty())
+        {
+            let value = rander_template(&item.value).map_err(|e| e.to_string())?;
+            let name = HeaderName::from_bytes(item.key.as_bytes()).map_err(|e| e.to_string())?;
+            let value = HeaderValue::from_str(&value).map_err(|e| e.to_string())?;
+            headers.insert(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+pub fn is_connected(id: u64) -> bool {
+    WS_CONNECTIONS
+        .lock()
+        .map(|conns| conns.contains_key(&id))
+        .unwrap_or(false)
+}
+
+///建立连接,同一个节点之前的连接会被关闭
+pub fn connect(id: u64, client: Client, data: &WebSocketData) -> Result<(), String> {
+    let url = rander_template(&data.url).map_err(|e| e.to_string())?;
+    let headers = data.render_headers()?;
+    let (tx, rx) = mpsc::unbounded_channel();
+    if let Ok(mut conns) = WS_CONNECTIONS.lock() {
+        conns.insert(id, tx.clone());
+    }
+    let events = unsafe { WS_CHANNEL.0.clone() };
+    TOKIO_RT.spawn(async move {
+        run_connection(id, client, url, headers, rx, events).await;
+        if let Ok(mut conns) = WS_CONNECTIONS.lock() {
+            if conns.get(&id).is_some_and(|c| c.same_channel(&tx)) {
+                conns.remove(&id);
+            }
+        }
+    });
+    Ok(())
+}
+
+pub fn disconnect(id: u64) {
+    if let Ok(mut conns) = WS_CONNECTIONS.lock() {
+        conns.remove(&id);
+    }
+}
+
+///发送消息,返回用于显示的日志
+pub fn send(id: u64, msg: &WsMessage) -> Result<WsLogItem, String> {
+    let message = msg.render()?;
+    let log = WsLogItem::from_message(WsDirection::Outgoing, &message)
+        .map(|mut log| {
+            log.kind = msg.kind;
+            log
+        })
+        .unwrap_or_else(|| WsLogItem::new(WsDirection::Outgoing, msg.kind, String::new()));
+    let conns = WS_CONNECTIONS.lock().map_err(|e| e.to_string())?;
+    let sender = conns.get(&id).ok_or_else(|| "未连接".to_owned())?;
+    sender.send(message).map_err(|_| "连接已关闭".to_owned())?;
+    Ok(log)
+}
+
+pub async fn run_connection(
+    id: u64,
+    client: Client,
+    url: String,
+    headers: HeaderMap,
+    mut outgoing: UnboundedReceiver<Message>,
+    events: Sender<(u64, WsEvent)>,
+) {
+    let upgrade = client.get(&url).headers(headers).upgrade().send().await;
+    let websocket = match upgrade {
+        Ok(resp) => resp.into_websocket().await,
+        Err(e) => Err(e),
+    };
+    let websocket = match websocket {
+        Ok(ws) => ws,
+        Err(e) => {
+            let _ = events.send((id, WsEvent::Error(e.to_string()))).await;
+            let _ = events.send((id, WsEvent::Closed)).await;
+            return;
+        }
+    };
+    let _ = events.send((id, WsEvent::Connected)).await;
+    let (mut sink, mut stream) = websocket.split();
+    loop {
+        tokio::select! {
+            out = outgoing.recv() => match out {
+                Some(msg) => {
+                    if let Err(e) = sink.send(msg).await {
+                        let _ = events.send((id, WsEvent::Error(e.to_string()))).await;
+                        break;
+                    }
+                }
+                None => {
+                    let _ = sink.close().await;
+                    break;
+                }
+            },
+            incoming = stream.next() => match incoming {
+                Some(Ok(msg)) => {
+                    if let Some(log) = WsLogItem::from_message(WsDirection::Incoming, &msg) {
+                        let _ = events.send((id, WsEvent::Received(log))).await;
+                    }
+                }
+                Some(Err(e)) => {
+                    let _ = events.send((id, WsEvent::Error(e.to_string()))).await;
+                    break;
+                }
+                None => break,
+            },
+        }
+    }
+    let _ = events.send((id, WsEvent::Closed)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    async fn echo_server() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let Ok(mut ws) = tokio_tungstenite::accept_async(stream).await else {
+                        return;
+                    };
+                    while let Some(Ok(msg)) = ws.next().await {
+                        if msg.is_text() || msg.is_binary() {
+                            let _ = ws.send(msg).await;
+                        }
+                    }
+                });
+            }
+        });
+        format!("ws://{}/", addr)
+    }
+
+    #[test]
+    fn test_render_message() {
+        let msg = WsMessage {
+            kind: WsMessageKind::Json,
+            content: "{a:1,}".to_owned(),
+            ..Default::default()
+        };
+        assert!(matches!(msg.render().unwrap(), Message::Text(t) if t == r#"{"a":1}"#));
+        let msg = WsMessage {
+            kind: WsMessageKind::Binary,
+            content: "AQID".to_owned(),
+            ..Default::default()
+        };
+        assert!(matches!(msg.render().unwrap(), Message::Binary(b) if b[..] == [1, 2, 3]));
+    }
+
+    #[test]
+    fn test_echo() {
+        let rt = tokio::runtime::Runtime::new().unwrap();
+        rt.block_on(async {
+            let url = echo_server().await;
+            let (tx, rx) = mpsc::unbounded_channel();
+            let (events_tx, mut events) = mpsc::channel(10);
+            let conn = tokio::spawn(run_connection(
+                1,
+                Client::new(),
+                url,
+                HeaderMap::new(),
+                rx,
+                events_tx,
+            ));
+            assert!(matches!(events.recv().await, Some((1, WsEvent::Connected))));
+            tx.send(Message::Text("hello".into())).unwrap();
+            tx.send(Message::Binary(vec![1u8, 2, 3])).unwrap();
+            let Some((_, WsEvent::Received(text))) = events.recv().await else {
+                panic!("没有收到文本消息");
+            };
+            assert_eq!(text.content, "hello");
+            assert_eq!(text.direction, WsDirection::Incoming);
+            let Some((_, WsEvent::Received(binary))) = events.recv().await else {
+                panic!("没有收到二进制消息");
+            };
+            assert_eq!(binary.kind, WsMessageKind::Binary);
+            assert_eq!(binary.content, "AQID");
+            drop(tx);
+            assert!(matches!(events.recv().await, Some((1, WsEvent::Closed))));
+            conn.await.unwrap();
+        });
+    }
+}